@@ -0,0 +1,118 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+use gtk::{
+    ButtonExt,
+    EntryExt,
+    Inhibit,
+    LabelExt,
+    OrientableExt,
+    ToggleButtonExt,
+    WidgetExt,
+};
+use gtk::Orientation::Vertical;
+use relm::Widget;
+use relm_attributes::widget;
+
+use self::Msg::*;
+
+pub struct Model {
+    name: String,
+    shout: bool,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Clear,
+    Quit,
+}
+
+#[widget]
+impl Widget for Win {
+    fn model() -> Model {
+        Model {
+            name: String::new(),
+            shout: false,
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            // The entry and the check button are updated as well.
+            Clear => {
+                self.model.name = String::new();
+                self.model.shout = false;
+            },
+            Quit => relm::quit(0),
+        }
+    }
+
+    view! {
+        gtk::Window {
+            gtk::Box {
+                orientation: Vertical,
+                // The model is updated when the user types in the entry.
+                #[name="entry"]
+                gtk::Entry {
+                    text: bind self.model.name,
+                },
+                gtk::CheckButton {
+                    active: bind self.model.shout,
+                    label: "Shout",
+                },
+                gtk::Label {
+                    text: &greeting(&self.model.name, self.model.shout),
+                },
+                gtk::Button {
+                    clicked => Clear,
+                    label: "Clear",
+                    // Bound to the property of another widget, without going through update().
+                    sensitive: bind entry.text_length with |length: u32| length > 0,
+                },
+            },
+            delete_event(_, _) => (Quit, Inhibit(false)),
+        }
+    }
+}
+
+fn greeting(name: &str, shout: bool) -> String {
+    let greeting = format!("Hello, {}!", name);
+    if shout {
+        greeting.to_uppercase()
+    }
+    else {
+        greeting
+    }
+}
+
+fn main() {
+    Win::run(()).unwrap();
+}
@@ -67,7 +67,7 @@ impl Widget for Win {
         match event {
             Decrement => self.model.counter -= 1,
             Increment => self.model.counter += 1,
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
@@ -61,7 +61,7 @@ relm_widget! {
             match event {
                 Msg::Decrement => self.model.counter -= 1,
                 Msg::Increment => self.model.counter += 1,
-                Msg::Quit => gtk::main_quit(),
+                Msg::Quit => relm::quit(0),
             }
         }
 
@@ -84,7 +84,7 @@ impl Update for Win {
                 self.model.counter += 1;
                 label.set_text(&self.model.counter.to_string());
             },
-            Msg::Quit => gtk::main_quit(),
+            Msg::Quit => relm::quit(0),
         }
     }
 }
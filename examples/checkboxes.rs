@@ -136,7 +136,7 @@ impl Update for Win {
 
     fn update(&mut self, event: Msg) {
         match event {
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
             MinusToggle => {
                 if self.minus_button.widget().get_active() {
                     self.plus_button.emit(Uncheck);
@@ -119,7 +119,7 @@ impl Widget for Win {
     fn update(&mut self, event: Msg) {
         match event {
             SelectionChanged(_selection) => println!("selection changed"),
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
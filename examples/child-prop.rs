@@ -111,7 +111,7 @@ impl Update for Win {
 
     fn update(&mut self, event: Msg) {
         match event {
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 }
@@ -22,7 +22,6 @@
 #![feature(proc_macro, unboxed_closures)]
 
 extern crate chrono;
-extern crate futures_glib;
 extern crate gtk;
 #[macro_use]
 extern crate relm;
@@ -33,13 +32,12 @@ extern crate relm_derive;
 use std::time::Duration;
 
 use chrono::{DateTime, Local};
-use futures_glib::Interval;
 use gtk::{
     Inhibit,
     LabelExt,
     WidgetExt,
 };
-use relm::{Relm, Widget};
+use relm::{Interval, Relm, Widget};
 use relm_attributes::widget;
 
 use self::Msg::*;
@@ -70,7 +68,7 @@ impl Widget for Win {
     fn update(&mut self, event: Msg) {
         match event {
             Tick(()) => self.model.time = Local::now(),
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
@@ -22,7 +22,6 @@
 #![feature(fn_traits, unboxed_closures)]
 
 extern crate chrono;
-extern crate futures_glib;
 extern crate gtk;
 #[macro_use]
 extern crate relm;
@@ -32,7 +31,6 @@ extern crate relm_derive;
 use std::time::Duration;
 
 use chrono::Local;
-use futures_glib::Interval;
 use gtk::{
     ContainerExt,
     Inhibit,
@@ -42,7 +40,7 @@ use gtk::{
     Window,
     WindowType,
 };
-use relm::{Relm, Update, Widget};
+use relm::{Interval, Relm, Update, Widget};
 
 use self::Msg::*;
 
@@ -77,7 +75,7 @@ impl Update for Win {
                 let time = Local::now();
                 self.label.set_text(&format!("{}", time.format("%H:%M:%S")));
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 }
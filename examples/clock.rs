@@ -20,7 +20,6 @@
  */
 
 extern crate chrono;
-extern crate futures_glib;
 extern crate gtk;
 #[macro_use]
 extern crate relm;
@@ -30,7 +29,6 @@ extern crate relm_derive;
 use std::time::Duration;
 
 use chrono::Local;
-use futures_glib::Interval;
 use gtk::{
     ContainerExt,
     Inhibit,
@@ -40,7 +38,7 @@ use gtk::{
     Window,
     WindowType,
 };
-use relm::{Relm, Update, Widget};
+use relm::{Interval, Relm, Update, Widget};
 
 use self::Msg::*;
 
@@ -75,7 +73,7 @@ impl Update for Win {
                 let time = Local::now();
                 self.label.set_text(&format!("{}", time.format("%H:%M:%S")));
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 }
@@ -152,7 +152,7 @@ impl Widget for Win {
                 println!("{}", text);
                 self.model.counter += 1
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
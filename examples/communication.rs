@@ -219,7 +219,7 @@ impl Update for Win {
                 self.model.counter += 1;
                 self.label.set_text(&self.model.counter.to_string());
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 }
@@ -0,0 +1,129 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+use gtk::{
+    ButtonExt,
+    Inhibit,
+    LabelExt,
+    OrientableExt,
+    SpinnerExt,
+    WidgetExt,
+};
+use gtk::Orientation::Vertical;
+use relm::Widget;
+use relm_attributes::widget;
+
+use self::Msg::*;
+use self::Status::*;
+
+#[derive(Clone, Copy)]
+pub enum Status {
+    Idle,
+    Loading,
+    Loaded(u32),
+}
+
+pub struct Model {
+    logged_in: bool,
+    status: Status,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Load,
+    LogIn,
+    LogOut,
+    Quit,
+}
+
+#[widget]
+impl Widget for Win {
+    fn model() -> Model {
+        Model {
+            logged_in: false,
+            status: Idle,
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Load => {
+                self.model.status =
+                    match self.model.status {
+                        Idle => Loading,
+                        Loading => Loaded(42),
+                        Loaded(_) => Idle,
+                    };
+            },
+            LogIn => self.model.logged_in = true,
+            LogOut => self.model.logged_in = false,
+            Quit => relm::quit(0),
+        }
+    }
+
+    view! {
+        gtk::Window {
+            gtk::Box {
+                orientation: Vertical,
+                gtk::Button {
+                    clicked => Load,
+                    label: "Next status",
+                },
+                // Only the widget of the active branch exists.
+                if self.model.logged_in {
+                    gtk::Button {
+                        clicked => LogOut,
+                        label: "Log out",
+                    }
+                }
+                else {
+                    gtk::Button {
+                        clicked => LogIn,
+                        label: "Log in",
+                    }
+                },
+                match self.model.status {
+                    Idle => {},
+                    Loading => gtk::Spinner {
+                        active: true,
+                    },
+                    Loaded(count) => gtk::Label {
+                        text: &format!("{} items loaded", count),
+                    },
+                },
+            },
+            delete_event(_, _) => (Quit, Inhibit(false)),
+        }
+    }
+}
+
+fn main() {
+    Win::run(()).unwrap();
+}
@@ -77,7 +77,7 @@ impl Widget for Win {
             Release => {
                 println!("Release");
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
@@ -80,7 +80,7 @@ impl Update for Win {
             Release => {
                 println!("Release");
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 }
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate gtk;
+#[macro_use]
+extern crate relm;
+#[macro_use]
+extern crate relm_derive;
+
+use gtk::{
+    Button,
+    ButtonExt,
+    ContainerExt,
+    Label,
+    LabelExt,
+    WidgetExt,
+    Window,
+    WindowExt,
+    WindowType,
+};
+use gtk::Orientation::Vertical;
+use relm::{OpenWindow, Relm, Update, Widget};
+
+use self::Msg::*;
+use self::PrefsMsg::*;
+
+#[derive(Msg)]
+enum PrefsMsg {
+    SetCount(usize),
+}
+
+struct Prefs {
+    label: Label,
+    window: Window,
+}
+
+impl Update for Prefs {
+    type Model = usize;
+    type ModelParam = usize;
+    type Msg = PrefsMsg;
+
+    fn model(_: &Relm<Self>, number: usize) -> usize {
+        number
+    }
+
+    fn update(&mut self, event: PrefsMsg) {
+        match event {
+            SetCount(count) => self.label.set_text(&format!("{} windows are open", count)),
+        }
+    }
+}
+
+impl Widget for Prefs {
+    type Root = Window;
+
+    fn root(&self) -> Self::Root {
+        self.window.clone()
+    }
+
+    fn view(_relm: &Relm<Self>, number: usize) -> Self {
+        let window = Window::new(WindowType::Toplevel);
+        window.set_title(&format!("Preferences #{}", number));
+        let label = Label::new(None);
+        window.add(&label);
+        window.show_all();
+
+        Prefs {
+            label,
+            window,
+        }
+    }
+}
+
+#[derive(Msg)]
+enum Msg {
+    Open,
+}
+
+struct Win {
+    model: usize,
+    relm: Relm<Win>,
+    window: Window,
+}
+
+impl Update for Win {
+    type Model = usize;
+    type ModelParam = ();
+    type Msg = Msg;
+
+    fn model(_: &Relm<Self>, _: ()) -> usize {
+        0
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Open => {
+                self.model += 1;
+                // The window owns its component, so there is no need to store the handle.
+                let _ = self.relm.open_window::<Prefs>(self.model);
+                let windows = relm::windows::<Prefs>();
+                for window in &windows {
+                    window.emit(SetCount(windows.len()));
+                }
+            },
+        }
+    }
+}
+
+impl Widget for Win {
+    type Root = Window;
+
+    fn root(&self) -> Self::Root {
+        self.window.clone()
+    }
+
+    fn view(relm: &Relm<Self>, model: usize) -> Self {
+        let window = Window::new(WindowType::Toplevel);
+        let vbox = gtk::Box::new(Vertical, 0);
+        let open_button = Button::new_with_label("Open preferences");
+        vbox.add(&open_button);
+        window.add(&vbox);
+        window.show_all();
+
+        connect!(relm, open_button, connect_clicked(_), Open);
+
+        Win {
+            model,
+            relm: relm.clone(),
+            window,
+        }
+    }
+}
+
+fn main() {
+    // Quit when the main window and all the preferences windows are closed.
+    relm::quit_on_last_window(true);
+    Win::run(()).unwrap();
+}
@@ -66,7 +66,7 @@ impl Widget for Win {
                 self.model.content = text.chars().rev().collect();
                 self.model.content += &format!(" ({})", len);
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
@@ -104,7 +104,7 @@ impl Widget for Win {
     fn update(&mut self, event: Msg) {
         match event {
             Reset => self.model.text = String::new(),
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
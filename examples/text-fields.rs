@@ -76,7 +76,7 @@ impl Update for Win {
                 self.model.content = self.input.get_text().unwrap().chars().rev().collect();
                 self.label.set_text(&self.model.content);
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 }
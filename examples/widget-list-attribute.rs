@@ -0,0 +1,129 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+use gtk::{
+    ButtonExt,
+    Inhibit,
+    OrientableExt,
+    WidgetExt,
+};
+use gtk::Orientation::Vertical;
+use relm::Widget;
+use relm_attributes::widget;
+
+use self::Msg::*;
+
+#[derive(Clone)]
+pub struct Item {
+    id: u32,
+    text: String,
+}
+
+pub struct Model {
+    items: Vec<Item>,
+    next_id: u32,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Add,
+    Quit,
+    Remove(u32),
+    RemoveFirst,
+    Reverse,
+}
+
+#[widget]
+impl Widget for Win {
+    fn model() -> Model {
+        Model {
+            items: vec![],
+            next_id: 0,
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Add => {
+                let mut items = self.model.items.clone();
+                items.push(Item {
+                    id: self.model.next_id,
+                    text: format!("Item {}", self.model.next_id),
+                });
+                self.model.next_id += 1;
+                // The buttons are created, destroyed and reordered when the items are assigned.
+                self.model.items = items;
+            },
+            Quit => relm::quit(0),
+            Remove(id) => self.model.items = self.model.items.iter().filter(|item| item.id != id).cloned().collect(),
+            RemoveFirst => {
+                if !self.model.items.is_empty() {
+                    self.model.items = self.model.items[1..].to_vec();
+                }
+            },
+            Reverse => self.model.items = self.model.items.iter().rev().cloned().collect(),
+        }
+    }
+
+    view! {
+        gtk::Window {
+            gtk::Box {
+                orientation: Vertical,
+                gtk::Button {
+                    clicked => Add,
+                    label: "Add",
+                },
+                gtk::Button {
+                    clicked => RemoveFirst,
+                    label: "Remove first",
+                },
+                gtk::Button {
+                    clicked => Reverse,
+                    label: "Reverse",
+                },
+                gtk::Box {
+                    orientation: Vertical,
+                    // Create a button for each item, identified by the id of the item.
+                    for item in self.model.items, key: item.id {
+                        gtk::Button {
+                            clicked => Remove(item.id),
+                            label: &item.text,
+                        }
+                    },
+                },
+            },
+            delete_event(_, _) => (Quit, Inhibit(false)),
+        }
+    }
+}
+
+fn main() {
+    Win::run(()).unwrap();
+}
@@ -37,7 +37,7 @@ use gtk::{
     WindowType,
 };
 use gtk::Orientation::{Horizontal, Vertical};
-use relm::{Component, ContainerWidget, Relm, Update, Widget};
+use relm::{Components, Relm, Update, Widget};
 
 use self::CounterMsg::*;
 use self::Msg::*;
@@ -125,7 +125,7 @@ enum Msg {
 }
 
 struct Win {
-    counters: Vec<Component<Counter>>,
+    counters: Components<usize, Counter>,
     hbox: gtk::Box,
     relm: Relm<Win>,
     window: Window,
@@ -143,13 +143,13 @@ impl Update for Win {
     fn update(&mut self, event: Msg) {
         match event {
             Add => {
-                let widget = self.hbox.add_widget::<Counter, _>(&self.relm, ());
-                self.counters.push(widget);
+                let key = self.counters.len();
+                let _ = self.counters.push(&self.hbox, &self.relm, key, ());
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
             Remove => {
-                if let Some(counter) = self.counters.pop() {
-                    self.hbox.remove_widget(counter);
+                if let Some(key) = self.counters.len().checked_sub(1) {
+                    let _ = self.counters.remove(&key);
                 }
             },
         }
@@ -185,7 +185,7 @@ impl Widget for Win {
         connect!(relm, window, connect_delete_event(_, _), return (Some(Quit), Inhibit(false)));
 
         Win {
-            counters: vec![],
+            counters: Components::new(),
             hbox: hbox,
             relm: relm.clone(),
             window: window,
@@ -37,11 +37,11 @@ use std::process::{Command, Stdio};
 use env_logger::LogBuilder;
 use log::LogRecord;
 use proc_macro::TokenStream;
-use relm_gen_widget::gen_widget;
+use relm_gen_widget::{WidgetOptions, gen_widget_with_options};
 use syn::parse_item;
 
 #[proc_macro_attribute]
-pub fn widget(_attributes: TokenStream, input: TokenStream) -> TokenStream {
+pub fn widget(attributes: TokenStream, input: TokenStream) -> TokenStream {
     let format = |record: &LogRecord| {
         record.args().to_string()
     };
@@ -57,7 +57,11 @@ pub fn widget(_attributes: TokenStream, input: TokenStream) -> TokenStream {
     let tokens = quote! {
         #ast
     };
-    let expanded = gen_widget(tokens);
+    let expanded =
+        match WidgetOptions::parse(&attributes.to_string()) {
+            Ok(options) => gen_widget_with_options(tokens, options),
+            Err(error) => error.to_compile_error(),
+        };
     log_formatted(expanded.parse::<String>().unwrap());
     expanded.parse().unwrap()
 }
@@ -43,6 +43,21 @@ use std::rc::Rc;
 use futures::{Async, Poll, Stream};
 use futures::task::{self, Task};
 
+/// Maximum number of messages emitted recursively on a stream in debug builds.
+/// Beyond that, the observers of the stream are most likely emitting to each other in a cycle.
+const MAX_EMIT_DEPTH: usize = 100;
+
+/// A guard leaving an emit() of the stream when it goes out of scope, even if an observer panics.
+struct EmitGuard<MSG> {
+    stream: Rc<RefCell<_EventStream<MSG>>>,
+}
+
+impl<MSG> Drop for EmitGuard<MSG> {
+    fn drop(&mut self) {
+        self.stream.borrow_mut().emit_depth -= 1;
+    }
+}
+
 /// A lock is used to temporarily stop emitting messages.
 #[must_use]
 pub struct Lock<MSG> {
@@ -56,6 +71,7 @@ impl<MSG> Drop for Lock<MSG> {
 }
 
 struct _EventStream<MSG> {
+    emit_depth: usize,
     events: VecDeque<MSG>,
     locked: bool,
     observers: Vec<Rc<Fn(&MSG)>>,
@@ -81,6 +97,7 @@ impl<MSG> EventStream<MSG> {
     pub fn new() -> Self {
         EventStream {
             stream: Rc::new(RefCell::new(_EventStream {
+                emit_depth: 0,
                 events: VecDeque::new(),
                 locked: false,
                 observers: vec![],
@@ -107,16 +124,39 @@ impl<MSG> EventStream<MSG> {
                 task.notify();
             }
 
-            let len = self.stream.borrow().observers.len();
-            for i in 0..len {
-                let observer = self.stream.borrow().observers[i].clone();
-                observer(&event);
+            {
+                let _guard = self.enter_emit();
+                let len = self.stream.borrow().observers.len();
+                for i in 0..len {
+                    let observer = self.stream.borrow().observers[i].clone();
+                    observer(&event);
+                }
             }
 
             self.stream.borrow_mut().events.push_back(event);
         }
     }
 
+    /// Guard against the event cycles in debug builds, where an observer emits a message which
+    /// ends up emitting again on this stream, instead of overflowing the stack.
+    ///
+    /// Only the synchronous cycles are detected, like the observers added by connect! emitting
+    /// to each other: a message handled later by update() is not nested in emit(), so a cycle
+    /// going through update() is not counted and does not overflow the stack either.
+    fn enter_emit(&self) -> EmitGuard<MSG> {
+        self.stream.borrow_mut().emit_depth += 1;
+        let guard = EmitGuard {
+            stream: self.stream.clone(),
+        };
+        // The guard is dropped while unwinding, so that the depth is still right if the panic is caught.
+        if cfg!(debug_assertions) && self.stream.borrow().emit_depth > MAX_EMIT_DEPTH {
+            panic!("Event cycle: more than {} messages emitted recursively on the same stream, check the events \
+                    connecting this widget to its children and parent (the #[widget] attribute warns about the \
+                    cycles it can find)", MAX_EMIT_DEPTH);
+        }
+        guard
+    }
+
     fn get_event(&self) -> Option<MSG> {
         self.stream.borrow_mut().events.pop_front()
     }
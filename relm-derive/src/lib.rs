@@ -13,11 +13,12 @@ extern crate syn;
 use proc_macro::TokenStream;
 
 use quote::Tokens;
-use relm_gen_widget::gen_widget;
+use relm_gen_widget::{WidgetOptions, gen_widget_with_options};
 use relm_derive_common::{impl_msg, impl_simple_msg};
 use syn::{
     Ident,
     Item,
+    Ty,
     VariantData,
     parse_item,
     parse_macro_input,
@@ -52,14 +53,22 @@ pub fn widget(input: TokenStream) -> TokenStream {
 
 fn impl_widget(ast: &Item) -> Tokens {
     if let Struct(VariantData::Struct(ref fields), _) = ast.node {
+        let mut options = WidgetOptions::new();
+        for field in fields {
+            if field.ident == Some(Ident::new("options")) {
+                if let Some(tokens) = macro_tokens(&field.ty) {
+                    options =
+                        match WidgetOptions::parse(tokens.as_str()) {
+                            Ok(options) => options,
+                            Err(error) => return error.to_compile_error(),
+                        };
+                }
+            }
+        }
         for field in fields {
             if field.ident == Some(Ident::new("widget")) {
-                if let Mac(ref mac) = field.ty {
-                    if let Delimited(syn::Delimited { ref tts, .. }) = mac.tts[0] {
-                        let mut tokens = Tokens::new();
-                        tokens.append_all(tts);
-                        return gen_widget(tokens);
-                    }
+                if let Some(tokens) = macro_tokens(&field.ty) {
+                    return gen_widget_with_options(tokens, options);
                 }
             }
         }
@@ -67,3 +76,14 @@ fn impl_widget(ast: &Item) -> Tokens {
 
     panic!("Expecting `widget` field.");
 }
+
+fn macro_tokens(ty: &Ty) -> Option<Tokens> {
+    if let Mac(ref mac) = *ty {
+        if let Delimited(syn::Delimited { ref tts, .. }) = mac.tts[0] {
+            let mut tokens = Tokens::new();
+            tokens.append_all(tts);
+            return Some(tokens);
+        }
+    }
+    None
+}
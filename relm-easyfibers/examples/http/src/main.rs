@@ -126,7 +126,7 @@ impl Widget for Win {
                 let future = relm.connect(http_future, ImageChunk, hyper_error_to_msg);
                 relm.connect_exec_ignore_err(future, DownloadCompleted);
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
@@ -112,7 +112,7 @@ impl Widget for Win {
                 model.message = String::new();
                 self.entry.grab_focus();
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
@@ -127,7 +127,7 @@ impl Widget for Win {
                 self.entry.set_text("");
                 self.entry.grab_focus();
             },
-            Quit => gtk::main_quit(),
+            Quit => relm::quit(0),
         }
     }
 
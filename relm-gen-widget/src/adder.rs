@@ -20,32 +20,33 @@
  */
 
 use std::boxed;
+use std::collections::{HashMap, HashSet};
 
 use quote::Tokens;
 use syn;
-use syn::{Expr, ExprKind, Ident, Path, Stmt, parse_expr};
-use syn::ExprKind::{Assign, AssignOp, Block, Field};
-use syn::fold::{Folder, noop_fold_expr};
-use syn::Stmt::Semi;
+use syn::{Expr, ExprKind, Ident, Stmt, parse_expr};
+use syn::ExprKind::{AddrOf, Assign, AssignOp, Block, Call, Index, MethodCall, Paren};
+use syn::fold::{Folder, noop_fold_block, noop_fold_expr};
+use syn::Mutability::Mutable;
+use syn::Stmt::{Local, Semi};
 use syn::Unsafety::Normal;
 
-use super::{MsgModelMap, PropertyModelMap};
+use super::{DynamicModelMap, MsgModelMap, PropertyModelMap};
+use walker::{ModelPath, model_path, paths_overlap};
+
+/// Methods of the standard collections and `String` which mutate their receiver.
+/// The other method calls on a model variable are not considered as mutations.
+const MUTATING_METHODS: &[&str] = &[
+    "append", "clear", "dedup", "extend", "insert", "insert_str", "pop", "pop_back", "pop_front", "push",
+    "push_back", "push_front", "push_str", "remove", "resize", "retain", "reverse", "sort", "sort_by",
+    "sort_by_key", "split_off", "swap", "swap_remove", "truncate",
+];
 
 macro_rules! fold_assign {
     ($_self:expr, $lhs:expr, $new_assign:expr) => {{
         let mut statements = vec![];
-        let new_statements =
-            if let Field(ref field_expr, ref ident) = $lhs.node {
-                if is_model_path(field_expr) {
-                    Some(create_stmts(ident, $_self.property_map, $_self.msg_map))
-                }
-                else {
-                    None
-                }
-            }
-            else {
-                None
-            };
+        let new_statements = modified_path(&$lhs)
+            .map(|path| create_stmts(&path, $_self.property_map, $_self.msg_map, $_self.dynamic_map));
         let mut new_assign = $new_assign;
         if let Some(mut stmts) = new_statements {
             let new_expr = Expr { node: new_assign, attrs: vec![] };
@@ -58,21 +59,100 @@ macro_rules! fold_assign {
 }
 
 pub struct Adder<'a> {
+    dynamic_map: &'a DynamicModelMap,
     msg_map: &'a MsgModelMap,
     property_map: &'a PropertyModelMap,
 }
 
 impl<'a> Adder<'a> {
-    pub fn new(property_map: &'a PropertyModelMap, msg_map: &'a MsgModelMap) -> Self {
+    pub fn new(property_map: &'a PropertyModelMap, msg_map: &'a MsgModelMap, dynamic_map: &'a DynamicModelMap) -> Self {
         Adder {
+            dynamic_map,
             msg_map,
             property_map,
         }
     }
+
+    fn create_stmts(&self, paths: &[ModelPath]) -> Vec<Stmt> {
+        let mut stmts = vec![];
+        for path in paths {
+            stmts.append(&mut create_stmts(path, self.property_map, self.msg_map, self.dynamic_map));
+        }
+        stmts
+    }
+
+    /*
+     * Wrap the statements following a local mutable borrow of a model variable, like
+     * let text = &mut self.model.text;
+     * in a block, so that the borrow ends before the set_property() calls added after this block.
+     */
+    fn wrap_mutable_borrows(&self, mut stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let position = stmts.iter().position(|stmt| mutable_borrow_in_local(stmt).is_some());
+        let index =
+            match position {
+                Some(index) => index,
+                None => return stmts,
+            };
+        let mut rest = stmts.split_off(index);
+        let borrow = rest.remove(0);
+        let path = mutable_borrow_in_local(&borrow).expect("mutable borrow");
+        let mut rest = self.wrap_mutable_borrows(rest);
+        let has_value =
+            match rest.last() {
+                Some(&Stmt::Expr(_)) => true,
+                _ => false,
+            };
+        rest.insert(0, borrow);
+        let new_stmts = self.create_stmts(&[path]);
+        let block =
+            if has_value {
+                quote! {{
+                    let __relm_value = { #(#rest)* };
+                    #(#new_stmts)*
+                    __relm_value
+                }}
+            }
+            else {
+                quote! {{
+                    { #(#rest)* }
+                    #(#new_stmts)*
+                }}
+            };
+        stmts.append(&mut tokens_to_stmts(block));
+        stmts
+    }
 }
 
 impl<'a> Folder for Adder<'a> {
+    fn fold_block(&mut self, block: syn::Block) -> syn::Block {
+        let block = noop_fold_block(self, block);
+        syn::Block {
+            stmts: self.wrap_mutable_borrows(block.stmts),
+        }
+    }
+
     fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let is_call =
+            match expr.node {
+                Call(..) | MethodCall(..) => true,
+                _ => false,
+            };
+        if is_call {
+            let paths = mutated_model_variables(&expr);
+            let new_expr = noop_fold_expr(self, expr);
+            if paths.is_empty() {
+                return new_expr;
+            }
+            // Keep the value returned by the call.
+            let stmts = self.create_stmts(&paths);
+            let block = quote! {{
+                let __relm_value = #new_expr;
+                #(#stmts)*
+                __relm_value
+            }};
+            return parse_expr(&block.parse::<String>().expect("parse::<String>() in fold_expr"))
+                .expect("parse_expr() in fold_expr");
+        }
         let lhs_clone =
             match expr.node {
                 Assign(ref lhs, _) | AssignOp(_, ref lhs, _) => lhs.clone(),
@@ -87,6 +167,12 @@ impl<'a> Folder for Adder<'a> {
     }
 }
 
+#[derive(Debug, Eq, Hash, PartialEq)]
+pub struct DynamicChildren {
+    pub container_name: Ident,
+    pub name: Ident,
+}
+
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub struct Message {
     pub expr: Expr,
@@ -102,68 +188,235 @@ pub struct Property {
     pub widget_name: Ident,
 }
 
-fn create_stmts(ident: &Ident, property_map: &PropertyModelMap, msg_map: &MsgModelMap) -> Vec<Stmt> {
+fn create_stmts(path: &[Ident], property_map: &PropertyModelMap, msg_map: &MsgModelMap,
+    dynamic_map: &DynamicModelMap) -> Vec<Stmt>
+{
     let mut stmts = vec![];
-    stmts.append(&mut create_stmts_for_props(ident, property_map));
-    stmts.append(&mut create_stmts_for_msgs(ident, msg_map));
+    stmts.append(&mut create_stmts_for_props(path, property_map));
+    stmts.append(&mut create_stmts_for_msgs(path, msg_map));
+    stmts.append(&mut create_stmts_for_dynamic_children(path, dynamic_map));
     stmts
 }
 
-fn create_stmts_for_msgs(ident: &Ident, msg_map: &MsgModelMap) -> Vec<Stmt> {
+fn create_stmts_for_dynamic_children(path: &[Ident], dynamic_map: &DynamicModelMap) -> Vec<Stmt> {
     let mut stmts = vec![];
-    if let Some(messages) = msg_map.get(ident) {
-        for msg in messages {
-            let widget_name = &msg.widget_name;
-            let mut value = Tokens::new();
-            value.append_all(&[&msg.expr]);
-            let variant = Ident::new(msg.name.as_str());
-            let stmt = quote! {
-                { self.#widget_name.stream().emit(#variant(#value)); }
-            };
-            let expr = parse_expr(&stmt.parse::<String>().expect("parse::<String>() in create_stmts"))
-                .expect("parse_expr() in create_stmts");
-            if let ExprKind::Block(_, ref block) = expr.node {
-                stmts.push(block.stmts[0].clone());
-            }
+    for for_loop in overlapping_values(path, dynamic_map) {
+        let container_name = &for_loop.container_name;
+        let name = &for_loop.name;
+        let stmt = quote! {
+            { Self::#name(&self.__relm, &self.#container_name, &mut self.#name, &self.model); }
+        };
+        let expr = parse_expr(&stmt.parse::<String>().expect("parse::<String>() in create_stmts"))
+            .expect("parse_expr() in create_stmts");
+        if let ExprKind::Block(_, ref block) = expr.node {
+            stmts.push(block.stmts[0].clone());
         }
     }
     stmts
 }
 
-fn create_stmts_for_props(ident: &Ident, property_map: &PropertyModelMap) -> Vec<Stmt> {
+fn create_stmts_for_msgs(path: &[Ident], msg_map: &MsgModelMap) -> Vec<Stmt> {
     let mut stmts = vec![];
-    if let Some(properties) = property_map.get(ident) {
-        for property in properties {
-            let widget_name = &property.widget_name;
-            let prop_name = Ident::new(format!("set_{}", property.name));
-            let mut tokens = Tokens::new();
-            tokens.append_all(&[&property.expr]);
-            let stmt =
-                if property.is_relm_widget {
-                    quote! {
-                        { self.#widget_name.#prop_name(#tokens); }
-                    }
+    for msg in overlapping_values(path, msg_map) {
+        let widget_name = &msg.widget_name;
+        let mut value = Tokens::new();
+        value.append_all(&[&msg.expr]);
+        let variant = Ident::new(msg.name.as_str());
+        let stmt = quote! {
+            { self.#widget_name.stream().emit(#variant(#value)); }
+        };
+        let expr = parse_expr(&stmt.parse::<String>().expect("parse::<String>() in create_stmts"))
+            .expect("parse_expr() in create_stmts");
+        if let ExprKind::Block(_, ref block) = expr.node {
+            stmts.push(block.stmts[0].clone());
+        }
+    }
+    stmts
+}
+
+fn create_stmts_for_props(path: &[Ident], property_map: &PropertyModelMap) -> Vec<Stmt> {
+    let mut stmts = vec![];
+    for property in overlapping_values(path, property_map) {
+        let widget_name = &property.widget_name;
+        let prop_name = Ident::new(format!("set_{}", property.name));
+        let mut tokens = Tokens::new();
+        tokens.append_all(&[&property.expr]);
+        let stmt =
+            if property.is_relm_widget {
+                quote! {
+                    { self.#widget_name.#prop_name(#tokens); }
                 }
-                else {
-                    quote! {
-                        { self.#widget_name.#prop_name(#tokens); }
-                    }
-                };
-            let expr = parse_expr(&stmt.parse::<String>().expect("parse::<String>() in create_stmts"))
-                .expect("parse_expr() in create_stmts");
-            if let ExprKind::Block(_, ref block) = expr.node {
-                stmts.push(block.stmts[0].clone());
             }
+            else {
+                quote! {
+                    { self.#widget_name.#prop_name(#tokens); }
+                }
+            };
+        let expr = parse_expr(&stmt.parse::<String>().expect("parse::<String>() in create_stmts"))
+            .expect("parse_expr() in create_stmts");
+        if let ExprKind::Block(_, ref block) = expr.node {
+            stmts.push(block.stmts[0].clone());
         }
     }
     stmts
 }
 
-fn is_model_path(expr: &Expr) -> bool {
-    if let Field(ref expr, ref ident) = expr.node {
-        if let Expr { node: ExprKind::Path(None, Path { ref segments, .. }), .. } = **expr {
-            return segments.len() == 1 && segments[0].ident == Ident::new("self") && *ident == Ident::new("model");
+/// Get the path of the model variable modified through the expression, like `self.model.user.name`
+/// or `self.model.items[index]`.
+pub fn modified_path(expr: &Expr) -> Option<ModelPath> {
+    match expr.node {
+        Index(ref expr, _) | Paren(ref expr) => modified_path(expr),
+        _ => model_path(expr),
+    }
+}
+
+/// Get the path of the model variable of the expression `&mut self.model.variable`.
+fn mutable_borrow(expr: &Expr) -> Option<ModelPath> {
+    match expr.node {
+        AddrOf(Mutable, ref expr) => modified_path(expr),
+        Paren(ref expr) => mutable_borrow(expr),
+        _ => None,
+    }
+}
+
+/// Get the path of the model variable borrowed in `let variable = &mut self.model.variable;`.
+fn mutable_borrow_in_local(stmt: &Stmt) -> Option<ModelPath> {
+    if let Local(ref local) = *stmt {
+        if let Some(ref init) = local.init {
+            return mutable_borrow(init);
+        }
+    }
+    None
+}
+
+/// Get the model variables mutated by a call: the receiver of a mutating method like
+/// `self.model.text.push_str()` and the arguments like `&mut self.model.text`, as in `mem::swap()`.
+pub fn mutated_model_variables(expr: &Expr) -> Vec<ModelPath> {
+    let mut paths = vec![];
+    let args =
+        match expr.node {
+            Call(_, ref args) => &args[..],
+            MethodCall(ref method, _, ref args) => {
+                let method: &str = method.as_ref();
+                if MUTATING_METHODS.contains(&method) {
+                    if let Some(path) = args.first().and_then(modified_path) {
+                        paths.push(path);
+                    }
+                }
+                &args[..]
+            },
+            _ => return paths,
+        };
+    for arg in args {
+        if let Some(path) = mutable_borrow(arg) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
         }
     }
-    false
+    paths
+}
+
+/// Get the values of the map for the model paths overlapping `path`, without duplicates.
+fn overlapping_values<'a, T: PartialEq>(path: &[Ident], map: &'a HashMap<ModelPath, HashSet<T>>) -> Vec<&'a T> {
+    let mut values = vec![];
+    for (key, set) in map {
+        if paths_overlap(path, key) {
+            for value in set {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+    }
+    values
+}
+
+fn tokens_to_stmts(tokens: Tokens) -> Vec<Stmt> {
+    let expr = parse_expr(&tokens.parse::<String>().expect("parse::<String>() in tokens_to_stmts"))
+        .expect("parse_expr() in tokens_to_stmts");
+    match expr.node {
+        Block(_, block) => block.stmts,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use syn::{Ident, parse_expr};
+    use syn::fold::Folder;
+
+    use walker::ModelPath;
+    use super::{Adder, Property, mutated_model_variables};
+
+    fn path(idents: &[&str]) -> ModelPath {
+        idents.iter().map(|ident| Ident::new(*ident)).collect()
+    }
+
+    fn mutated(expr: &str) -> Vec<ModelPath> {
+        mutated_model_variables(&parse_expr(expr).expect("expr"))
+    }
+
+    /// Add the statements updating the label bound to `self.model.text` and return the code
+    /// without spaces.
+    fn fold(code: &str) -> String {
+        let mut properties = HashSet::new();
+        properties.insert(Property {
+            expr: parse_expr("self.model.text.clone()").expect("expr"),
+            is_relm_widget: false,
+            name: "text".to_string(),
+            widget_name: Ident::new("label"),
+        });
+        let mut property_map = HashMap::new();
+        property_map.insert(path(&["text"]), properties);
+        let msg_map = HashMap::new();
+        let dynamic_map = HashMap::new();
+        let mut adder = Adder::new(&property_map, &msg_map, &dynamic_map);
+        let expr = adder.fold_expr(parse_expr(code).expect("expr"));
+        quote! { #expr }.as_str().replace(' ', "")
+    }
+
+    const SET_TEXT: &str = "self.label.set_text(self.model.text.clone());";
+
+    #[test]
+    fn mutating_methods() {
+        assert_eq!(mutated("self.model.text.push_str(\"a\")"), vec![path(&["text"])]);
+        assert_eq!(mutated("self.model.user.names.push(name)"), vec![path(&["user", "names"])]);
+        assert_eq!(mutated("self.model.items[0].clear()"), vec![path(&["items"])]);
+        assert_eq!(mutated("self.model.text.len()"), Vec::<ModelPath>::new());
+        assert_eq!(mutated("self.text.push_str(\"a\")"), Vec::<ModelPath>::new());
+    }
+
+    #[test]
+    fn mutable_borrow_arguments() {
+        assert_eq!(mutated("mem::swap(&mut self.model.a, &mut self.model.b)"), vec![path(&["a"]), path(&["b"])]);
+        assert_eq!(mutated("self.model.a.append(&mut self.model.b)"), vec![path(&["a"]), path(&["b"])]);
+        assert_eq!(mutated("process(&self.model.a)"), Vec::<ModelPath>::new());
+    }
+
+    #[test]
+    fn method_call() {
+        let code = fold("{ self.model.text.push_str(\"a\"); }");
+        assert!(code.contains(SET_TEXT), "{}", code);
+        let code = fold("{ self.model.other.push_str(\"a\"); }");
+        assert!(!code.contains(SET_TEXT), "{}", code);
+    }
+
+    #[test]
+    fn call_value() {
+        // The value returned by the call is kept.
+        let code = fold("{ let value = self.model.text.pop(); }");
+        assert!(code.contains("let__relm_value=self.model.text.pop();"), "{}", code);
+        assert!(code.contains(SET_TEXT), "{}", code);
+    }
+
+    #[test]
+    fn local_mutable_borrow() {
+        let code = fold("{ let text = &mut self.model.text; text.push('a'); }");
+        let push = code.find("text.push('a');").expect("push");
+        let set_text = code.find(SET_TEXT).expect("set_text");
+        assert!(push < set_text, "{}", code);
+    }
 }
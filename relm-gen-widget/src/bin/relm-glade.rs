@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Convert a view to a GtkBuilder interface which can be edited in Glade.
+//!
+//! Usage: relm-glade <view.relm|widget.rs> [output.ui]
+
+extern crate relm_gen_widget;
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process;
+
+use relm_gen_widget::view_to_glade;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let input =
+        match args.next() {
+            Some(input) => input,
+            None => {
+                eprintln!("Usage: relm-glade <view.relm|widget.rs> [output.ui]");
+                process::exit(1);
+            },
+        };
+    let mut source = String::new();
+    let mut file = File::open(&input).expect("cannot open input file");
+    file.read_to_string(&mut source).expect("cannot read input file");
+    let interface =
+        match view_to_glade(&source) {
+            Ok(interface) => interface,
+            Err(error) => {
+                eprintln!("{}: {}", input, error);
+                process::exit(1);
+            },
+        };
+    match args.next() {
+        Some(output) => {
+            let mut file = File::create(&output).expect("cannot create output file");
+            file.write_all(interface.as_bytes()).expect("cannot write output file");
+        },
+        None => print!("{}", interface),
+    }
+}
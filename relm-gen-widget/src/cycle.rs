@@ -0,0 +1,511 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Detection of the event cycles in a widget, like a parent sending a message to a child which emits
+//! an event that the parent maps back to the same message.
+//!
+//! The graph links the messages handled by update() to the messages they emit and to the messages
+//! sent to the relm children through the model variables they modify, and the events of the relm
+//! children to the messages they are mapped to in the view! macro.
+//! The messages that a relm child emits to itself are known when the child was declared earlier in
+//! the crate and when its type is written in the view as its name, like `Counter`: since the path of
+//! the widgets is unknown, a type written `counter::Counter` or declared several times is ignored.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Display, Formatter};
+use std::sync::Mutex;
+
+use quote::Tokens;
+use syn::{self, Arm, Expr, Ident, ImplItem, Pat, parse_expr};
+use syn::ExprKind::{Assign, AssignOp, Call, Field, Match, MethodCall, Paren};
+use syn::FnArg::Captured;
+use syn::ImplItemKind::Method;
+use syn::visit::{Visitor, walk_expr};
+
+use adder::{modified_path, mutated_model_variables};
+use parser::EitherWidget::Relm;
+use parser::EventValue::{CurrentWidget, ForeignWidget};
+use parser::EventValueReturn::{CallReturn, Return, WithoutReturn};
+use parser::{EventValueReturn, Widget};
+use walker::{ModelPath, is_self, paths_overlap};
+use super::MsgModelMap;
+
+lazy_static! {
+    // Map a widget type to the pairs (message handled, message emitted to itself) of its update() method,
+    // or to None when several widgets have this name.
+    static ref SELF_MESSAGES: Mutex<HashMap<String, Option<Vec<(String, String)>>>> = Mutex::new(HashMap::new());
+}
+
+type Graph = BTreeMap<Node, BTreeSet<Node>>;
+
+/// A message sent to the widget itself (when `component` is `None`) or to one of its relm children.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Node {
+    component: Option<String>,
+    message: String,
+}
+
+impl Node {
+    fn new(component: Option<String>, message: String) -> Self {
+        Node {
+            component,
+            message,
+        }
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self.component {
+            Some(ref component) => write!(formatter, "{}@{}", component, self.message),
+            None => write!(formatter, "{}", self.message),
+        }
+    }
+}
+
+/// Find the event cycles of the widget `name`, which is the name of its type.
+pub fn event_cycles(name: &str, widget: &Widget, update: &ImplItem, msg_map: &MsgModelMap) -> Vec<Vec<Node>> {
+    let mut children = HashMap::new();
+    let mut graph = Graph::new();
+    add_view_edges(widget, &mut graph, &mut children);
+
+    let mut self_messages = vec![];
+    for arm in update_arms(update) {
+        let mut visitor = ArmVisitor::new(&children);
+        visitor.visit_expr(&arm.body);
+        for message in arm.pats.iter().filter_map(pat_variant) {
+            let from = Node::new(None, message.clone());
+            for node in &visitor.emitted {
+                if node.component.is_none() {
+                    self_messages.push((message.clone(), node.message.clone()));
+                }
+                add_edge(&mut graph, from.clone(), node.clone());
+            }
+            for path in &visitor.paths {
+                for (model_path, messages) in msg_map {
+                    if paths_overlap(path, model_path) {
+                        for msg in messages {
+                            let node = Node::new(Some(msg.widget_name.to_string()), msg.name.clone());
+                            add_edge(&mut graph, from.clone(), node);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut widgets = SELF_MESSAGES.lock().expect("lock() in event_cycles()");
+    for (child, typ) in &children {
+        if let Some(&Some(ref messages)) = widgets.get(typ) {
+            for &(ref handled, ref emitted) in messages {
+                add_edge(&mut graph, Node::new(Some(child.clone()), handled.clone()),
+                    Node::new(Some(child.clone()), emitted.clone()));
+            }
+        }
+    }
+    let messages =
+        if widgets.contains_key(name) {
+            None
+        }
+        else {
+            Some(self_messages)
+        };
+    widgets.insert(name.to_string(), messages);
+
+    find_cycles(&graph)
+}
+
+/// Log a warning for each event cycle of the widget `name`.
+pub fn warn_event_cycles(name: &str, widget: &Widget, update: &ImplItem, msg_map: &MsgModelMap) {
+    for cycle in event_cycles(name, widget, update, msg_map) {
+        warn!("possible event cycle in widget {}: {}", name, cycle_to_string(&cycle));
+    }
+}
+
+/// Show a cycle as `Increment -> counter@Set -> counter@Changed -> Increment`.
+pub fn cycle_to_string(cycle: &[Node]) -> String {
+    let mut nodes: Vec<_> = cycle.iter().map(ToString::to_string).collect();
+    nodes.push(cycle[0].to_string());
+    nodes.join(" -> ")
+}
+
+struct ArmVisitor<'a> {
+    children: &'a HashMap<String, String>,
+    emitted: Vec<Node>,
+    paths: Vec<ModelPath>,
+}
+
+impl<'a> ArmVisitor<'a> {
+    fn new(children: &'a HashMap<String, String>) -> Self {
+        ArmVisitor {
+            children,
+            emitted: vec![],
+            paths: vec![],
+        }
+    }
+
+    /*
+     * Get the component receiving the messages emitted on `expr`: a relm child for self.child or
+     * self.child.stream(), the widget itself for relm.stream() or self.model.relm.stream().
+     */
+    fn receiver(&self, expr: &Expr) -> Option<Option<String>> {
+        match expr.node {
+            MethodCall(ref method, _, ref args) if method == "stream" && args.len() == 1 => self.receiver(&args[0]),
+            Paren(ref expr) => self.receiver(expr),
+            Field(ref object, ref field) => {
+                if is_self(object) && self.children.contains_key(field.as_ref()) {
+                    Some(Some(field.to_string()))
+                }
+                else if field == "relm" {
+                    Some(None)
+                }
+                else {
+                    None
+                }
+            },
+            syn::ExprKind::Path(None, ref path) if path.segments.len() == 1 && path.segments[0].ident == "relm" =>
+                Some(None),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Visitor for ArmVisitor<'a> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr.node {
+            Assign(ref lhs, _) | AssignOp(_, ref lhs, _) => {
+                if let Some(path) = modified_path(lhs) {
+                    self.paths.push(path);
+                }
+            },
+            MethodCall(ref method, _, ref args) => {
+                if method == "emit" && args.len() == 2 {
+                    if let (Some(component), Some(message)) = (self.receiver(&args[0]), expr_variant(&args[1])) {
+                        self.emitted.push(Node::new(component, message));
+                    }
+                }
+                self.paths.append(&mut mutated_model_variables(expr));
+            },
+            Call(..) => self.paths.append(&mut mutated_model_variables(expr)),
+            _ => (),
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Visitor to get the arms of the match on the message in the update() method.
+struct MatchVisitor {
+    arms: Vec<Arm>,
+    event: Ident,
+}
+
+impl Visitor for MatchVisitor {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Match(ref matched, ref arms) = expr.node {
+            if let syn::ExprKind::Path(None, ref path) = matched.node {
+                if path.segments.len() == 1 && path.segments[0].ident == self.event {
+                    self.arms.extend(arms.iter().cloned());
+                    return;
+                }
+            }
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/*
+ * Add the edges from the events of the relm children to the messages they are mapped to and
+ * collect the names of the relm children with their type.
+ */
+fn add_view_edges(widget: &Widget, graph: &mut Graph, children: &mut HashMap<String, String>) {
+    if let Relm(ref relm_widget) = widget.widget {
+        let name = widget.name.to_string();
+        let segments: Vec<_> = widget.typ.segments.iter().map(|segment| segment.ident.as_ref()).collect();
+        children.insert(name.clone(), segments.join("::"));
+        for (message, events) in &relm_widget.events {
+            let from = Node::new(Some(name.clone()), message.clone());
+            for event in events {
+                let to =
+                    match event.value {
+                        CurrentWidget(ref value) => return_value_variant(value).map(|message| Node::new(None, message)),
+                        ForeignWidget(ref widget, ref value) => return_value_variant(value)
+                            .map(|message| Node::new(Some(widget.as_str().to_string()), message)),
+                    };
+                if let Some(to) = to {
+                    add_edge(graph, from.clone(), to);
+                }
+            }
+        }
+    }
+    for child in &widget.children {
+        add_view_edges(child, graph, children);
+    }
+    for for_loop in &widget.loops {
+        add_view_edges(&for_loop.child, graph, children);
+    }
+    for condition in &widget.conditions {
+        for branch in &condition.branches {
+            if let Some(ref child) = branch.child {
+                add_view_edges(child, graph, children);
+            }
+        }
+    }
+}
+
+fn add_edge(graph: &mut Graph, from: Node, to: Node) {
+    graph.entry(from).or_insert_with(BTreeSet::new).insert(to);
+}
+
+/// Get the name of the variant of a message like `Msg::Set(value)` or `Increment`.
+fn expr_variant(expr: &Expr) -> Option<String> {
+    match expr.node {
+        Call(ref function, _) => expr_variant(function),
+        Paren(ref expr) => expr_variant(expr),
+        syn::ExprKind::Path(_, ref path) => path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+/*
+ * Find one cycle for each back edge found by a depth-first search, which finds at least one cycle
+ * in each group of messages that can trigger each other.
+ */
+fn find_cycles(graph: &Graph) -> Vec<Vec<Node>> {
+    let mut cycles = vec![];
+    let mut visited = BTreeSet::new();
+    for node in graph.keys() {
+        let mut path = vec![];
+        visit(graph, node, &mut path, &mut visited, &mut cycles);
+    }
+    cycles
+}
+
+/// Get the variant of a pattern like `Msg::Set(value)`, `Msg::Move { x, y }` or `Increment`.
+fn pat_variant(pat: &Pat) -> Option<String> {
+    match *pat {
+        Pat::Path(_, ref path) | Pat::Struct(ref path, _, _) | Pat::TupleStruct(ref path, _, _) =>
+            path.segments.last().map(|segment| segment.ident.to_string()),
+        Pat::Ident(_, ref ident, None) if ident.as_ref().chars().next().map(char::is_uppercase) == Some(true) =>
+            Some(ident.to_string()),
+        _ => None,
+    }
+}
+
+fn return_value_variant(value: &EventValueReturn) -> Option<String> {
+    match *value {
+        Return(ref message, _) | WithoutReturn(ref message) => tokens_variant(message),
+        CallReturn(_) => None,
+    }
+}
+
+fn tokens_variant(tokens: &Tokens) -> Option<String> {
+    parse_expr(tokens.as_str()).ok().and_then(|expr| expr_variant(&expr))
+}
+
+/// Get the arms of the match on the message parameter of the update() method.
+fn update_arms(update: &ImplItem) -> Vec<Arm> {
+    if let Method(ref sig, ref block) = update.node {
+        if let Some(&Captured(Pat::Ident(_, ref event, _), _)) = sig.decl.inputs.get(1) {
+            let mut visitor = MatchVisitor {
+                arms: vec![],
+                event: event.clone(),
+            };
+            for stmt in &block.stmts {
+                visitor.visit_stmt(stmt);
+            }
+            return visitor.arms;
+        }
+    }
+    vec![]
+}
+
+fn visit(graph: &Graph, node: &Node, path: &mut Vec<Node>, visited: &mut BTreeSet<Node>,
+    cycles: &mut Vec<Vec<Node>>)
+{
+    if let Some(index) = path.iter().position(|path_node| path_node == node) {
+        cycles.push(path[index..].to_vec());
+        return;
+    }
+    if visited.contains(node) {
+        return;
+    }
+    path.push(node.clone());
+    if let Some(nodes) = graph.get(node) {
+        for next in nodes {
+            visit(graph, next, path, visited, cycles);
+        }
+    }
+    path.pop();
+    visited.insert(node.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Mutex;
+
+    use log::{self, Log, LogLevel, LogLevelFilter, LogMetadata, LogRecord};
+    use syn::{Ident, ImplItem, parse_expr, parse_item, parse_token_trees};
+    use syn::ItemKind::Impl;
+
+    use adder::Message;
+    use parser::{Widget, parse};
+    use walker::ModelPath;
+    use super::{cycle_to_string, event_cycles, warn_event_cycles};
+    use super::super::MsgModelMap;
+
+    lazy_static! {
+        static ref WARNINGS: Mutex<Vec<String>> = Mutex::new(vec![]);
+    }
+
+    // Logger keeping the warnings of this module.
+    struct WarningLogger;
+
+    impl Log for WarningLogger {
+        fn enabled(&self, metadata: &LogMetadata) -> bool {
+            metadata.level() <= LogLevel::Warn && metadata.target() == "relm_gen_widget::cycle"
+        }
+
+        fn log(&self, record: &LogRecord) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.lock().expect("lock() in log()").push(record.args().to_string());
+            }
+        }
+    }
+
+    fn cycles(name: &str, view: &str, update: &str, msg_map: &MsgModelMap) -> Vec<String> {
+        event_cycles(name, &widget(view), &update_method(update), msg_map).iter()
+            .map(|cycle| cycle_to_string(cycle))
+            .collect()
+    }
+
+    fn update_method(arms: &str) -> ImplItem {
+        let item = parse_item(&format!("
+            impl Widget {{
+                fn update(&mut self, event: Msg) {{
+                    match event {{
+                        {}
+                    }}
+                }}
+            }}", arms)).expect("item");
+        let items =
+            match item.node {
+                Impl(_, _, _, _, _, items) => items,
+                _ => vec![],
+            };
+        items.into_iter().next().expect("update method")
+    }
+
+    fn widget(view: &str) -> Widget {
+        parse(&parse_token_trees(view).expect("tokens"), "gtk").expect("widget")
+    }
+
+    #[test]
+    fn forwarded_events() {
+        let view = "
+            gtk::Box {
+                #[name=\"first\"]
+                FirstChild {
+                    Changed => second@Reset,
+                },
+                #[name=\"second\"]
+                SecondChild {
+                    Reset => Reset,
+                },
+            }";
+        let update = "Reset => self.first.stream().emit(FirstMsg::Changed),";
+        assert_eq!(cycles("ForwardWin", view, update, &HashMap::new()),
+            vec!["Reset -> first@Changed -> second@Reset -> Reset"]);
+        // Without the message sent to the first child, there is no cycle.
+        assert!(cycles("ForwardWin", view, "Reset => (),", &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn model_messages() {
+        let view = "
+            gtk::Box {
+                #[name=\"counter\"]
+                ModelCounter {
+                    Value: self.model.value,
+                    Changed(value) => Set(value),
+                },
+            }";
+        let path: ModelPath = vec![Ident::new("value")];
+        let message = Message {
+            expr: parse_expr("self.model.value").expect("expr"),
+            name: "Value".to_string(),
+            widget_name: Ident::new("counter"),
+        };
+        let mut msg_map = HashMap::new();
+        msg_map.insert(path, vec![message].into_iter().collect::<HashSet<_>>());
+        // The cycle goes through the messages that the child emits to itself.
+        let update = "Value(value) => self.model.relm.stream().emit(Changed(value)),";
+        assert!(cycles("ModelCounter", "gtk::Label {}", update, &HashMap::new()).is_empty());
+        assert_eq!(cycles("ModelWin", view, "Set(value) => self.model.value = value,", &msg_map),
+            vec!["Set -> counter@Value -> counter@Changed -> Set"]);
+    }
+
+    #[test]
+    fn child_paths() {
+        let update = "Start => self.model.relm.stream().emit(Stop),";
+        assert!(cycles("PathChild", "gtk::Label {}", update, &HashMap::new()).is_empty());
+        // Only a type written by its name is known, since the path of the widgets is unknown.
+        let view = "
+            gtk::Box {
+                #[name=\"child\"]
+                other::PathChild {
+                    Stop => child@Start,
+                },
+            }";
+        assert!(cycles("PathWin", view, "", &HashMap::new()).is_empty());
+        let view = "
+            gtk::Box {
+                #[name=\"child\"]
+                PathChild {
+                    Stop => child@Start,
+                },
+            }";
+        assert_eq!(cycles("PathWin", view, "", &HashMap::new()), vec!["child@Start -> child@Stop -> child@Start"]);
+        // The messages of a widget declared twice, in different modules, are ignored.
+        assert!(cycles("PathChild", "gtk::Label {}", "", &HashMap::new()).is_empty());
+        assert!(cycles("PathWin", view, "", &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn warnings() {
+        log::set_logger(|max_level| {
+            max_level.set(LogLevelFilter::Warn);
+            Box::new(WarningLogger)
+        }).expect("set_logger");
+        let view = "
+            gtk::Box {
+                #[name=\"counter\"]
+                WarnCounter {
+                    Changed => Reset,
+                },
+            }";
+        let update = "Reset => self.counter.stream().emit(CounterMsg::Changed),";
+        warn_event_cycles("WarnWin", &widget(view), &update_method(update), &HashMap::new());
+        warn_event_cycles("WarnWin2", &widget(view), &update_method("Reset => (),"), &HashMap::new());
+        assert_eq!(*WARNINGS.lock().expect("lock() in warnings()"),
+            vec!["possible event cycle in widget WarnWin: Reset -> counter@Changed -> Reset"]);
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Errors of the `#[widget]` attribute and of the view! macro.
+//!
+//! The token trees of syn do not carry their location, so the messages quote the offending tokens
+//! instead; they are emitted with compile_error!() rather than with a panic.
+
+use std::fmt::{self, Display, Formatter};
+use std::result;
+
+use quote::{Tokens, ToTokens};
+use syn::TokenTree;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Error {
+            message: message.into(),
+        }
+    }
+
+    /// Create an error for an unexpected token in the view! macro.
+    pub fn unexpected(expected: &str, found: Option<&TokenTree>) -> Self {
+        match found {
+            Some(token) =>
+                Error::new(format!("expected {}, found `{}` in view! macro", expected, token_to_string(token))),
+            None => Error::new(format!("expected {}, found the end of the view! macro", expected)),
+        }
+    }
+
+    pub fn to_compile_error(&self) -> Tokens {
+        let message = &self.message;
+        quote! {
+            compile_error!(#message);
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.message)
+    }
+}
+
+pub fn token_to_string(token: &TokenTree) -> String {
+    let mut tokens = Tokens::new();
+    token.to_tokens(&mut tokens);
+    tokens.as_str().to_string()
+}
@@ -0,0 +1,334 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Write the expansion of a widget to `target/relm-expansion/<Widget>.rs` to inspect the generated code.
+//!
+//! Since the tokens do not carry their location, each line of the expansion using a widget is
+//! annotated with the path of this widget in the view! macro and with its line in the source.
+//! This line is found by searching the widget types, in the order of the view, in the view file or,
+//! for an inline view! macro, in the source file of the crate containing the `impl Widget`.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use quote::{Tokens, ToTokens};
+use syn::Ident;
+
+use parser::Widget;
+
+/// The environment variable enabling the expansion output for every widget.
+pub const DEBUG_ENV_VAR: &str = "RELM_DEBUG_EXPANSION";
+
+/// The directories of a crate which can contain the source of a widget.
+const SOURCE_DIRECTORIES: &[&str] = &["src", "examples", "tests", "benches"];
+
+/// The location of a widget in the view! macro.
+#[derive(Debug)]
+pub struct WidgetLocation {
+    description: String,
+    ident: Ident,
+}
+
+/// Search the lines of the widgets in a source, from the beginning of the view.
+struct LineFinder {
+    column: usize,
+    glade: bool,
+    line: usize,
+    lines: Vec<String>, // The lines without spaces.
+    path: String,
+}
+
+impl LineFinder {
+    fn new(path: &str, content: &str, line: usize) -> Self {
+        LineFinder {
+            column: 0,
+            glade: path.ends_with(".glade") || path.ends_with(".ui"),
+            line,
+            lines: content.lines().map(|line| line.replace(" ", "")).collect(),
+            path: path.to_string(),
+        }
+    }
+
+    /// Find the line of the next widget of this type and return its location, like `src/main.rs:42`.
+    fn find(&mut self, typ: &str) -> Option<String> {
+        let pattern =
+            if self.glade {
+                format!("class=\"Gtk{}\"", typ.rsplit("::").next().unwrap_or(typ))
+            }
+            else {
+                typ.to_string()
+            };
+        for line in self.line..self.lines.len() {
+            let column = if line == self.line { self.column } else { 0 };
+            let text = &self.lines[line][column..];
+            // The spaces are removed, so the class attribute follows the element name.
+            let index = if self.glade { text.find(&pattern) } else { find_ident(text, &pattern) };
+            if let Some(index) = index {
+                self.line = line;
+                self.column = column + index + pattern.len();
+                return Some(format!("{}:{}", self.path, line + 1));
+            }
+        }
+        None
+    }
+}
+
+/// Get the location of every widget of the view, including those of the loops and of the conditions.
+pub fn widget_locations(name: &Ident, widget: &Widget, view_file: Option<&String>) -> Vec<WidgetLocation> {
+    let mut finder =
+        match view_file {
+            Some(path) => read_file(Path::new(path)).map(|content| LineFinder::new(path, &content, 0)),
+            None => find_source(name, widget),
+        };
+    let mut locations = vec![];
+    add_locations(widget, "", finder.as_mut(), &mut locations);
+    locations
+}
+
+fn add_locations(widget: &Widget, parent: &str, mut finder: Option<&mut LineFinder>,
+    locations: &mut Vec<WidgetLocation>)
+{
+    let typ = tokens_to_string(&widget.typ).replace(" ", "");
+    let mut description =
+        if parent.is_empty() {
+            typ.clone()
+        }
+        else {
+            format!("{} > {}", parent, typ)
+        };
+    if let Some(location) = finder.as_mut().and_then(|finder| finder.find(&typ)) {
+        description = format!("{} ({})", description, location);
+    }
+    // The children, the loops and the conditions are visited in the order of the view.
+    let mut children = widget.children.iter();
+    for position in 0..widget.children.len() + widget.loops.len() + widget.conditions.len() {
+        if let Some(for_loop) = widget.loops.iter().find(|for_loop| for_loop.position == position) {
+            let parent = format!("{} > for {} in {}", description, for_loop.pattern.as_str(),
+                                 tokens_to_string(&for_loop.collection));
+            add_locations(&for_loop.child, &parent, finder.as_mut().map(|finder| &mut **finder), locations);
+        }
+        else if let Some(condition) = widget.conditions.iter().find(|condition| condition.position == position) {
+            for branch in &condition.branches {
+                if let Some(ref child) = branch.child {
+                    let parent = format!("{} > {}", description, condition.name);
+                    add_locations(child, &parent, finder.as_mut().map(|finder| &mut **finder), locations);
+                }
+            }
+        }
+        else if let Some(child) = children.next() {
+            add_locations(child, &description, finder.as_mut().map(|finder| &mut **finder), locations);
+        }
+    }
+    locations.push(WidgetLocation {
+        description,
+        ident: widget.name.clone(),
+    });
+}
+
+/*
+ * Find the source file containing the inline view! macro of the widget `name`, starting at the
+ * view! macro following `impl Widget for name`.
+ * When several files of the crate implement a widget with this name, like the examples, the one
+ * where the most widgets of the view are found is used.
+ */
+fn find_source(name: &Ident, widget: &Widget) -> Option<LineFinder> {
+    let directory = PathBuf::from(env::var("CARGO_MANIFEST_DIR").ok()?);
+    let mut files = vec![];
+    for source_directory in SOURCE_DIRECTORIES {
+        rust_files(&directory.join(source_directory), &mut files);
+    }
+    let implementation = format!("Widget for {}", name);
+    let mut best: Option<(usize, LineFinder)> = None;
+    for file in files {
+        let content =
+            match read_file(&file) {
+                Some(content) => content,
+                None => continue,
+            };
+        let view_line = content.lines()
+            .position(|line| find_ident(line, &implementation).is_some())
+            .and_then(|line| {
+                content.lines().skip(line).position(|view| view.contains("view!")).map(|view| line + view)
+            });
+        if let Some(view_line) = view_line {
+            let path = file.strip_prefix(&directory).unwrap_or(&file).to_string_lossy().into_owned();
+            let mut finder = LineFinder::new(&path, &content, view_line);
+            let mut locations = vec![];
+            add_locations(widget, "", Some(&mut finder), &mut locations);
+            let found = locations.iter().filter(|location| location.description.ends_with(')')).count();
+            if best.as_ref().map_or(true, |&(best_found, _)| found > best_found) {
+                best = Some((found, LineFinder::new(&path, &content, view_line)));
+            }
+        }
+    }
+    best.map(|(_, finder)| finder)
+}
+
+/// Find `ident` in `line`, where it is not part of a longer identifier.
+fn find_ident(line: &str, ident: &str) -> Option<usize> {
+    let is_ident_char = |char: char| char.is_alphanumeric() || char == '_';
+    line.match_indices(ident).map(|(index, _)| index).find(|&index| {
+        let before = line[..index].chars().next_back();
+        let after = line[index + ident.len()..].chars().next();
+        !before.map_or(false, &is_ident_char) && !after.map_or(false, &is_ident_char)
+    })
+}
+
+fn read_file(path: &Path) -> Option<String> {
+    let mut content = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut content)).ok().map(|_| content)
+}
+
+/// Add the rust files of `directory` and of its subdirectories to `files`.
+fn rust_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                rust_files(&path, files);
+            }
+            else if path.extension().map_or(false, |extension| extension == "rs") {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// Write the formatted expansion of a widget, returning the path of the written file.
+pub fn write_expansion(name: &Ident, code: &Tokens, locations: &[WidgetLocation]) -> io::Result<PathBuf> {
+    let target_directory = target_directory()?;
+    let directory = target_directory.join("relm-expansion");
+    fs::create_dir_all(&directory)?;
+    let path = directory.join(format!("{}.rs", name.as_ref().replace("::", "__")));
+    let code = format_code(code.as_str());
+    let mut expansion = format!("// Expansion of #[widget] for `{}`.\n\n", name);
+    for line in code.lines() {
+        expansion.push_str(line);
+        if let Some(location) = locations.iter().find(|location| find_ident(line, location.ident.as_ref()).is_some()) {
+            expansion.push_str(&format!(" // view!: {}", location.description));
+        }
+        expansion.push('\n');
+    }
+    let mut file = File::create(&path)?;
+    file.write_all(expansion.as_bytes())?;
+    Ok(path)
+}
+
+/*
+ * Get the target directory: the one of the workspace for a workspace member, unless
+ * CARGO_TARGET_DIR is set.
+ */
+fn target_directory() -> io::Result<PathBuf> {
+    if let Ok(directory) = env::var("CARGO_TARGET_DIR") {
+        return Ok(PathBuf::from(directory));
+    }
+    let directory = env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| io::Error::new(ErrorKind::NotFound, "cannot find the target directory"))?;
+    let directory = PathBuf::from(directory);
+    let mut root = directory.as_path();
+    let mut parent = directory.parent();
+    while let Some(ancestor) = parent {
+        if is_workspace_root(ancestor) {
+            root = ancestor;
+        }
+        parent = ancestor.parent();
+    }
+    Ok(root.join("target"))
+}
+
+fn is_workspace_root(directory: &Path) -> bool {
+    read_file(&directory.join("Cargo.toml"))
+        .map_or(false, |manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+}
+
+/// Format the code with rustfmt, if available.
+fn format_code(code: &str) -> String {
+    let command = Command::new("rustfmt")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = command {
+        let written = child.stdin.as_mut().map(|stdin| stdin.write_all(code.as_bytes()).is_ok()) == Some(true);
+        if let Ok(output) = child.wait_with_output() {
+            if written && output.status.success() {
+                if let Ok(formatted) = String::from_utf8(output.stdout) {
+                    return formatted;
+                }
+            }
+        }
+    }
+    code.to_string()
+}
+
+fn tokens_to_string<T: ToTokens>(value: &T) -> String {
+    let mut tokens = Tokens::new();
+    value.to_tokens(&mut tokens);
+    tokens.as_str().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineFinder, find_ident};
+
+    #[test]
+    fn idents() {
+        assert_eq!(find_ident("let label = gtk::Label::new(None);", "label"), Some(4));
+        assert_eq!(find_ident("let label2 = self.label_text;", "label"), None);
+        assert_eq!(find_ident("impl Widget for Winner {", "Widget for Win"), None);
+        assert_eq!(find_ident("impl Widget for Win {", "Widget for Win"), Some(5));
+    }
+
+    #[test]
+    fn lines() {
+        let source = "
+            impl Widget for Win {
+                view! {
+                    gtk::Window {
+                        gtk::Box {
+                            gtk::Button {
+                                label: \"gtk::Label\",
+                            },
+                            gtk::Label {
+                            },
+                            gtk::Button {
+                            },
+                        },
+                    }
+                }
+            }";
+        let mut finder = LineFinder::new("src/main.rs", source, 2);
+        assert_eq!(finder.find("gtk::Window"), Some("src/main.rs:4".to_string()));
+        assert_eq!(finder.find("gtk::Box"), Some("src/main.rs:5".to_string()));
+        assert_eq!(finder.find("gtk::Button"), Some("src/main.rs:6".to_string()));
+        // The search continues after the previous widget, even when it is on the same line.
+        assert_eq!(finder.find("gtk::Label"), Some("src/main.rs:7".to_string()));
+        assert_eq!(finder.find("gtk::Button"), Some("src/main.rs:11".to_string()));
+        assert_eq!(finder.find("gtk::Window"), None);
+
+        let glade = "<object class=\"GtkWindow\">\n  <object class=\"GtkButton\" id=\"button\">";
+        let mut finder = LineFinder::new("src/win.glade", glade, 0);
+        assert_eq!(finder.find("gtk::Button"), Some("src/win.glade:2".to_string()));
+    }
+}
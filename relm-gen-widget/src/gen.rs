@@ -20,22 +20,33 @@
  */
 
 use std::collections::HashMap;
+use std::mem;
 
 use quote::Tokens;
-use syn::{Expr, Generics, Ident, Path, Ty, parse_path};
+use syn::{Delimited, Expr, ExprKind, Generics, Ident, Pat, Path, Token, TokenTree, Ty, parse_expr, parse_path,
+    parse_token_trees};
 use syn::fold::Folder;
+use syn::visit::{Visitor, walk_pat};
 
+use error::{Error, Result};
 use parser::{
+    Condition,
     Event,
+    EventValue,
+    ForLoop,
     GtkWidget,
     RelmWidget,
     Widget,
 };
+use parser::BranchCondition::{Arm, Else, If, IfLet};
 use parser::EventValue::{CurrentWidget, ForeignWidget};
+use parser::EventValueReturn;
 use parser::EventValueReturn::{CallReturn, Return, WithoutReturn};
 use parser::EitherWidget::{Gtk, Relm};
-use transformer::Transformer;
-use super::{Driver, MODEL_IDENT};
+use reload::reloadable_widgets;
+use transformer::{SelfTransformer, Transformer};
+use walker::calls_self_method;
+use super::{Driver, MODEL_IDENT, WidgetOptions, block_to_impl_item};
 
 use self::WidgetType::*;
 use self::WithParentheses::{WithParens, WithoutParens};
@@ -51,7 +62,7 @@ macro_rules! set_container {
                     else {
                         "#[container]".to_string()
                     };
-                panic!("Cannot use the {} attribute twice in the same widget", attribute);
+                $_self.error(format!("cannot use the {} attribute twice in the same widget", attribute));
             }
             $_self.relm_widgets.insert($widget_name.clone(), $widget_type.clone());
             $_self.container_names.insert(container_type.clone(), ($widget_name.clone(), $widget_type.clone()));
@@ -59,6 +70,14 @@ macro_rules! set_container {
     };
 }
 
+/// Code to create and update a child of a for loop or of a condition.
+struct DynamicChild {
+    child_type: Tokens,
+    create: Tokens,
+    root: Tokens,
+    update: Tokens,
+}
+
 #[derive(PartialEq)]
 enum WithParentheses {
     WithParens,
@@ -71,10 +90,13 @@ enum WidgetType {
     IsRelm,
 }
 
-pub fn gen(name: &Ident, widget: &Widget, driver: &mut Driver) -> (Tokens, HashMap<Ident, Path>, Tokens)
+pub fn gen(name: &Ident, widget: &Widget, driver: &mut Driver) -> Result<(Tokens, HashMap<Ident, Path>, Tokens)>
 {
     let mut generator = Generator::new(driver);
     let widget_tokens = generator.widget(widget, None, IsGtk);
+    if let Some(error) = generator.error.take() {
+        return Err(error);
+    }
     let driver = generator.driver.take().expect("driver");
     let idents: Vec<_> = driver.widgets.keys().collect();
     let root_widget_name = &driver.root_widget.as_ref().expect("root_widget is None");
@@ -86,52 +108,139 @@ pub fn gen(name: &Ident, widget: &Widget, driver: &mut Driver) -> (Tokens, HashM
     let widget_names2 = widget_names1;
     let events = &generator.events;
     let properties = &generator.properties;
+    let deferred_properties = &generator.deferred_properties;
     let model_ident = Ident::new(MODEL_IDENT);
+    let dynamic_names1: Vec<_> = driver.dynamic_fields.iter().map(|&(ref name, _)| name).collect();
+    let dynamic_names1 = &dynamic_names1;
+    let dynamic_names2 = dynamic_names1;
+    let relm_field =
+        if dynamic_names1.is_empty() {
+            quote! {}
+        }
+        else {
+            quote! {
+                __relm: relm.clone(),
+            }
+        };
+    let widget_struct = quote! {
+        #name {
+            #root_widget_name: #root_widget_name,
+            #(#widget_names1: #widget_names2,)*
+            #(#dynamic_names1: #dynamic_names2,)*
+            #relm_field
+            model: #model_ident,
+        }
+    };
+    // The properties calling helper methods need the widget struct.
+    let widget_struct =
+        if deferred_properties.is_empty() {
+            widget_struct
+        }
+        else {
+            quote! {{
+                let __relm_self = #widget_struct;
+                #(#deferred_properties)*
+                __relm_self
+            }}
+        };
+    let reload = gen_reload(widget, driver.view_file.as_ref(), &driver.options);
     let code = quote! {
         #widget_tokens
 
         #(#events)*
         #(#properties)*
+        #reload
 
-        #name {
-            #root_widget_name: #root_widget_name,
-            #(#widget_names1: #widget_names2,)*
-            model: #model_ident,
-        }
+        #widget_struct
     };
-    let container_impl = gen_container_impl(&generator, widget, driver.generic_types.as_ref().expect("generic types"));
-    (code, generator.relm_widgets, container_impl)
+    let generic_types = driver.generic_types.as_ref().expect("generic types");
+    let container_impl = gen_container_impl(&generator, widget, generic_types)?;
+    Ok((code, generator.relm_widgets, container_impl))
+}
+
+/*
+ * Watch the view file to reload its static properties in debug builds.
+ * The include_str!() also makes cargo rebuild the crate when the view file changes.
+ */
+fn gen_reload(widget: &Widget, view_file: Option<&String>, options: &WidgetOptions) -> Tokens {
+    if let Some(view_file) = view_file {
+        let relm_crate = &options.crate_path;
+        let gtk_crate = &options.gtk_path;
+        let widgets = reloadable_widgets(widget);
+        let names = widgets.iter().map(|&(name, _)| {
+            match name {
+                Some(name) => {
+                    let name = name.as_ref();
+                    quote! { Some(#name) }
+                },
+                None => quote! { None },
+            }
+        });
+        let upcasts: Vec<Tokens> = widgets.iter()
+            .map(|&(_, widget)| {
+                let name = &widget.name;
+                quote! { #gtk_crate::Cast::upcast(#name.clone()) }
+            })
+            .collect();
+        quote! {
+            #relm_crate::watch_view_file(#view_file, include_str!(#view_file),
+                vec![#((#names, #upcasts),)*]);
+        }
+    }
+    else {
+        quote! {}
+    }
 }
 
 struct Generator<'a> {
     container_names: HashMap<Option<String>, (Ident, Path)>,
+    deferred_properties: Vec<Tokens>,
     driver: Option<&'a mut Driver>,
+    error: Option<Error>,
     events: Vec<Tokens>,
+    gtk_crate: Tokens,
+    loop_bindings: Vec<Ident>, // Variables of the pattern of the for loop being generated.
     properties: Vec<Tokens>,
+    relm_crate: Tokens,
     relm_widgets: HashMap<Ident, Path>,
     widget_names: Vec<Ident>,
 }
 
 impl<'a> Generator<'a> {
     fn new(driver: &'a mut Driver) -> Self {
+        let gtk_crate = driver.options.gtk_path.clone();
+        let relm_crate = driver.options.crate_path.clone();
         Generator {
             container_names: HashMap::new(),
+            deferred_properties: vec![],
             driver: Some(driver),
+            error: None,
             events: vec![],
+            gtk_crate,
+            loop_bindings: vec![],
             properties: vec![],
+            relm_crate,
             relm_widgets: HashMap::new(),
             widget_names: vec![],
         }
     }
 
+    /// Record an error, which is reported after the generation instead of the generated code.
+    fn error(&mut self, message: String) {
+        if self.error.is_none() {
+            self.error = Some(Error::new(message));
+        }
+    }
+
     fn add_child_or_show_all(&mut self, widget: &Widget, parent: Option<&Ident>, parent_widget_type: WidgetType)
         -> Tokens
     {
+        let gtk_crate = self.gtk_crate.clone();
         let widget_name = &widget.name;
         if let Some(name) = parent {
             if parent_widget_type == IsGtk {
                 quote! {
-                    ::gtk::ContainerExt::add(&#name, &#widget_name);
+                    #gtk_crate::ContainerExt::add(&#name, &#widget_name);
                 }
             }
             else {
@@ -156,9 +265,16 @@ impl<'a> Generator<'a> {
     }
 
     fn add_or_create_widget(&mut self, parent: Option<&Ident>, parent_widget_type: WidgetType, widget_name: &Ident,
-        widget_type_ident: &Path, init_parameters: &[Expr], is_container: bool) -> Tokens
+        widget_type_ident: &Path, init_parameters: &[Expr], param_names: &[Ident], is_container: bool) -> Tokens
     {
-        let init_parameters = gen_model_param(init_parameters, WithParens);
+        let relm_crate = self.relm_crate.clone();
+        let init_parameters =
+            if param_names.is_empty() {
+                gen_model_param(init_parameters, WithParens)
+            }
+            else {
+                gen_named_model_param(widget_type_ident, param_names, init_parameters, &relm_crate)
+            };
         if let Some(parent) = parent {
             if parent_widget_type == IsGtk {
                 let add_method =
@@ -170,7 +286,7 @@ impl<'a> Generator<'a> {
                     };
                 quote! {
                     let #widget_name = {
-                        ::relm::ContainerWidget::#add_method::<#widget_type_ident, _>(&#parent, &relm,
+                        #relm_crate::ContainerWidget::#add_method::<#widget_type_ident, _>(&#parent, &relm,
                             #init_parameters)
                     };
                 }
@@ -186,7 +302,7 @@ impl<'a> Generator<'a> {
         else {
             let driver = self.driver.as_mut().expect("driver");
             driver.root_widget_type = Some(quote! {
-                <#widget_type_ident as ::relm::Widget>::Root
+                <#widget_type_ident as #relm_crate::Widget>::Root
             });
             driver.root_widget = Some(widget_name.clone());
             driver.root_widget_expr = Some(quote! {
@@ -194,40 +310,106 @@ impl<'a> Generator<'a> {
             });
             if is_container {
                 quote! {
-                    let #widget_name = ::relm::create_container::<#widget_type_ident, _>(&relm, #init_parameters);
+                    let #widget_name = #relm_crate::create_container::<#widget_type_ident, _>(&relm, #init_parameters);
                 }
             }
             else {
                 quote! {
-                    let #widget_name = ::relm::create_component::<#widget_type_ident, _>(&relm, #init_parameters);
+                    let #widget_name = #relm_crate::create_component::<#widget_type_ident, _>(&relm, #init_parameters);
                 }
             }
         }
     }
 
+    /*
+     * Generate the widget-to-model direction of the two-way bindings: a method assigning the
+     * property to the model variable, called when the property changes.
+     * The model-to-widget direction is added by the Adder in this method, like in update().
+     * When the change comes from the model, the component is busy, so the notification is ignored.
+     */
+    fn collect_bindings(&mut self, widget: &Widget, gtk_widget: &GtkWidget) {
+        let relm_crate = self.relm_crate.clone();
+        let widget_name = &widget.name;
+        let widget_type = &widget.typ;
+        for (property, value) in &gtk_widget.bindings {
+            let method = Ident::new(format!("__relm_bind_{}_{}", widget_name, property));
+            let getter = Ident::new(format!("get_{}", property));
+            let property_name = property.replace('_', "-");
+            self.events.push(quote! {{
+                let relm = relm.clone();
+                #relm_crate::connect_notify(&#widget_name, #property_name, move |widget| {
+                    let _ = relm.with_component(|component| component.#method(widget));
+                });
+            }});
+            let driver = self.driver.as_mut().expect("driver");
+            driver.other_methods.push(block_to_impl_item(quote! {
+                fn #method(&mut self, widget: &#widget_type) {
+                    #value = #relm_crate::FromWidgetValue::from_widget_value(widget.#getter());
+                }
+            }));
+        }
+    }
+
+    /*
+     * Generate the GBindings between the properties of two widgets.
+     * They are created with the events, after every widget is created, so that the source widget
+     * can be declared after the target widget.
+     */
+    fn collect_property_bindings(&mut self, widget: &Widget, gtk_widget: &GtkWidget) {
+        let relm_crate = self.relm_crate.clone();
+        let widget_name = &widget.name;
+        for (property, binding) in &gtk_widget.property_bindings {
+            let property = property.replace('_', "-");
+            let source = &binding.source;
+            let source_property = binding.source_property.replace('_', "-");
+            let bind =
+                if let Some(ref transform) = binding.transform {
+                    quote! {
+                        #relm_crate::bind_property_transform(&#source, #source_property, &#widget_name, #property,
+                            #transform);
+                    }
+                }
+                else {
+                    let invert = binding.invert;
+                    quote! {
+                        #relm_crate::bind_property(&#source, #source_property, &#widget_name, #property, #invert);
+                    }
+                };
+            self.events.push(bind);
+        }
+    }
+
     fn collect_event(&mut self, widget_name: &Ident, name: &str, event: &Event) {
         let event_ident = Ident::new(format!("connect_{}", name));
         let event_params: Vec<_> = event.params.iter().map(|ident| Ident::new(ident.as_ref())).collect();
-        let shared_values = gen_shared_values(&event.shared_values);
+        let relm_crate = self.relm_crate.clone();
+        let shared_values = gen_shared_values(&event.shared_values, &relm_crate);
+        let loop_clones = gen_loop_clones(&self.loop_bindings, &event.value);
         let metadata = gen_event_metadata(event);
         let connect =
             match event.value {
                 CurrentWidget(WithoutReturn(ref event_value)) => quote! {{
                     #shared_values
-                    connect!(relm, #widget_name, #event_ident(#(#event_params),*), #metadata #event_value);
+                    #loop_clones
+                    #relm_crate::connect!(relm, #widget_name, #event_ident(#(#event_params),*), #metadata #event_value);
                 }},
                 ForeignWidget(ref foreign_widget_name, WithoutReturn(ref event_value)) => quote! {{
                     #shared_values
-                    connect!(#widget_name, #event_ident(#(#event_params),*), #foreign_widget_name, #event_value);
+                    #loop_clones
+                    #relm_crate::connect!(#widget_name, #event_ident(#(#event_params),*), #foreign_widget_name,
+                                          #event_value);
                 }},
                 CurrentWidget(Return(ref event_value, ref return_value)) => quote! {{
                     #shared_values
-                    connect!(relm, #widget_name, #event_ident(#(#event_params),*), return (#event_value, #return_value));
+                    #loop_clones
+                    #relm_crate::connect!(relm, #widget_name, #event_ident(#(#event_params),*),
+                                          return (#event_value, #return_value));
                 }},
                 ForeignWidget(_, Return(_, _)) | ForeignWidget(_, CallReturn(_)) => unreachable!(),
                 CurrentWidget(CallReturn(ref func)) => quote! {{
                     #shared_values
-                    connect!(relm, #widget_name, #event_ident(#(#event_params),*), #metadata #func);
+                    #loop_clones
+                    #relm_crate::connect!(relm, #widget_name, #event_ident(#(#event_params),*), #metadata #func);
                 }},
 
             };
@@ -246,6 +428,7 @@ impl<'a> Generator<'a> {
     }
 
     fn collect_relm_events(&mut self, widget: &Widget, relm_widget: &RelmWidget) {
+        let relm_crate = self.relm_crate.clone();
         let widget_name = &widget.name;
         for (name, widget_events) in &relm_widget.events {
             let event_ident = Ident::new(name.as_ref());
@@ -262,14 +445,17 @@ impl<'a> Generator<'a> {
                             (#(#event_params),*)
                         }
                     };
+                let loop_clones = gen_loop_clones(&self.loop_bindings, &event.value);
                 let metadata = gen_event_metadata(event);
                 let connect =
                     match event.value {
                         CurrentWidget(WithoutReturn(ref event_value)) => quote! {{
-                            connect!(#widget_name@#event_ident #params, relm, #metadata #event_value);
+                            #loop_clones
+                            #relm_crate::connect!(#widget_name@#event_ident #params, relm, #metadata #event_value);
                         }},
                         ForeignWidget(ref foreign_widget_name, WithoutReturn(ref event_value)) => quote! {{
-                            connect!(#widget_name@#event_ident #params, #foreign_widget_name,
+                            #loop_clones
+                            #relm_crate::connect!(#widget_name@#event_ident #params, #foreign_widget_name,
                                      #metadata #event_value);
                         }},
                         CurrentWidget(Return(_, _)) | CurrentWidget(CallReturn(_)) | ForeignWidget(_, Return(_, _)) |
@@ -288,13 +474,319 @@ impl<'a> Generator<'a> {
         }
     }
 
-    fn gtk_set_prop_calls(&self, widget: &Widget, ident: Tokens) -> (Vec<Tokens>, Vec<Tokens>) {
+    /*
+     * Generate a method creating and destroying the widget of the active branch of a condition.
+     * This method is called in view() and in update() when the model variables used by the
+     * condition are modified.
+     */
+    fn condition(&mut self, condition: &Condition, parent: &Ident, parent_type: &Path,
+        parent_widget_type: WidgetType, position: Tokens) -> Tokens
+    {
+        let gtk_crate = self.gtk_crate.clone();
+        let relm_crate = self.relm_crate.clone();
+        let mut remover = Transformer::new(MODEL_IDENT);
+        let mut bodies = vec![];
+        for (index, branch) in condition.branches.iter().enumerate() {
+            let body =
+                if let Some(ref child) = branch.child {
+                    let child_name = &child.name;
+                    let DynamicChild { child_type, create, root, update } =
+                        self.dynamic_child(child, parent_widget_type);
+                    quote! {
+                        if __relm_branch.is_active(#index) {
+                            let #child_name = __relm_branch.get::<#child_type>();
+                            #update
+                        }
+                        else {
+                            __relm_branch.clear();
+                            #create
+                            let __relm_root = #gtk_crate::Cast::upcast(#root);
+                            __relm_branch.set(#index, __relm_root, #child_name);
+                        }
+                    }
+                }
+                else {
+                    quote! {
+                        __relm_branch.clear();
+                    }
+                };
+            bodies.push(body);
+        }
+
+        let body =
+            if let Some(ref matched) = condition.matched {
+                let matched = remover.fold_expr(matched.clone());
+                let arms = condition.branches.iter().zip(bodies).map(|(branch, body)| {
+                    match branch.condition {
+                        Arm(ref pattern, Some(ref guard)) => {
+                            let guard = remover.fold_expr(guard.clone());
+                            quote! { #pattern if #guard => { #body }, }
+                        },
+                        Arm(ref pattern, None) => quote! { #pattern => { #body }, },
+                        Else | If(_) | IfLet(_, _) => unreachable!(),
+                    }
+                });
+                let arms: Vec<_> = arms.collect();
+                quote! {
+                    match #matched {
+                        #(#arms)*
+                    }
+                }
+            }
+            else {
+                let mut tokens = Tokens::new();
+                let mut has_else = false;
+                for (branch, body) in condition.branches.iter().zip(bodies) {
+                    if !tokens.as_str().is_empty() {
+                        tokens.append("else");
+                    }
+                    let branch_tokens =
+                        match branch.condition {
+                            Else => {
+                                has_else = true;
+                                quote! { { #body } }
+                            },
+                            If(ref expr) => {
+                                let expr = remover.fold_expr(expr.clone());
+                                quote! { if #expr { #body } }
+                            },
+                            IfLet(ref pattern, ref expr) => {
+                                let expr = remover.fold_expr(expr.clone());
+                                quote! { if let #pattern = #expr { #body } }
+                            },
+                            Arm(_, _) => unreachable!(),
+                        };
+                    tokens.append_all(&[branch_tokens]);
+                }
+                if !has_else {
+                    tokens.append_all(&[quote! {
+                        else {
+                            __relm_branch.clear();
+                        }
+                    }]);
+                }
+                tokens
+            };
+
+        let name = &condition.name;
+        let container_ident = Ident::new("__relm_container");
+        let container_type = gen_container_type(parent_type, parent_widget_type, &self.relm_crate);
+        let model_ident = Ident::new(MODEL_IDENT);
+        let driver = self.driver.as_mut().expect("driver");
+        driver.dynamic_fields.push((name.clone(), quote! { #relm_crate::Branch }));
+        driver.dynamic_methods.push(quote! {
+            #[allow(unused_variables)]
+            fn #name(relm: &#relm_crate::Relm<Self>, #container_ident: &#container_type,
+                __relm_branch: &mut #relm_crate::Branch, #model_ident: &<Self as #relm_crate::Update>::Model)
+            {
+                #body
+            }
+        });
+
+        quote! {
+            let mut #name = #relm_crate::Branch::new(#position);
+            Self::#name(relm, &#parent, &mut #name, &#model_ident);
+        }
+    }
+
+    /*
+     * Generate the loops and the conditions of a container in the order of their declaration.
+     * Their children are placed right after the static child declared before them and after the
+     * children of the loops and conditions declared between this static child and them.
+     */
+    fn dynamic_children(&mut self, widget: &Widget, parent_type: &Path, parent_widget_type: WidgetType) -> Tokens {
+        let gtk_crate = self.gtk_crate.clone();
+        let relm_crate = self.relm_crate.clone();
+        let mut positions: Vec<_> = widget.loops.iter().map(|for_loop| (for_loop.position, &for_loop.name))
+            .chain(widget.conditions.iter().map(|condition| (condition.position, &condition.name)))
+            .collect();
+        positions.sort();
+        let mut tokens = quote! {};
+        for (index, &(position, name)) in positions.iter().enumerate() {
+            let static_count = position - index;
+            let previous =
+                match static_count.checked_sub(1).map(|index| &widget.children[index]) {
+                    Some(child) => {
+                        let root = gen_widget_root(child);
+                        quote! { Some(#gtk_crate::Cast::upcast(#root)) }
+                    },
+                    None => quote! { None },
+                };
+            // The loops and conditions declared after the same static child.
+            let preceding: Vec<_> = positions[..index].iter().enumerate()
+                .filter(|&(other_index, &(other_position, _))| other_position - other_index == static_count)
+                .map(|(_, &(_, name))| name)
+                .collect();
+            let position = quote! {
+                #relm_crate::DynamicPosition::new(#previous, &[#(#preceding.position()),*])
+            };
+            let dynamic =
+                if let Some(for_loop) = widget.loops.iter().find(|for_loop| for_loop.name == *name) {
+                    self.for_loop(for_loop, &widget.name, parent_type, parent_widget_type, position)
+                }
+                else {
+                    let condition = widget.conditions.iter().find(|condition| condition.name == *name)
+                        .expect("condition");
+                    self.condition(condition, &widget.name, parent_type, parent_widget_type, position)
+                };
+            tokens = quote! {
+                #tokens
+                #dynamic
+            };
+        }
+        tokens
+    }
+
+    /*
+     * Generate the code to create a child of a for loop or of a condition in the __relm_container
+     * container and the code to update its properties.
+     */
+    fn dynamic_child(&mut self, child: &Widget, parent_widget_type: WidgetType) -> DynamicChild {
+        let child_name = &child.name;
+        let container_ident = Ident::new("__relm_container");
+
+        // These widgets are not fields of the widget struct and their events and properties are
+        // set when they are created.
+        let events = mem::replace(&mut self.events, vec![]);
+        let properties = mem::replace(&mut self.properties, vec![]);
+        let relm_widgets = self.relm_widgets.clone();
+        let container_count = self.container_names.len();
+        let dynamic_count = self.driver.as_ref().expect("driver").dynamic_fields.len();
+        let other_methods_count = self.driver.as_ref().expect("driver").other_methods.len();
+        let deferred_count = self.deferred_properties.len();
+        let widget_names_len = self.widget_names.len();
+        let widget = self.widget(child, Some(&container_ident), parent_widget_type);
+        if self.driver.as_ref().expect("driver").dynamic_fields.len() != dynamic_count {
+            self.error("nested for loops and conditions are not supported in view! macro".to_string());
+        }
+        if self.driver.as_ref().expect("driver").other_methods.len() != other_methods_count {
+            self.error("bindings are not supported in for loops and conditions in view! macro".to_string());
+        }
+        let child_events = mem::replace(&mut self.events, events);
+        let child_properties = mem::replace(&mut self.properties, properties);
+        self.relm_widgets = relm_widgets;
+        self.widget_names.truncate(widget_names_len);
+        if self.container_names.len() != container_count {
+            self.error("cannot use the #[container] attribute in a for loop or a condition".to_string());
+        }
+
+        let child_ident = quote! { #child_name };
+        let (child_type, root, update) =
+            match child.widget {
+                Gtk(_) => {
+                    let (properties, visible_properties) = self.gtk_set_prop_calls(child, child_ident);
+                    let child_type = &child.typ;
+                    (quote! { #child_type }, quote! { #child_name.clone() }, quote! {
+                        #(#properties)*
+                        #(#visible_properties)*
+                    })
+                },
+                Relm(ref relm_widget) => {
+                    let (properties, visible_properties) =
+                        self.gtk_set_prop_calls(child, quote! { #child_name.widget() });
+                    let messages = self.messages(child, relm_widget);
+                    let child_type = gen_relm_component_type(child.is_container, &child.typ, &self.relm_crate);
+                    (quote! { #child_type }, quote! { #child_name.widget().clone() }, quote! {
+                        #(#properties)*
+                        #(#visible_properties)*
+                        #messages
+                    })
+                },
+            };
+        if self.deferred_properties.len() != deferred_count {
+            self.error("calling a method on self is not supported in for loops and conditions in view! macro"
+                       .to_string());
+        }
+        DynamicChild {
+            child_type,
+            create: quote! {
+                #widget
+                #(#child_events)*
+                #(#child_properties)*
+            },
+            root,
+            update,
+        }
+    }
+
+    /*
+     * Generate a method creating, updating, reordering and destroying the children of a for loop
+     * so that they match the collection.
+     * This method is called in view() and in update() when the model variables used by the loop
+     * are modified.
+     */
+    fn for_loop(&mut self, for_loop: &ForLoop, parent: &Ident, parent_type: &Path, parent_widget_type: WidgetType,
+        position: Tokens) -> Tokens
+    {
+        let gtk_crate = self.gtk_crate.clone();
+        let relm_crate = self.relm_crate.clone();
+        let name = &for_loop.name;
+        let child_name = &for_loop.child.name;
+        let container_ident = Ident::new("__relm_container");
+        let model_ident = Ident::new(MODEL_IDENT);
+        let pattern = &for_loop.pattern;
+        // The events of the children are connected with move closures, which get a clone of the
+        // loop variables they use since these variables borrow the collection.
+        match pattern_bindings(pattern) {
+            Ok(bindings) => self.loop_bindings = bindings,
+            Err(error) => self.error(error.to_string()),
+        }
+        let DynamicChild { child_type, create, root, update } =
+            self.dynamic_child(&for_loop.child, parent_widget_type);
+        self.loop_bindings.clear();
+        let container_type = gen_container_type(parent_type, parent_widget_type, &self.relm_crate);
+        let mut remover = Transformer::new(MODEL_IDENT);
+        let collection = remover.fold_expr(for_loop.collection.clone());
+        let key = remover.fold_expr(for_loop.key.clone());
+
+        let driver = self.driver.as_mut().expect("driver");
+        driver.dynamic_fields.push((name.clone(), quote! { #relm_crate::LoopChildren<#child_type> }));
+        driver.dynamic_methods.push(quote! {
+            #[allow(unused_variables)]
+            fn #name(relm: &#relm_crate::Relm<Self>, #container_ident: &#container_type,
+                __relm_children: &mut #relm_crate::LoopChildren<#child_type>,
+                #model_ident: &<Self as #relm_crate::Update>::Model)
+            {
+                let mut __relm_index = 0;
+                for #pattern in &#collection {
+                    let __relm_key = &#key;
+                    if __relm_children.place(__relm_index, __relm_key) {
+                        let #child_name = __relm_children.get(__relm_index);
+                        #update
+                    }
+                    else {
+                        #create
+                        let __relm_root = #gtk_crate::Cast::upcast(#root);
+                        __relm_children.insert(__relm_index, __relm_key.clone(), __relm_root, #child_name);
+                    }
+                    __relm_index += 1;
+                }
+                __relm_children.truncate(__relm_index);
+            }
+        });
+
+        quote! {
+            let mut #name = #relm_crate::LoopChildren::new(#position);
+            Self::#name(relm, &#parent, &mut #name, &#model_ident);
+        }
+    }
+
+    fn gtk_set_prop_calls(&mut self, widget: &Widget, ident: Tokens) -> (Vec<Tokens>, Vec<Tokens>) {
         let mut properties = vec![];
         let mut visible_properties = vec![];
         for (key, value) in &widget.properties {
+            let property_func = Ident::new(format!("set_{}", key));
+            if calls_self_method(value) {
+                // A helper method can only be called once the widget struct is created.
+                let mut transformer = SelfTransformer::new("__relm_self");
+                let new_value = transformer.fold_expr(value.clone());
+                self.deferred_properties.push(quote! {
+                    __relm_self.#ident.#property_func(#new_value);
+                });
+                continue;
+            }
             let mut remover = Transformer::new(MODEL_IDENT);
             let new_value = remover.fold_expr(value.clone());
-            let property_func = Ident::new(format!("set_{}", key));
             let property = quote! {
                 #ident.#property_func(#new_value);
             };
@@ -320,12 +812,15 @@ impl<'a> Generator<'a> {
             self.relm_widgets.insert(widget_name.clone(), struct_name.clone());
         }
 
-        let construct_widget = gen_construct_widget(widget, gtk_widget);
+        let construct_widget = gen_construct_widget(widget, gtk_widget, &self.relm_crate, &self.gtk_crate);
         self.collect_events(widget, gtk_widget);
+        self.collect_bindings(widget, gtk_widget);
+        self.collect_property_bindings(widget, gtk_widget);
 
         let children: Vec<_> = widget.children.iter()
             .map(|child| self.widget(child, Some(widget_name), IsGtk))
             .collect();
+        let dynamic_children = self.dynamic_children(widget, struct_name, IsGtk);
 
         let add_child_or_show_all = self.add_child_or_show_all(widget, parent, parent_widget_type);
         let ident = quote! { #widget_name };
@@ -336,6 +831,7 @@ impl<'a> Generator<'a> {
             let #widget_name: #struct_name = #construct_widget;
             #(#properties)*
             #(#children)*
+            #dynamic_children
             #add_child_or_show_all
             #widget_name.show();
             #(#visible_properties)*
@@ -350,7 +846,7 @@ impl<'a> Generator<'a> {
         let widget_name = &widget.name;
         let widget_type_ident = &widget.typ;
         set_container!(self, widget, widget_name, widget_type_ident);
-        let relm_component_type = gen_relm_component_type(widget.is_container, widget_type_ident);
+        let relm_component_type = gen_relm_component_type(widget.is_container, widget_type_ident, &self.relm_crate);
         self.relm_widgets.insert(widget.name.clone(), relm_component_type);
 
         self.collect_relm_events(widget, relm_widget);
@@ -364,14 +860,18 @@ impl<'a> Generator<'a> {
         self.properties.append(&mut visible_properties);
 
         let add_or_create_widget = self.add_or_create_widget(
-            parent, parent_widget_type, widget_name, widget_type_ident, &widget.init_parameters, widget.is_container);
+            parent, parent_widget_type, widget_name, widget_type_ident, &widget.init_parameters,
+            &relm_widget.param_names, widget.is_container);
         let child_properties = gen_set_child_prop_calls(widget, parent, parent_widget_type, IsRelm);
         let messages = self.messages(widget, relm_widget);
 
+        let dynamic_children = self.dynamic_children(widget, widget_type_ident, IsRelm);
+
         quote! {
             #add_or_create_widget
             #messages
             #(#children)*
+            #dynamic_children
             #(#child_properties)*
         }
     }
@@ -399,7 +899,15 @@ impl<'a> Generator<'a> {
     }
 }
 
-fn gen_construct_widget(widget: &Widget, gtk_widget: &GtkWidget) -> Tokens {
+fn gen_widget_root(widget: &Widget) -> Tokens {
+    let name = &widget.name;
+    match widget.widget {
+        Gtk(_) => quote! { #name.clone() },
+        Relm(_) => quote! { #name.widget().clone() },
+    }
+}
+
+fn gen_construct_widget(widget: &Widget, gtk_widget: &GtkWidget, relm_crate: &Tokens, gtk_crate: &Tokens) -> Tokens {
     let struct_name = &widget.typ;
 
     let properties_count = gtk_widget.construct_properties.len() as u32;
@@ -410,13 +918,13 @@ fn gen_construct_widget(widget: &Widget, gtk_widget: &GtkWidget) -> Tokens {
         let value = remover.fold_expr(value.clone());
         let key = key.to_string();
         values.push(quote! {
-            ::gtk::ToValue::to_value(&#value)
+            #gtk_crate::ToValue::to_value(&#value)
         });
         let index = parameters.len();
         parameters.push(quote! {
-            ::relm::GParameter {
-                name: ::relm::ToGlibPtr::to_glib_full(#key),
-                value: ::std::ptr::read(::relm::ToGlibPtr::to_glib_none(&values[#index]).0),
+            #relm_crate::GParameter {
+                name: #relm_crate::ToGlibPtr::to_glib_full(#key),
+                value: ::std::ptr::read(#relm_crate::ToGlibPtr::to_glib_none(&values[#index]).0),
             }
         });
     }
@@ -436,20 +944,21 @@ fn gen_construct_widget(widget: &Widget, gtk_widget: &GtkWidget) -> Tokens {
     if widget.init_parameters.is_empty() {
         quote! {
             unsafe {
-                use gtk::StaticType;
-                use relm::{Downcast, FromGlibPtrNone};
-                let values: &[::gtk::Value] = &[#(#values),*];
+                use #gtk_crate::StaticType;
+                use #relm_crate::{Downcast, FromGlibPtrNone};
+                let values: &[#gtk_crate::Value] = &[#(#values),*];
                 let mut parameters = [#(#parameters),*];
-                ::gtk::Widget::from_glib_none(::relm::g_object_newv(
-                    ::relm::ToGlib::to_glib(&#struct_name::static_type()),
+                #gtk_crate::Widget::from_glib_none(#relm_crate::g_object_newv(
+                    #relm_crate::ToGlib::to_glib(&#struct_name::static_type()),
                     #properties_count, parameters.as_mut_ptr()) as *mut _)
                     .downcast_unchecked()
                 // TODO: use this new code when g_object_new_with_properties() is released.
                 /*let names: &[&str] = &[#(#names),*];
-                let values: &[&::gtk::ToValue] = &[#(#values),*];
-                ::gtk::Widget::from_glib_none(::relm::g_object_new_with_properties(#struct_name::static_type().to_glib(),
-                    #properties_count, ::relm::ToGlibPtr::to_glib_full(&names),
-                    ::relm::ToGlibPtr::to_glib_full(&values) as *mut _) as *mut _)
+                let values: &[&#gtk_crate::ToValue] = &[#(#values),*];
+                #gtk_crate::Widget::from_glib_none(#relm_crate::g_object_new_with_properties(
+                    #struct_name::static_type().to_glib(),
+                    #properties_count, #relm_crate::ToGlibPtr::to_glib_full(&names),
+                    #relm_crate::ToGlibPtr::to_glib_full(&values) as *mut _) as *mut _)
                 .downcast_unchecked()*/
             }
         }
@@ -462,6 +971,15 @@ fn gen_construct_widget(widget: &Widget, gtk_widget: &GtkWidget) -> Tokens {
     }
 }
 
+fn gen_container_type(parent_type: &Path, parent_widget_type: WidgetType, relm_crate: &Tokens) -> Tokens {
+    if parent_widget_type == IsGtk {
+        quote! { #parent_type }
+    }
+    else {
+        quote! { #relm_crate::ContainerComponent<#parent_type> }
+    }
+}
+
 fn gen_event_metadata(event: &Event) -> Tokens {
     if let CurrentWidget(CallReturn(_)) = event.value {
         quote! {
@@ -473,19 +991,19 @@ fn gen_event_metadata(event: &Event) -> Tokens {
     }
 }
 
-fn gen_shared_values(shared_values: &[Ident]) -> Tokens {
+fn gen_shared_values(shared_values: &[Ident], relm_crate: &Tokens) -> Tokens {
     let model_ident = Ident::new(MODEL_IDENT);
     let fields = shared_values.iter()
         .map(|ident| {
              let typ =
                  if ident == "relm" {
                      quote! {
-                         Relm<_>
+                         #relm_crate::Relm<_>
                      }
                  }
                  else {
                      quote! {
-                         Rc<_>
+                         ::std::rc::Rc<_>
                      }
                  };
              quote! {
@@ -497,62 +1015,152 @@ fn gen_shared_values(shared_values: &[Ident]) -> Tokens {
     }
 }
 
-fn gen_widget_ident(widget: &Widget) -> Tokens {
+/*
+ * Generate the clones of the loop variables used by an event, so that its closure does not borrow
+ * the collection of the loop.
+ */
+fn gen_loop_clones(loop_bindings: &[Ident], value: &EventValue) -> Tokens {
+    if loop_bindings.is_empty() {
+        return quote! {};
+    }
+    let mut tokens = Tokens::new();
+    match *value {
+        CurrentWidget(ref value) => event_value_tokens(value, &mut tokens),
+        ForeignWidget(ref foreign_widget, ref value) => {
+            tokens.append(foreign_widget.as_str());
+            event_value_tokens(value, &mut tokens);
+        },
+    }
+    let mut used_idents = vec![];
+    if let Ok(token_trees) = parse_token_trees(tokens.as_str()) {
+        collect_variables(&token_trees, &mut used_idents);
+    }
+    let clones = loop_bindings.iter()
+        .filter(|binding| used_idents.contains(binding))
+        .map(|binding| quote! {
+            let #binding = #binding.clone();
+        });
+    quote! {
+        #(#clones)*
+    }
+}
+
+fn event_value_tokens(value: &EventValueReturn, tokens: &mut Tokens) {
+    match *value {
+        CallReturn(ref func) => tokens.append(func.as_str()),
+        Return(ref value, ref return_value) => {
+            tokens.append(value.as_str());
+            tokens.append(return_value.as_str());
+        },
+        WithoutReturn(ref value) => tokens.append(value.as_str()),
+    }
+}
+
+/*
+ * Collect the identifiers that can be variables, i.e. those not following a `.`.
+ */
+fn collect_variables(token_trees: &[TokenTree], idents: &mut Vec<Ident>) {
+    let mut after_dot = false;
+    for token_tree in token_trees {
+        match *token_tree {
+            TokenTree::Token(Token::Ident(ref ident)) if !after_dot => idents.push(ident.clone()),
+            TokenTree::Delimited(Delimited { ref tts, .. }) => collect_variables(tts, idents),
+            _ => (),
+        }
+        after_dot = *token_tree == TokenTree::Token(Token::Dot);
+    }
+}
+
+/*
+ * Get the variables bound by the pattern of a for loop.
+ */
+fn pattern_bindings(pattern: &Tokens) -> Result<Vec<Ident>> {
+    let expr = parse_expr(&format!("for {} in () {{}}", pattern))
+        .map_err(|_| Error::new(format!("invalid pattern `{}` in the for loop in view! macro", pattern)))?;
+    let mut visitor = BindingVisitor {
+        bindings: vec![],
+    };
+    if let ExprKind::ForLoop(ref pat, _, _, _) = expr.node {
+        visitor.visit_pat(pat);
+    }
+    Ok(visitor.bindings)
+}
+
+struct BindingVisitor {
+    bindings: Vec<Ident>,
+}
+
+impl Visitor for BindingVisitor {
+    fn visit_pat(&mut self, pat: &Pat) {
+        if let Pat::Ident(_, ref ident, _) = *pat {
+            // An identifier starting with an uppercase letter is a unit struct or an enum variant.
+            if !ident.as_ref().starts_with(char::is_uppercase) {
+                self.bindings.push(ident.clone());
+            }
+        }
+        walk_pat(self, pat);
+    }
+}
+
+fn gen_widget_ident(widget: &Widget) -> Result<Tokens> {
     match widget.widget {
         Gtk(ref gtk_widget) => {
             if let Some(Ty::Path(_, Path { ref segments, .. })) = gtk_widget.relm_name {
                 let ident = &segments[0].ident;
-                quote! {
+                Ok(quote! {
                     #ident
-                }
+                })
             }
             else {
-                panic!("relm name should be a Path");
+                Err(Error::new("expected a type name, like `impl Widget for Win`, in #[widget]"))
             }
         },
         Relm(_) => {
             let path = &widget.typ;
-            quote! {
+            Ok(quote! {
                 #path
-            }
+            })
         },
     }
 }
 
-fn gen_widget_type(widget: &Widget) -> Tokens {
+fn gen_widget_type(widget: &Widget) -> Result<Tokens> {
     match widget.widget {
         Gtk(ref gtk_widget) => {
-            let ident = gtk_widget.relm_name.as_ref().unwrap();
-            quote! {
+            let ident = gtk_widget.relm_name.as_ref()
+                .ok_or_else(|| Error::new("expected a type name, like `impl Widget for Win`, in #[widget]"))?;
+            Ok(quote! {
                 #ident
-            }
+            })
         },
         Relm(_) => {
             let path = &widget.typ;
-            quote! {
+            Ok(quote! {
                 #path
-            }
+            })
         },
     }
 }
 
-fn gen_add_widget_method(container_names: &HashMap<Option<String>, (Ident, Path)>) -> Tokens {
+fn gen_add_widget_method(container_names: &HashMap<Option<String>, (Ident, Path)>, relm_crate: &Tokens,
+    gtk_crate: &Tokens) -> Tokens
+{
     if container_names.len() > 1 {
         let mut default_container = Tokens::new();
         let mut other_containers = Tokens::new();
         for (parent_id, &(ref name, _)) in container_names {
             if parent_id.is_none() {
                 default_container = quote! {
-                    ::gtk::ContainerExt::add(&container.container, widget.widget());
-                    ::gtk::Cast::upcast(container.container.clone())
+                    #gtk_crate::ContainerExt::add(&container.container, widget.widget());
+                    #gtk_crate::Cast::upcast(container.container.clone())
                 };
             }
             else {
                 if other_containers.as_str().is_empty() {
                     other_containers = quote! {
                         if WIDGET::parent_id() == Some(#parent_id) {
-                            ::gtk::ContainerExt::add(&container.containers.#name, widget.widget());
-                            ::gtk::Cast::upcast(container.containers.#name.clone())
+                            #gtk_crate::ContainerExt::add(&container.containers.#name, widget.widget());
+                            #gtk_crate::Cast::upcast(container.containers.#name.clone())
                         }
                     };
                 }
@@ -560,8 +1168,8 @@ fn gen_add_widget_method(container_names: &HashMap<Option<String>, (Ident, Path)
                     other_containers = quote! {
                         #other_containers
                         else if WIDGET::parent_id() == Some(#parent_id) {
-                            ::gtk::ContainerExt::add(&container.containers.#name, widget.widget());
-                            ::gtk::Cast::upcast(container.containers.#name.clone())
+                            #gtk_crate::ContainerExt::add(&container.containers.#name, widget.widget());
+                            #gtk_crate::Cast::upcast(container.containers.#name.clone())
                         }
                     };
                 }
@@ -575,8 +1183,8 @@ fn gen_add_widget_method(container_names: &HashMap<Option<String>, (Ident, Path)
             };
         }
         quote! {
-            fn add_widget<WIDGET: Widget>(container: &::relm::ContainerComponent<Self>,
-                widget: &::relm::Component<WIDGET>) -> gtk::Container
+            fn add_widget<WIDGET: #relm_crate::Widget>(container: &#relm_crate::ContainerComponent<Self>,
+                widget: &#relm_crate::Component<WIDGET>) -> #gtk_crate::Container
             {
                 #other_containers
                 #default_container
@@ -589,34 +1197,25 @@ fn gen_add_widget_method(container_names: &HashMap<Option<String>, (Ident, Path)
     }
 }
 
-fn gen_container_impl(generator: &Generator, widget: &Widget, generic_types: &Generics) -> Tokens {
+fn gen_container_impl(generator: &Generator, widget: &Widget, generic_types: &Generics) -> Result<Tokens> {
+    let relm_crate = &generator.relm_crate;
     let where_clause = gen_where_clause(generic_types);
-    let widget_type = gen_widget_type(widget);
+    let widget_type = gen_widget_type(widget)?;
     if generator.container_names.is_empty() {
-        quote! {
-        }
-    }
-    else if !generator.container_names.contains_key(&None) {
-        panic!("Use of #[container=\"name\"] attribute without the default #[container].");
+        Ok(quote! {
+        })
     }
-    else {
-        let mut container_type = None;
-        for (ident, &(_, ref typ)) in &generator.container_names {
-            if ident.is_none() {
-                container_type = Some(typ);
-            }
-        }
-        let typ = container_type.expect("container type");
-        let &(ref name, _) = generator.container_names.get(&None).expect("default container");
-        let add_widget_method = gen_add_widget_method(&generator.container_names);
+    else if let Some(&(ref name, ref typ)) = generator.container_names.get(&None) {
+        let add_widget_method = gen_add_widget_method(&generator.container_names, &generator.relm_crate,
+                                                      &generator.gtk_crate);
 
-        let widget_ident = gen_widget_ident(widget);
+        let widget_ident = gen_widget_ident(widget)?;
         let (containers, containers_type, other_containers_func) = gen_other_containers(&generator, &widget_ident);
 
-        quote! {
+        Ok(quote! {
             #containers
 
-            impl #generic_types ::relm::Container for #widget_type #where_clause {
+            impl #generic_types #relm_crate::Container for #widget_type #where_clause {
                 type Container = #typ;
                 type Containers = #containers_type;
 
@@ -628,7 +1227,10 @@ fn gen_container_impl(generator: &Generator, widget: &Widget, generic_types: &Ge
 
                 #add_widget_method
             }
-        }
+        })
+    }
+    else {
+        Err(Error::new("use of the #[container=\"name\"] attribute without the default #[container]"))
     }
 }
 
@@ -651,8 +1253,8 @@ fn gen_other_containers(generator: &Generator, widget_type: &Tokens) -> (Tokens,
                     // Relm widget
                     values.push(Ident::new(format!("{}.widget()", name)));
                     let original_ident = original_type.segments[0].ident.clone();
-                    original_type.segments[0].ident =
-                        Ident::new(format!("<{} as ::relm::Widget>::Root", original_ident));
+                    let root = format!("<{} as {}::Widget>::Root", original_ident, generator.relm_crate.as_str());
+                    original_type.segments[0].ident = Ident::new(root);
                     original_type
                 };
             types.push(typ);
@@ -684,6 +1286,29 @@ fn gen_other_containers(generator: &Generator, widget_type: &Tokens) -> (Tokens,
     }
 }
 
+/*
+ * Start from the default model param of the relm widget and set the named parameters, so that the
+ * missing parameters keep their default value.
+ * The fields of the model param of another widget are unknown here, so rustc reports the unknown
+ * names as missing fields.
+ */
+fn gen_named_model_param(widget_type: &Path, names: &[Ident], init_parameters: &[Expr], relm_crate: &Tokens)
+    -> Tokens
+{
+    let values: Vec<_> = init_parameters.iter()
+        .map(|param| {
+            let mut remover = Transformer::new(MODEL_IDENT);
+            remover.fold_expr(param.clone())
+        })
+        .collect();
+    quote! {{
+        let mut __relm_params: <#widget_type as #relm_crate::Update>::ModelParam =
+            ::std::default::Default::default();
+        #(__relm_params.#names = #values;)*
+        __relm_params
+    }}
+}
+
 fn gen_model_param(init_parameters: &[Expr], with_parens: WithParentheses) -> Tokens {
     let mut params = vec![];
     for param in init_parameters {
@@ -703,16 +1328,16 @@ fn gen_model_param(init_parameters: &[Expr], with_parens: WithParentheses) -> To
     }
 }
 
-fn gen_relm_component_type(is_container: bool, name: &Path) -> Path {
+fn gen_relm_component_type(is_container: bool, name: &Path, relm_crate: &Tokens) -> Path {
     let tokens =
         if is_container {
             quote! {
-                ::relm::ContainerComponent<#name>
+                #relm_crate::ContainerComponent<#name>
             }
         }
         else {
             quote! {
-                ::relm::Component<#name>
+                #relm_crate::Component<#name>
             }
         };
     parse_path(tokens.as_str()).expect("gen_relm_component_type is a Path")
@@ -766,3 +1391,60 @@ pub fn gen_where_clause(generics: &Generics) -> Tokens {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use syn::{Generics, Ident, parse_token_trees, parse_type};
+
+    use parser::EitherWidget::Gtk;
+    use parser::parse;
+    use super::gen;
+    use super::super::{Driver, WidgetOptions};
+
+    // Generate the method updating the children of the single for loop of the view.
+    fn loop_method(view: &str) -> String {
+        let mut widget = parse(&parse_token_trees(view).expect("tokens"), "gtk").expect("widget");
+        if let Gtk(ref mut gtk_widget) = widget.widget {
+            gtk_widget.relm_name = Some(parse_type("Win").expect("type"));
+        }
+        let mut driver = Driver::new(WidgetOptions::new());
+        driver.generic_types = Some(Generics::default());
+        gen(&Ident::new("Win"), &widget, &mut driver).expect("gen");
+        assert_eq!(driver.dynamic_methods.len(), 1);
+        driver.dynamic_methods[0].as_str().replace(' ', "")
+    }
+
+    #[test]
+    fn for_loop() {
+        let method = loop_method("gtk::Box {
+            for item in self.model.items, key: item.id {
+                gtk::Label {
+                    text: &item.text,
+                }
+            },
+        }");
+        assert!(method.contains("foritemin&__relm_model.items{let__relm_key=&item.id;"));
+        // An existing child with the key of the item is moved to the index of the item and updated.
+        assert!(method.contains("if__relm_children.place(__relm_index,__relm_key){"));
+        assert!(method.contains(".set_text(&item.text);"));
+        // Otherwise, a child is created and inserted at this index.
+        assert!(method.contains("__relm_children.insert(__relm_index,__relm_key.clone(),__relm_root,"));
+        // The children of the removed items are destroyed.
+        assert!(method.contains("__relm_children.truncate(__relm_index);"));
+    }
+
+    #[test]
+    fn for_loop_events() {
+        let method = loop_method("gtk::Box {
+            for (item, count) in self.model.items, key: item.id {
+                gtk::Button {
+                    clicked => Remove(item.id, self.model.count),
+                    label: &item.text,
+                }
+            },
+        }");
+        // Only the loop variables used by the event are cloned in the closure.
+        assert!(method.contains("letitem=item.clone();::relm::connect!("));
+        assert!(!method.contains("letcount=count.clone();"));
+    }
+}
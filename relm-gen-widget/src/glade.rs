@@ -0,0 +1,582 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Conversion of a Glade/GtkBuilder interface file to the syntax of the view! macro, and back.
+//!
+//! The first widget of the interface becomes the root widget:
+//!
+//!  * its properties become properties,
+//!  * its packing properties become child properties,
+//!  * its signals become events sending the message in the handler field (e.g. `Increment`, or
+//!    `(_, _) => (Quit, Inhibit(false))` for a signal with many parameters),
+//!  * its id becomes the name of the widget.
+//!
+//! A property value between braces, like `{&self.model.counter.to_string()}`, is used as is, which
+//! allows binding it to the model.
+
+use std::collections::HashMap;
+
+use quote::{Tokens, ToTokens};
+use syn::{Expr, ExprKind, Lit, UnOp};
+use xml::reader::{EventReader, XmlEvent};
+
+use error::{Error, Result};
+use parser::{Event, EventValueReturn, Widget};
+use parser::EitherWidget::{Gtk, Relm};
+use parser::EventValue::{CurrentWidget, ForeignWidget};
+use parser::EventValueReturn::{CallReturn, Return, WithoutReturn};
+
+/// Properties which are always strings, even when their value looks like another type.
+const STRING_PROPERTIES: &[&str] = &["label", "placeholder_text", "text", "title", "tooltip_markup", "tooltip_text"];
+
+/// Properties which are floating-point numbers, even when their value is written as an integer.
+const FLOAT_PROPERTIES: &[&str] = &[
+    "angle", "climb_rate", "fraction", "lower", "opacity", "page_increment", "page_size", "pulse_step",
+    "step_increment", "upper", "value", "xalign", "yalign",
+];
+
+/// Classes of the objects which are not widgets, like the models and the adjustments, which can be
+/// declared before the root widget.
+const NON_WIDGET_CLASSES: &[&str] = &[
+    "GtkAccelGroup", "GtkAdjustment", "GtkEntryBuffer", "GtkEntryCompletion", "GtkFileFilter", "GtkListStore",
+    "GtkSizeGroup", "GtkTextBuffer", "GtkTextTagTable", "GtkTreeModelFilter", "GtkTreeModelSort", "GtkTreeStore",
+];
+
+/// Properties whose values are the nicks of an enum, with the type of this enum in the gtk crate.
+const ENUM_PROPERTIES: &[(&str, &str)] = &[
+    ("halign", "Align"),
+    ("hscrollbar_policy", "PolicyType"),
+    ("input_purpose", "InputPurpose"),
+    ("justify", "Justification"),
+    ("orientation", "Orientation"),
+    ("pack_type", "PackType"),
+    ("relief", "ReliefStyle"),
+    ("selection_mode", "SelectionMode"),
+    ("shadow_type", "ShadowType"),
+    ("transition_type", "StackTransitionType"),
+    ("valign", "Align"),
+    ("vscrollbar_policy", "PolicyType"),
+    ("window_position", "WindowPosition"),
+    ("wrap_mode", "WrapMode"),
+];
+
+struct Element {
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+    name: String,
+    text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|value| value.as_str())
+    }
+
+    fn required_attribute(&self, name: &str) -> Result<&str> {
+        self.attribute(name)
+            .ok_or_else(|| Error::new(format!("expected a {} attribute on <{}> in Glade file", name, self.name)))
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> Box<Iterator<Item=&'a Element> + 'a> {
+        Box::new(self.children.iter().filter(move |child| child.name == name))
+    }
+}
+
+/// Convert the content of a Glade file to the tokens of a view! macro, where the gtk types are
+/// prefixed with `gtk_path`, like `::gtk`.
+pub fn glade_to_view(content: &str, gtk_path: &str) -> Result<String> {
+    let interface = parse_xml(content)?;
+    if interface.name != "interface" {
+        return Err(Error::new(format!("expected <interface> but found <{}> in Glade file", interface.name)));
+    }
+    let object = interface.children("object")
+        .find(|object| object.attribute("class").map(is_widget_class) == Some(true))
+        .ok_or_else(|| Error::new("expected a widget <object> in Glade file"))?;
+    let mut view = String::new();
+    object_to_view(object, None, gtk_path, &mut view)?;
+    Ok(view)
+}
+
+fn object_to_view(object: &Element, packing: Option<&Element>, gtk_path: &str, view: &mut String)
+    -> Result<()>
+{
+    let class = object.required_attribute("class")?;
+    if !class.starts_with("Gtk") {
+        return Err(Error::new(format!("unsupported class {} in Glade file: only the gtk widgets are supported",
+            class)));
+    }
+    if let Some(id) = object.attribute("id") {
+        view.push_str(&format!("#[name=\"{}\"]\n", id));
+    }
+    view.push_str(&format!("{}::{} {{\n", gtk_path, &class[3..]));
+    for property in object.children("property") {
+        let name = property_name(property, class)?;
+        if let Some(source) = property.attribute("bind-source") {
+            let source_property = property.attribute("bind-property").unwrap_or(&name).replace('-', "_");
+            let invert =
+                if property.attribute("bind-flags").map(|flags| flags.contains("invert-boolean")) == Some(true) {
+                    "!"
+                }
+                else {
+                    ""
+                };
+            view.push_str(&format!("{}: bind {}{}.{},\n", name, invert, source, source_property));
+        }
+        else {
+            view.push_str(&format!("{}: {},\n", name, property_value(&name, &property.text, gtk_path)));
+        }
+    }
+    if let Some(packing) = packing {
+        // The children are added in the order of the file, so their position is not needed.
+        let properties: Vec<_> = packing.children("property")
+            .filter(|property| property.attribute("name") != Some("position"))
+            .collect();
+        if !properties.is_empty() {
+            view.push_str("packing: {\n");
+            for property in properties {
+                let name = property_name(property, class)?;
+                view.push_str(&format!("{}: {},\n", name, property_value(&name, &property.text, gtk_path)));
+            }
+            view.push_str("},\n");
+        }
+    }
+    for signal in object.children("signal") {
+        let name = signal.required_attribute("name")?.replace('-', "_");
+        let handler = signal.required_attribute("handler")?;
+        if handler.contains("=>") {
+            view.push_str(&format!("{}{},\n", name, handler));
+        }
+        else {
+            view.push_str(&format!("{} => {},\n", name, handler));
+        }
+    }
+    for child in object.children("child") {
+        if child.attribute("internal-child").is_some() {
+            return Err(Error::new("internal children are not supported in Glade file"));
+        }
+        // A child without object is a placeholder.
+        if let Some(child_object) = child.children("object").next() {
+            object_to_view(child_object, child.children("packing").next(), gtk_path, view)?;
+        }
+    }
+    view.push_str("},\n");
+    Ok(())
+}
+
+fn parse_xml(content: &str) -> Result<Element> {
+    let mut stack: Vec<Element> = vec![];
+    for event in EventReader::from_str(content) {
+        let event = event.map_err(|error| Error::new(format!("invalid Glade file: {}", error)))?;
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                stack.push(Element {
+                    attributes: attributes.into_iter()
+                        .map(|attribute| (attribute.name.local_name, attribute.value))
+                        .collect(),
+                    children: vec![],
+                    name: name.local_name,
+                    text: String::new(),
+                });
+            },
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop().expect("element");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            },
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            },
+            _ => (),
+        }
+    }
+    Err(Error::new("empty Glade file"))
+}
+
+/// Get the name of a property, like `width_chars` for `width-chars`.
+fn property_name(property: &Element, class: &str) -> Result<String> {
+    let name = property.required_attribute("name")?;
+    let is_property = name.chars().next().map(|char| char.is_ascii_lowercase()) == Some(true) &&
+        name.chars().all(|char| char.is_ascii_lowercase() || char.is_digit(10) || char == '-' || char == '_');
+    if !is_property {
+        return Err(Error::new(format!("unknown property `{}` of {} in Glade file", name, class)));
+    }
+    Ok(name.replace('-', "_"))
+}
+
+/// Convert a property value to a rust expression.
+fn property_value(name: &str, value: &str, gtk_path: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('{') && value.ends_with('}') {
+        return value[1..value.len() - 1].to_string();
+    }
+    if STRING_PROPERTIES.contains(&name) {
+        return format!("{:?}", value);
+    }
+    if let Some(&(_, typ)) = ENUM_PROPERTIES.iter().find(|&&(property, _)| property == name) {
+        return format!("{}::{}::{}", gtk_path, typ, nick_to_variant(value));
+    }
+    match value {
+        "True" => "true".to_string(),
+        "False" => "false".to_string(),
+        _ if is_number(value) => {
+            if FLOAT_PROPERTIES.contains(&name) && !value.contains('.') {
+                format!("{}.0", value)
+            }
+            else {
+                value.to_string()
+            }
+        },
+        _ => format!("{:?}", value),
+    }
+}
+
+fn is_widget_class(class: &str) -> bool {
+    class.starts_with("Gtk") && !class.starts_with("GtkCellRenderer") && !NON_WIDGET_CLASSES.contains(&class)
+}
+
+fn is_number(value: &str) -> bool {
+    value.parse::<f64>().is_ok() && value.chars().all(|char| char.is_digit(10) || char == '.' || char == '-')
+}
+
+/// Convert an enum nick, like `word-char`, to the name of the variant, like `WordChar`.
+fn nick_to_variant(nick: &str) -> String {
+    nick.split(|char| char == '-' || char == '_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Convert a widget tree to a GtkBuilder interface.
+///
+/// The static properties are written as is, the other ones are written between braces, so that the
+/// interface can be converted back to the same widget tree.
+/// The loops, the conditions and the relm widgets cannot be represented and are written as comments.
+pub fn widget_to_glade(widget: &Widget) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<!-- Generated with relm-glade -->\n");
+    xml.push_str("<interface>\n");
+    xml.push_str("  <requires lib=\"gtk+\" version=\"3.20\"/>\n");
+    widget_to_object(widget, true, 1, &mut xml);
+    xml.push_str("</interface>\n");
+    xml
+}
+
+fn widget_to_object(widget: &Widget, root: bool, level: usize, xml: &mut String) {
+    let indent = "  ".repeat(level);
+    let gtk_widget =
+        match widget.widget {
+            Gtk(ref gtk_widget) => gtk_widget,
+            Relm(_) => {
+                xml.push_str(&format!("{}<!-- relm widget {} -->\n", indent, escape(&tokens_to_string(&widget.typ))));
+                return;
+            },
+        };
+    let class = format!("Gtk{}", widget.typ.segments.last().expect("widget type").ident);
+    if gtk_widget.save && !root {
+        xml.push_str(&format!("{}<object class=\"{}\" id=\"{}\">\n", indent, class, widget.name));
+    }
+    else {
+        xml.push_str(&format!("{}<object class=\"{}\">\n", indent, class));
+    }
+    let mut properties: Vec<(&str, &Expr)> = widget.properties.iter()
+        .map(|(name, value)| (name.as_str(), value))
+        .chain(gtk_widget.construct_properties.iter().map(|(name, value)| (name.as_ref(), value)))
+        .collect();
+    properties.sort_by_key(|&(name, _)| name);
+    for (name, value) in properties {
+        let value =
+            match gtk_widget.bindings.get(name) {
+                Some(model_variable) => format!("{{bind {}}}", tokens_to_string(model_variable)),
+                None => expr_to_value(value),
+            };
+        xml.push_str(&format!("{}  <property name=\"{}\">{}</property>\n", indent, name, escape(&value)));
+    }
+    let mut property_bindings: Vec<_> = gtk_widget.property_bindings.iter().collect();
+    property_bindings.sort_by_key(|&(name, _)| name);
+    for (name, binding) in property_bindings {
+        match binding.transform {
+            Some(ref transform) => {
+                let value = format!("{{bind {}.{} with {}}}", binding.source, binding.source_property,
+                    tokens_to_string(transform));
+                xml.push_str(&format!("{}  <property name=\"{}\">{}</property>\n", indent, name, escape(&value)));
+            },
+            None => {
+                let flags = if binding.invert { "sync-create|invert-boolean" } else { "sync-create" };
+                xml.push_str(&format!("{}  <property name=\"{}\" bind-source=\"{}\" bind-property=\"{}\" \
+                                      bind-flags=\"{}\"/>\n", indent, name, binding.source, binding.source_property,
+                                      flags));
+            },
+        }
+    }
+    let mut events: Vec<_> = gtk_widget.events.iter().collect();
+    events.sort_by_key(|&(name, _)| name);
+    for (name, event) in events {
+        xml.push_str(&format!("{}  <signal name=\"{}\" handler=\"{}\" swapped=\"no\"/>\n", indent,
+                              name.replace('_', "-"), escape(&event_handler(event))));
+    }
+    for child in &widget.children {
+        xml.push_str(&format!("{}  <child>\n", indent));
+        widget_to_object(child, false, level + 2, xml);
+        if !child.child_properties.is_empty() {
+            let mut child_properties: Vec<_> = child.child_properties.iter().collect();
+            child_properties.sort_by_key(|&(name, _)| name);
+            xml.push_str(&format!("{}    <packing>\n", indent));
+            for (name, value) in child_properties {
+                xml.push_str(&format!("{}      <property name=\"{}\">{}</property>\n", indent, name,
+                                      escape(&expr_to_value(value))));
+            }
+            xml.push_str(&format!("{}    </packing>\n", indent));
+        }
+        xml.push_str(&format!("{}  </child>\n", indent));
+    }
+    for for_loop in &widget.loops {
+        xml.push_str(&format!("{}  <!-- for {} in {} -->\n", indent, escape(for_loop.pattern.as_str()),
+                              escape(&tokens_to_string(&for_loop.collection))));
+    }
+    for condition in &widget.conditions {
+        xml.push_str(&format!("{}  <!-- condition {} -->\n", indent, condition.name));
+    }
+    xml.push_str(&format!("{}</object>\n", indent));
+}
+
+/// Convert an event to a signal handler, in the syntax expected by `glade_to_view()`.
+fn event_handler(event: &Event) -> String {
+    let value =
+        match event.value {
+            CurrentWidget(ref value) => event_value(value),
+            ForeignWidget(ref widget, ref value) => format!("{}@{}", widget.as_str(), event_value(value)),
+        };
+    let default_params = event.params.len() == 1 && event.params[0] == "_";
+    if default_params && event.shared_values.is_empty() {
+        value
+    }
+    else {
+        let params: Vec<_> = event.params.iter().map(|param| param.to_string()).collect();
+        let mut handler = format!("({})", params.join(", "));
+        if !event.shared_values.is_empty() {
+            let shared_values: Vec<_> = event.shared_values.iter().map(|value| value.to_string()).collect();
+            handler.push_str(&format!(" with ({})", shared_values.join(", ")));
+        }
+        format!("{} => {}", handler, value)
+    }
+}
+
+fn event_value(value: &EventValueReturn) -> String {
+    match *value {
+        CallReturn(ref value) => format!("return {}", value.as_str()),
+        Return(ref value, ref return_value) => format!("({}, {})", value.as_str(), return_value.as_str()),
+        WithoutReturn(ref value) => value.as_str().to_string(),
+    }
+}
+
+/// Convert an expression to a property value: the literals and the enum variants are converted to
+/// their GtkBuilder representation, the other expressions are written between braces.
+fn expr_to_value(expr: &Expr) -> String {
+    literal_value(expr).unwrap_or_else(|| format!("{{{}}}", tokens_to_string(expr)))
+}
+
+/// Get the GtkBuilder representation of a literal or an enum variant.
+pub fn literal_value(expr: &Expr) -> Option<String> {
+    match expr.node {
+        ExprKind::Lit(Lit::Bool(true)) => Some("True".to_string()),
+        ExprKind::Lit(Lit::Bool(false)) => Some("False".to_string()),
+        ExprKind::Lit(Lit::Float(ref value, _)) => Some(value.clone()),
+        ExprKind::Lit(Lit::Int(value, _)) => Some(value.to_string()),
+        ExprKind::Lit(Lit::Str(ref value, _)) => Some(value.clone()),
+        ExprKind::Unary(UnOp::Neg, ref expr) => {
+            if let ExprKind::Lit(_) = expr.node {
+                literal_value(expr).map(|value| format!("-{}", value))
+            }
+            else {
+                None
+            }
+        },
+        ExprKind::Path(None, ref path) => {
+            let variant = path.segments.last().expect("path segment").ident.as_ref();
+            if is_camel_case(variant) {
+                Some(variant_to_nick(variant))
+            }
+            else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn is_camel_case(ident: &str) -> bool {
+    ident.chars().next().map(char::is_uppercase) == Some(true) && !ident.contains('_') &&
+        ident.chars().any(char::is_lowercase)
+}
+
+fn tokens_to_string<T: ToTokens>(value: &T) -> String {
+    let mut tokens = Tokens::new();
+    value.to_tokens(&mut tokens);
+    tokens.as_str().to_string()
+}
+
+/// Convert the name of an enum variant, like `WordChar`, to its nick, like `word-char`.
+fn variant_to_nick(variant: &str) -> String {
+    let mut nick = String::new();
+    for char in variant.chars() {
+        if char.is_uppercase() && !nick.is_empty() {
+            nick.push('-');
+        }
+        nick.extend(char.to_lowercase());
+    }
+    nick
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_token_trees;
+
+    use parser::parse;
+    use super::{glade_to_view, widget_to_glade};
+
+    fn view(objects: &str) -> String {
+        glade_to_view(&format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<interface>\n{}</interface>\n",
+            objects), "gtk").expect("view")
+    }
+
+    fn error(content: &str) -> String {
+        glade_to_view(content, "gtk").expect_err("error").to_string()
+    }
+
+    #[test]
+    fn properties() {
+        let view = view(r#"
+            <object class="GtkLabel" id="label">
+              <property name="label">42</property>
+              <property name="visible">True</property>
+              <property name="xalign">0</property>
+              <property name="yalign">0.5</property>
+              <property name="width-chars">-1</property>
+              <property name="justify">center</property>
+              <property name="name">{&amp;self.model.name}</property>
+            </object>"#);
+        assert_eq!(view, "#[name=\"label\"]\ngtk::Label {\nlabel: \"42\",\nvisible: true,\nxalign: 0.0,\n\
+            yalign: 0.5,\nwidth_chars: -1,\njustify: gtk::Justification::Center,\nname: &self.model.name,\n},\n");
+    }
+
+    #[test]
+    fn gtk_path() {
+        let content = "<interface><object class=\"GtkBox\"><property name=\"orientation\">vertical</property>\
+            </object></interface>";
+        assert_eq!(glade_to_view(content, ":: gtk_fork").expect("view"),
+            ":: gtk_fork::Box {\norientation: :: gtk_fork::Orientation::Vertical,\n},\n");
+    }
+
+    #[test]
+    fn root_widget() {
+        let view = view(r#"
+            <object class="GtkAdjustment" id="adjustment">
+              <property name="upper">100</property>
+            </object>
+            <object class="GtkListStore" id="store"/>
+            <object class="GtkWindow">
+              <signal name="delete-event" handler="(_, _) => (Quit, Inhibit(false))" swapped="no"/>
+              <child>
+                <object class="GtkButton">
+                  <signal name="clicked" handler="Increment" swapped="no"/>
+                </object>
+                <packing>
+                  <property name="expand">False</property>
+                  <property name="position">0</property>
+                </packing>
+              </child>
+              <child>
+                <placeholder/>
+              </child>
+            </object>"#);
+        assert_eq!(view, "gtk::Window {\ndelete_event(_, _) => (Quit, Inhibit(false)),\ngtk::Button {\n\
+            packing: {\nexpand: false,\n},\nclicked => Increment,\n},\n},\n");
+    }
+
+    #[test]
+    fn round_trip() {
+        let view = view(r#"
+            <object class="GtkWindow">
+              <property name="title">Counter</property>
+              <signal name="delete-event" handler="(_, _) =&gt; (Quit, Inhibit(false))" swapped="no"/>
+              <child>
+                <object class="GtkBox">
+                  <property name="orientation">vertical</property>
+                  <child>
+                    <object class="GtkLabel" id="label">
+                      <property name="text">{&amp;self.model.counter.to_string()}</property>
+                      <property name="xalign">0.5</property>
+                    </object>
+                    <packing>
+                      <property name="expand">True</property>
+                    </packing>
+                  </child>
+                  <child>
+                    <object class="GtkButton">
+                      <property name="label">+</property>
+                      <property name="sensitive" bind-source="label" bind-property="visible" bind-flags="sync-create"/>
+                      <signal name="clicked" handler="Increment" swapped="no"/>
+                    </object>
+                  </child>
+                </object>
+              </child>
+            </object>"#);
+        let widget = parse(&parse_token_trees(&view).expect("tokens"), "gtk").expect("widget");
+        let glade = widget_to_glade(&widget);
+        assert!(glade.contains("<object class=\"GtkLabel\" id=\"label\">"), "{}", glade);
+        assert!(glade.contains("<property name=\"sensitive\" bind-source=\"label\" bind-property=\"visible\" \
+            bind-flags=\"sync-create\"/>"), "{}", glade);
+        assert!(glade.contains("<signal name=\"clicked\" handler=\"Increment\" swapped=\"no\"/>"), "{}", glade);
+        // The expressions are written back with the spaces between their tokens.
+        assert_eq!(glade_to_view(&glade, "gtk").expect("view").replace(' ', ""), view.replace(' ', ""));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("<interface><object class=\"GtkLabel\"><property name=\"Label\">x</property></object>\
+                         </interface>"), "unknown property `Label` of GtkLabel in Glade file");
+        assert_eq!(error("<interface><object class=\"GtkAdjustment\"/></interface>"),
+            "expected a widget <object> in Glade file");
+        assert_eq!(error("<interface><object class=\"GtkLabel\"><signal handler=\"Quit\"/></object></interface>"),
+            "expected a name attribute on <signal> in Glade file");
+        assert_eq!(error("<interface><object class=\"GtkDialog\"><child internal-child=\"vbox\"/></object>\
+                         </interface>"), "internal children are not supported in Glade file");
+        assert_eq!(error("<object class=\"GtkLabel\"/>"), "expected <interface> but found <object> in Glade file");
+        assert!(error("<interface>").starts_with("invalid Glade file"));
+    }
+}
@@ -21,8 +21,6 @@
 
 /*
  * TODO: automatically add the model() method with a () return type when it is not found?
- * FIXME: Doing model.text.push_str() will not cause a set_text() to be added.
- * TODO: think about conditions and loops (widget-list).
  */
 
 #![recursion_limit="128"]
@@ -30,58 +28,89 @@
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
+extern crate log;
+#[macro_use]
 extern crate quote;
 extern crate syn;
+extern crate xml;
 
 mod adder;
+mod cycle;
+mod error;
+mod expansion;
 mod gen;
+mod glade;
 mod parser;
+mod reload;
 mod transformer;
 mod walker;
 
 use std::collections::{HashMap, HashSet};
+use std::env;
 
-use adder::{Adder, Message, Property};
+use adder::{Adder, DynamicChildren, Message, Property};
+use cycle::warn_event_cycles;
+pub use error::{Error, Result};
+use expansion::{DEBUG_ENV_VAR, WidgetLocation, widget_locations, write_expansion};
 use gen::gen;
 pub use gen::gen_where_clause;
+use glade::widget_to_glade;
+use parser::BranchCondition::{Arm, Else, If, IfLet};
 use parser::EitherWidget::{Gtk, Relm};
-use parser::{Widget, parse};
-use quote::Tokens;
+use parser::{Widget, parse, parse_view_file, view_file};
+use quote::{Tokens, ToTokens};
+pub use reload::{StaticProperty, static_properties};
 use syn::{
     Delimited,
     FunctionRetTy,
     Generics,
     Ident,
     ImplItem,
+    Item,
+    ItemKind,
+    Lit,
     Mac,
     MethodSig,
     Path,
+    Token,
     TokenTree,
+    parse_crate,
+    parse_expr,
     parse_item,
+    parse_path,
+    parse_token_trees,
     parse_type,
 };
-use syn::FnArg::Captured;
+use syn::DelimToken::Paren;
+use syn::FnArg::{Captured, SelfRef};
 use syn::fold::Folder;
 use syn::ImplItemKind::{Const, Macro, Method, Type};
 use syn::ItemKind::Impl;
+use syn::Mutability::Immutable;
 use syn::Pat::Wild;
 use syn::Ty::{self, Tup};
 use syn::visit::Visitor;
-use walker::ModelVariableVisitor;
+use walker::{HelperMethods, ModelPath, ModelVariableVisitor, calls_self_method};
 
 const MODEL_IDENT: &str = "__relm_model";
 
-type MsgModelMap = HashMap<Ident, HashSet<Message>>;
-type PropertyModelMap = HashMap<Ident, HashSet<Property>>;
+type DynamicModelMap = HashMap<ModelPath, HashSet<DynamicChildren>>;
+type MsgModelMap = HashMap<ModelPath, HashSet<Message>>;
+type PropertyModelMap = HashMap<ModelPath, HashSet<Property>>;
 
 #[derive(Debug)]
 pub struct Driver {
     data_method: Option<ImplItem>,
     generic_types: Option<Generics>,
+    dynamic_fields: Vec<(Ident, Tokens)>, // Map loop ident to the type of its children collection.
+    dynamic_methods: Vec<Tokens>,
+    dynamic_model_map: Option<DynamicModelMap>,
+    helper_methods: HelperMethods,
     model_type: Option<ImplItem>,
     model_param_type: Option<ImplItem>,
     msg_model_map: Option<MsgModelMap>,
     msg_type: Option<ImplItem>,
+    options: WidgetOptions,
     other_methods: Vec<ImplItem>,
     properties_model_map: Option<PropertyModelMap>,
     root_method: Option<ImplItem>,
@@ -90,7 +119,9 @@ pub struct Driver {
     root_widget_expr: Option<Tokens>,
     root_widget_type: Option<Tokens>,
     update_method: Option<ImplItem>,
+    view_file: Option<String>,
     view_macro: Option<Mac>,
+    widget_locations: Vec<WidgetLocation>,
     widget_model_type: Option<Ty>,
     widget_msg_type: Option<Ty>,
     widget_parent_id: Option<String>,
@@ -100,6 +131,7 @@ pub struct Driver {
 struct View {
     container_impl: Tokens,
     item: ImplItem,
+    dynamic_model_map: DynamicModelMap,
     msg_model_map: MsgModelMap,
     properties_model_map: PropertyModelMap,
     relm_widgets: HashMap<Ident, Path>,
@@ -107,14 +139,19 @@ struct View {
 }
 
 impl Driver {
-    fn new() -> Self {
+    fn new(options: WidgetOptions) -> Self {
         Driver {
             data_method: None,
             generic_types: None,
+            dynamic_fields: vec![],
+            dynamic_methods: vec![],
+            dynamic_model_map: None,
+            helper_methods: HashMap::new(),
             model_type: None,
             model_param_type: None,
             msg_model_map: None,
             msg_type: None,
+            options,
             other_methods: vec![],
             properties_model_map: None,
             root_method: None,
@@ -123,7 +160,9 @@ impl Driver {
             root_widget_expr: None,
             root_widget_type: None,
             update_method: None,
+            view_file: None,
             view_macro: None,
+            widget_locations: vec![],
             widget_model_type: None,
             widget_msg_type: None,
             widget_parent_id: None,
@@ -133,16 +172,22 @@ impl Driver {
 
     fn add_set_property_to_method(&self, func: &mut ImplItem) {
         if let Method(_, ref mut block) = func.node {
+            let dynamic_map = self.dynamic_model_map.as_ref().expect("update method");
             let msg_map = self.msg_model_map.as_ref().expect("update method");
             let property_map = self.properties_model_map.as_ref().expect("update method");
-            let mut adder = Adder::new(property_map, msg_map);
+            let mut adder = Adder::new(property_map, msg_map, dynamic_map);
             *block = adder.fold_block(block.clone());
         }
     }
 
     fn add_widgets(&mut self, widget: &Widget, map: &PropertyModelMap) {
         // Only add widgets that are needed by the update() function.
-        let mut to_add = false;
+        // The containers of the loops and conditions are needed to update their children.
+        let mut to_add = !widget.loops.is_empty() || !widget.conditions.is_empty();
+        // The properties calling helper methods are set after the creation of the widget struct.
+        if widget.properties.values().any(calls_self_method) {
+            to_add = true;
+        }
         for values in map.values() {
             for value in values {
                 if value.widget_name == widget.name {
@@ -163,48 +208,72 @@ impl Driver {
     }
 
     fn create_struct(&self, typ: &Ty, relm_widgets: &HashMap<Ident, Path>, generics: &Generics) -> Tokens {
+        let relm_crate = &self.options.crate_path;
         let where_clause = gen_where_clause(generics);
         let widgets = self.widgets.iter().filter(|&(ident, _)| !relm_widgets.contains_key(ident));
         let (idents, types): (Vec<_>, Vec<_>) = widgets.unzip();
         let relm_idents = relm_widgets.keys();
         let relm_types = relm_widgets.values();
+        let (dynamic_idents, dynamic_types): (Vec<_>, Vec<_>) = self.dynamic_fields.iter()
+            .map(|&(ref ident, ref typ)| (ident, typ))
+            .unzip();
+        let relm_field =
+            if dynamic_idents.is_empty() {
+                quote! {}
+            }
+            else {
+                quote! {
+                    __relm: #relm_crate::Relm<#typ>,
+                }
+            };
         let widget_model_type = self.widget_model_type.as_ref().expect("missing model method");
         quote! {
             #[allow(dead_code, missing_docs)]
             pub struct #typ #where_clause {
                 #(#idents: #types,)*
                 #(#relm_idents: #relm_types,)*
+                #(#dynamic_idents: #dynamic_types,)*
+                #relm_field
                 model: #widget_model_type,
             }
         }
     }
 
-    fn gen_widget(&mut self, input: Tokens) -> Tokens {
+    fn gen_widget(&mut self, input: Tokens) -> Result<Tokens> {
         let source = input.to_string();
-        let mut ast = parse_item(&source).expect("parse_item() in gen_widget()");
+        let mut ast = parse_item(&source).map_err(Error::new)?;
         if let Impl(unsafety, polarity, generics, path, typ, items) = ast.node {
             self.generic_types = Some(generics.clone());
-            let name = get_name(&typ);
+            let name = get_name(&typ)?;
             let mut new_items = vec![];
             let mut update_items = vec![];
             for item in items {
                 let mut i = item.clone();
                 match item.node {
-                    Const(_, _) => panic!("Unexpected const item"),
-                    Macro(mac) => self.view_macro = Some(mac),
+                    Const(_, _) =>
+                        return Err(Error::new(format!("unexpected const item `{}` in #[widget]", item.ident))),
+                    Macro(mac) => {
+                        if !is_macro(&mac, "view") {
+                            return Err(Error::new(format!("unexpected macro `{}!` in #[widget], expected `view!`",
+                                                          tokens_to_string(&mac.path))));
+                        }
+                        self.view_macro = Some(mac)
+                    },
                     Method(sig, _) => {
                         match item.ident.to_string().as_ref() {
                             "parent_id" => self.data_method = Some(i),
                             "root" => self.root_method = Some(i),
                             "model" => {
-                                self.widget_model_type = Some(get_return_type(sig));
-                                add_model_param(&mut i, &mut self.model_param_type);
+                                self.widget_model_type = Some(get_return_type(sig)?);
+                                add_model_param(&mut i, &mut self.model_param_type, &self.options.crate_path);
                                 update_items.push(i);
                             },
                             "subscriptions" => update_items.push(i),
-                            "init_view" | "on_add" => new_items.push(i),
+                            "init_view" | "on_add" | "on_destroy" | "on_hide" | "on_init" | "on_remove" |
+                                "on_show" =>
+                                new_items.push(i),
                             "update" => {
-                                self.widget_msg_type = Some(get_second_param_type(&sig));
+                                self.widget_msg_type = Some(get_second_param_type(&sig)?);
                                 self.update_method = Some(i)
                             },
                             _ => self.other_methods.push(i),
@@ -216,17 +285,27 @@ impl Driver {
                             "Model" => self.model_type = Some(i),
                             "ModelParam" => self.model_param_type = Some(i),
                             "Msg" => self.msg_type = Some(i),
-                            _ => panic!("Unexpected type item {:?}", item.ident),
+                            _ => return Err(Error::new(format!("unexpected type item `{}` in #[widget], expected \
+                                                                `Model`, `ModelParam`, `Msg` or `Root`", item.ident))),
                         }
                     },
                 }
             }
-            let view = self.get_view(&name, &typ);
-            if let Some(on_add) = gen_set_child_prop_calls(&view.widget) {
+            if self.widget_model_type.is_none() {
+                return Err(Error::new(format!("missing `fn model()` method in #[widget] for `{}`", name)));
+            }
+            if self.update_method.is_none() {
+                return Err(Error::new(format!("missing `fn update()` method in #[widget] for `{}`", name)));
+            }
+            self.helper_methods = get_helper_methods(&self.other_methods);
+            let view = self.get_view(&name, &typ)?;
+            if let Some(on_add) = gen_set_child_prop_calls(&view.widget, &self.options.gtk_path) {
                 new_items.push(on_add);
             }
+            self.dynamic_model_map = Some(view.dynamic_model_map);
             self.msg_model_map = Some(view.msg_model_map);
             self.properties_model_map = Some(view.properties_model_map);
+            self.warn_event_cycles(&name, &view.widget);
             new_items.push(view.item);
             self.widgets.insert(self.root_widget.clone().expect("root widget"),
             self.root_widget_type.clone().expect("root widget type"));
@@ -241,17 +320,23 @@ impl Driver {
             let item = Impl(unsafety, polarity, generics, path, typ, new_items);
             ast.node = item;
             let container_impl = view.container_impl;
-            quote! {
+            let tokens = quote! {
                 #widget_struct
                 #ast
                 #container_impl
                 #update_impl
 
                 #other_methods
+            };
+            if self.options.debug {
+                if let Err(error) = write_expansion(&name, &tokens, &self.widget_locations) {
+                    warn!("Cannot write the expansion of the widget {}: {}", name, error);
+                }
             }
+            Ok(tokens)
         }
         else {
-            panic!("Expected impl");
+            Err(Error::new("#[widget] can only be used on an `impl Widget for` block"))
         }
     }
 
@@ -302,9 +387,11 @@ impl Driver {
         for method in &mut other_methods {
             self.add_set_property_to_method(method);
         }
+        let dynamic_methods = &self.dynamic_methods;
         quote! {
             impl #generics #typ #where_clause {
                 #(#other_methods)*
+                #(#dynamic_methods)*
             }
         }
     }
@@ -340,52 +427,59 @@ impl Driver {
         func
     }
 
-    fn get_view(&mut self, name: &Ident, typ: &Ty) -> View {
-        {
-            let segments = &self.view_macro.as_ref().expect("view! macro missing").path.segments;
-            if segments.len() != 1 || segments[0].ident != "view" {
-                panic!("Unexpected macro item")
-            }
+    fn get_view(&mut self, name: &Ident, typ: &Ty) -> Result<View> {
+        if self.view_macro.is_none() {
+            return Err(Error::new(format!("missing `view!` macro in #[widget] for `{}`", name)));
         }
         self.impl_view(name, typ)
     }
 
-    fn impl_view(&mut self, name: &Ident, typ: &Ty) -> View {
+    fn impl_view(&mut self, name: &Ident, typ: &Ty) -> Result<View> {
         let tokens = &self.view_macro.take().expect("view_macro in impl_view()").tts;
         if let TokenTree::Delimited(Delimited { ref tts, .. }) = tokens[0] {
-            let mut widget = parse(tts);
+            let mut widget = parse(tts, self.options.gtk_path.as_str())?;
+            wrap_bindings(&mut widget, &self.options.crate_path);
+            self.view_file = view_file(tts)?;
+            if self.options.debug {
+                self.widget_locations = widget_locations(name, &widget, self.view_file.as_ref());
+            }
             if let Gtk(ref mut widget) = widget.widget {
                 widget.relm_name = Some(typ.clone());
             }
             self.widget_parent_id = widget.parent_id.clone();
+            let mut dynamic_model_map = HashMap::new();
             let mut msg_model_map = HashMap::new();
             let mut properties_model_map = HashMap::new();
-            get_properties_model_map(&widget, &mut properties_model_map);
-            get_msg_model_map(&widget, &mut msg_model_map);
+            get_properties_model_map(&widget, &mut properties_model_map, &self.helper_methods);
+            get_msg_model_map(&widget, &mut msg_model_map, &self.helper_methods);
+            get_dynamic_model_map(&widget, &mut dynamic_model_map, &self.helper_methods);
             self.add_widgets(&widget, &properties_model_map);
-            let (view, relm_widgets, container_impl) = gen(name, &widget, self);
+            let (view, relm_widgets, container_impl) = gen(name, &widget, self)?;
             let model_ident = Ident::new(MODEL_IDENT);
+            let relm_crate = &self.options.crate_path;
             let item = block_to_impl_item(quote! {
                 #[allow(unused_variables)] // Necessary to avoid warnings in case the parameters are unused.
-                fn view(relm: &::relm::Relm<Self>, #model_ident: Self::Model) -> Self {
+                fn view(relm: &#relm_crate::Relm<Self>, #model_ident: Self::Model) -> Self {
                     #view
                 }
             });
-            View {
+            Ok(View {
                 container_impl,
                 item,
+                dynamic_model_map,
                 msg_model_map,
                 properties_model_map,
                 relm_widgets,
                 widget,
-            }
+            })
         }
         else {
-            panic!("Expected `{{` but found `{:?}` in view! macro", tokens[0]);
+            Err(Error::unexpected("`{`", tokens.first()))
         }
     }
 
     fn update_impl(&mut self, typ: &Ty, generics: &Generics, items: Vec<ImplItem>) -> Tokens {
+        let relm_crate = self.options.crate_path.clone();
         let where_clause = gen_where_clause(generics);
 
         let msg = self.get_msg_type();
@@ -393,7 +487,7 @@ impl Driver {
         let update = self.get_update();
         let model = self.get_model_type();
         quote! {
-            impl #generics ::relm::Update for #typ #where_clause {
+            impl #generics #relm_crate::Update for #typ #where_clause {
                 #msg
                 #model
                 #model_param
@@ -402,19 +496,169 @@ impl Driver {
             }
         }
     }
+
+    /*
+     * Warn about the messages that can trigger themselves, like a parent sending a message to a child
+     * which emits an event mapped back to the same message.
+     */
+    fn warn_event_cycles(&self, name: &Ident, widget: &Widget) {
+        let update = self.update_method.as_ref().expect("update method");
+        let msg_map = self.msg_model_map.as_ref().expect("msg model map");
+        warn_event_cycles(name.as_ref(), widget, update, msg_map);
+    }
+}
+
+/// Options of the `#[widget]` attribute, as in `#[widget(debug, crate = "::my_crate::relm")]`.
+#[derive(Debug)]
+pub struct WidgetOptions {
+    /// Path of the relm crate used in the generated code (`::relm` by default).
+    pub crate_path: Tokens,
+    /// Write the expansion of the widget to `target/relm-expansion/<Widget>.rs`.
+    pub debug: bool,
+    /// Path of the gtk crate used in the generated code (`::gtk` by default).
+    pub gtk_path: Tokens,
+}
+
+impl WidgetOptions {
+    /// Create the default options; `debug` is enabled by the `RELM_DEBUG_EXPANSION` environment variable.
+    pub fn new() -> Self {
+        WidgetOptions {
+            crate_path: quote! { ::relm },
+            debug: env::var_os(DEBUG_ENV_VAR).is_some(),
+            gtk_path: quote! { ::gtk },
+        }
+    }
+
+    /// Parse the arguments of the attribute, with or without their parentheses.
+    pub fn parse(attributes: &str) -> Result<Self> {
+        let mut options = WidgetOptions::new();
+        let tokens = parse_token_trees(attributes).map_err(Error::new)?;
+        let arguments =
+            match tokens.first() {
+                Some(&TokenTree::Delimited(Delimited { delim: Paren, ref tts })) if tokens.len() == 1 =>
+                    Some(tts.clone()),
+                _ => None,
+            };
+        let tokens = arguments.unwrap_or(tokens);
+        for option in tokens.split(|token| *token == TokenTree::Token(Token::Comma)) {
+            match option.first() {
+                None => (),
+                Some(&TokenTree::Token(Token::Ident(ref ident))) if ident == "debug" && option.len() == 1 =>
+                    options.debug = true,
+                Some(&TokenTree::Token(Token::Ident(ref ident))) if ident == "crate" =>
+                    options.crate_path = parse_path_option(option)?,
+                Some(&TokenTree::Token(Token::Ident(ref ident))) if ident == "gtk" =>
+                    options.gtk_path = parse_path_option(option)?,
+                _ => {
+                    let mut tokens = Tokens::new();
+                    tokens.append_all(option);
+                    let message = format!("unknown option `{}` in #[widget], expected `debug`, `crate` or `gtk`",
+                                          tokens.as_str());
+                    return Err(Error::new(message));
+                },
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Parse an option of the form `name = "path"` to the tokens of the path.
+fn parse_path_option(option: &[TokenTree]) -> Result<Tokens> {
+    if option.len() == 3 && option[1] == TokenTree::Token(Token::Eq) {
+        if let TokenTree::Token(Token::Literal(Lit::Str(ref path, _))) = option[2] {
+            let path = parse_path(path)
+                .map_err(|error| Error::new(format!("invalid path `{}` in #[widget]: {}", path, error)))?;
+            return Ok(quote! { #path });
+        }
+    }
+    let mut tokens = Tokens::new();
+    tokens.append_all(option);
+    Err(Error::new(format!("expected a path string as in `crate = \"::relm\"` in #[widget], found `{}`",
+                           tokens.as_str())))
 }
 
+impl Default for WidgetOptions {
+    fn default() -> Self {
+        WidgetOptions::new()
+    }
+}
+
+/// Generate the code of a widget, or a compile_error!() when the widget is invalid.
 pub fn gen_widget(input: Tokens) -> Tokens {
-    let mut driver = Driver::new();
-    driver.gen_widget(input)
+    gen_widget_with_options(input, WidgetOptions::new())
+}
+
+/// Generate the code of a widget with the options of the attribute.
+pub fn gen_widget_with_options(input: Tokens, options: WidgetOptions) -> Tokens {
+    let mut driver = Driver::new(options);
+    match driver.gen_widget(input) {
+        Ok(tokens) => tokens,
+        Err(error) => error.to_compile_error(),
+    }
+}
+
+/// Convert a view to a GtkBuilder interface.
+///
+/// The source is either the content of a `.relm` file or a rust source file, in which case the
+/// view! macro of the first widget found is converted.
+pub fn view_to_glade(source: &str) -> Result<String> {
+    let tokens =
+        if let Ok(krate) = parse_crate(source) {
+            find_view_macro(&krate.items)
+                .ok_or_else(|| Error::new("cannot find a view! macro in the rust source file"))?
+        }
+        else {
+            parse_view_file(source)?
+        };
+    Ok(widget_to_glade(&parse(&tokens, "gtk")?))
+}
+
+fn find_view_macro(items: &[Item]) -> Option<Vec<TokenTree>> {
+    for item in items {
+        match item.node {
+            Impl(_, _, _, _, _, ref impl_items) => {
+                for impl_item in impl_items {
+                    if let Macro(ref mac) = impl_item.node {
+                        if is_macro(mac, "view") {
+                            if let TokenTree::Delimited(Delimited { ref tts, .. }) = mac.tts[0] {
+                                return Some(tts.clone());
+                            }
+                        }
+                    }
+                }
+            },
+            ItemKind::Mac(ref mac) if is_macro(mac, "relm_widget") => {
+                if let TokenTree::Delimited(Delimited { ref tts, .. }) = mac.tts[0] {
+                    let mut tokens = Tokens::new();
+                    tokens.append_all(tts);
+                    if let Ok(item) = parse_item(tokens.as_str()) {
+                        if let Some(tokens) = find_view_macro(&[item]) {
+                            return Some(tokens);
+                        }
+                    }
+                }
+            },
+            ItemKind::Mod(Some(ref items)) => {
+                if let Some(tokens) = find_view_macro(items) {
+                    return Some(tokens);
+                }
+            },
+            _ => (),
+        }
+    }
+    None
+}
+
+fn is_macro(mac: &Mac, name: &str) -> bool {
+    mac.path.segments.last().map(|segment| segment.ident == name) == Some(true)
 }
 
-fn add_model_param(model_fn: &mut ImplItem, model_param_type: &mut Option<ImplItem>) {
+fn add_model_param(model_fn: &mut ImplItem, model_param_type: &mut Option<ImplItem>, relm_crate: &Tokens) {
     if let Method(ref mut method_sig, _) = model_fn.node {
         let len = method_sig.decl.inputs.len();
         if len == 0 || len == 1 {
             let type_tokens = quote! {
-                &::relm::Relm<Self>
+                &#relm_crate::Relm<Self>
             };
             let typ = parse_type(type_tokens.as_str()).expect("Relm type");
             method_sig.decl.inputs.insert(0, Captured(Wild, typ));
@@ -443,25 +687,26 @@ fn block_to_impl_item(tokens: Tokens) -> ImplItem {
     }
 }
 
-fn get_name(typ: &Ty) -> Ident {
+fn get_name(typ: &Ty) -> Result<Ident> {
     if let Ty::Path(_, ref path) = *typ {
         let mut parts = vec![];
         for segment in &path.segments {
             parts.push(segment.ident.as_ref());
         }
-        Ident::new(parts.join("::"))
+        Ok(Ident::new(parts.join("::")))
     }
     else {
-        panic!("Expected Path")
+        Err(Error::new(format!("expected a type name, like `impl Widget for Win`, found `{}` in #[widget]",
+                               tokens_to_string(typ))))
     }
 }
 
 macro_rules! get_map {
-    ($widget:expr, $map:expr, $is_relm:expr) => {{
+    ($widget:expr, $map:expr, $helper_methods:expr, $is_relm:expr) => {{
         for (name, expr) in &$widget.properties {
-            let mut visitor = ModelVariableVisitor::new();
+            let mut visitor = ModelVariableVisitor::new($helper_methods);
             visitor.visit_expr(&expr);
-            let model_variables = visitor.idents;
+            let model_variables = visitor.paths;
             for var in model_variables {
                 let set = $map.entry(var).or_insert_with(HashSet::new);
                 set.insert(Property {
@@ -473,23 +718,90 @@ macro_rules! get_map {
             }
         }
         for child in &$widget.children {
-            get_properties_model_map(child, $map);
+            get_properties_model_map(child, $map, $helper_methods);
         }
     }};
 }
 
-fn get_msg_model_map(widget: &Widget, map: &mut MsgModelMap) {
+/*
+ * The map maps model variable path to the loops and conditions using it, either in their
+ * collection, their key, their conditions or their bodies.
+ */
+fn get_dynamic_model_map(widget: &Widget, map: &mut DynamicModelMap, helper_methods: &HelperMethods) {
+    for for_loop in &widget.loops {
+        let mut visitor = ModelVariableVisitor::new(helper_methods);
+        visitor.visit_expr(&for_loop.collection);
+        visitor.visit_expr(&for_loop.key);
+        visit_widget_exprs(&for_loop.child, &mut visitor);
+        insert_dynamic_children(map, visitor, &widget.name, &for_loop.name);
+    }
+    for condition in &widget.conditions {
+        let mut visitor = ModelVariableVisitor::new(helper_methods);
+        if let Some(ref matched) = condition.matched {
+            visitor.visit_expr(matched);
+        }
+        for branch in &condition.branches {
+            match branch.condition {
+                Arm(_, Some(ref expr)) | If(ref expr) | IfLet(_, ref expr) => visitor.visit_expr(expr),
+                Arm(_, None) | Else => (),
+            }
+            if let Some(ref child) = branch.child {
+                visit_widget_exprs(child, &mut visitor);
+            }
+        }
+        insert_dynamic_children(map, visitor, &widget.name, &condition.name);
+    }
+    for child in &widget.children {
+        get_dynamic_model_map(child, map, helper_methods);
+    }
+}
+
+fn insert_dynamic_children(map: &mut DynamicModelMap, visitor: ModelVariableVisitor, container_name: &Ident,
+    name: &Ident)
+{
+    for path in visitor.paths {
+        let set = map.entry(path).or_insert_with(HashSet::new);
+        set.insert(DynamicChildren {
+            container_name: container_name.clone(),
+            name: name.clone(),
+        });
+    }
+}
+
+/// Visit all the expressions used by a widget and its children.
+fn visit_widget_exprs(widget: &Widget, visitor: &mut ModelVariableVisitor) {
+    for expr in widget.properties.values().chain(widget.child_properties.values()).chain(&widget.init_parameters) {
+        visitor.visit_expr(expr);
+    }
+    match widget.widget {
+        Gtk(ref gtk_widget) => {
+            for expr in gtk_widget.construct_properties.values() {
+                visitor.visit_expr(expr);
+            }
+        },
+        Relm(ref relm_widget) => {
+            for expr in relm_widget.messages.values() {
+                visitor.visit_expr(expr);
+            }
+        },
+    }
+    for child in &widget.children {
+        visit_widget_exprs(child, visitor);
+    }
+}
+
+fn get_msg_model_map(widget: &Widget, map: &mut MsgModelMap, helper_methods: &HelperMethods) {
     match widget.widget {
         Gtk(_) => {
             for child in &widget.children {
-                get_msg_model_map(child, map);
+                get_msg_model_map(child, map, helper_methods);
             }
         },
         Relm(ref relm_widget) => {
             for (name, expr) in &relm_widget.messages {
-                let mut visitor = ModelVariableVisitor::new();
+                let mut visitor = ModelVariableVisitor::new(helper_methods);
                 visitor.visit_expr(&expr);
-                let model_variables = visitor.idents;
+                let model_variables = visitor.paths;
                 for var in model_variables {
                     let set = map.entry(var).or_insert_with(HashSet::new);
                     set.insert(Message {
@@ -500,41 +812,117 @@ fn get_msg_model_map(widget: &Widget, map: &mut MsgModelMap) {
                 }
             }
             for child in &widget.children {
-                get_msg_model_map(child, map);
+                get_msg_model_map(child, map, helper_methods);
             }
         },
     }
 }
 
 /*
- * The map maps model variable name to a vector of tuples (widget name, property name).
+ * The map maps model variable path to a vector of tuples (widget name, property name).
  */
-fn get_properties_model_map(widget: &Widget, map: &mut PropertyModelMap) {
+fn get_properties_model_map(widget: &Widget, map: &mut PropertyModelMap, helper_methods: &HelperMethods) {
     match widget.widget {
-        Gtk(_) => get_map!(widget, map, false),
-        Relm(_) => get_map!(widget, map, true),
+        Gtk(_) => get_map!(widget, map, helper_methods, false),
+        Relm(_) => get_map!(widget, map, helper_methods, true),
     }
 }
 
-fn get_return_type(sig: MethodSig) -> Ty {
+/*
+ * Get the model variables read by the helper methods taking &self, including through the other
+ * helper methods they call, so that the properties calling them are updated when these variables
+ * are modified.
+ */
+fn get_helper_methods(methods: &[ImplItem]) -> HelperMethods {
+    let empty_helper_methods = HashMap::new();
+    let mut calls = HashMap::new();
+    let mut helper_methods = HashMap::new();
+    for method in methods {
+        if let Method(ref sig, ref block) = method.node {
+            if let Some(&SelfRef(_, Immutable)) = sig.decl.inputs.first() {
+                let mut visitor = ModelVariableVisitor::new(&empty_helper_methods);
+                for stmt in &block.stmts {
+                    visitor.visit_stmt(stmt);
+                }
+                calls.insert(method.ident.clone(), visitor.self_methods);
+                helper_methods.insert(method.ident.clone(), visitor.paths);
+            }
+        }
+    }
+    // Propagate the paths through the calls until nothing changes.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (method, called_methods) in &calls {
+            let mut new_paths = vec![];
+            for called_method in called_methods {
+                if let Some(paths) = helper_methods.get(called_method) {
+                    new_paths.extend(paths.iter().cloned());
+                }
+            }
+            let paths = helper_methods.get_mut(method).expect("helper method");
+            for path in new_paths {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                    changed = true;
+                }
+            }
+        }
+    }
+    helper_methods
+}
+
+fn get_return_type(sig: MethodSig) -> Result<Ty> {
     if let FunctionRetTy::Ty(ty) = sig.decl.output {
-        ty
+        Ok(ty)
     }
     else {
-        panic!("Unexpected default, expecting Ty");
+        Err(Error::new("the `model()` method in #[widget] must return the model type"))
     }
 }
 
-fn get_second_param_type(sig: &MethodSig) -> Ty {
-    if let Captured(_, ref path) = sig.decl.inputs[1] {
-        path.clone()
+fn get_second_param_type(sig: &MethodSig) -> Result<Ty> {
+    if let Some(&Captured(_, ref path)) = sig.decl.inputs.get(1) {
+        Ok(path.clone())
     }
     else {
-        panic!("Unexpected `{:?}`, expecting Captured Ty", sig.decl.inputs[1]);
+        Err(Error::new("the `update()` method in #[widget] must take the message as its second parameter"))
+    }
+}
+
+fn tokens_to_string<T: ToTokens>(value: &T) -> String {
+    let mut tokens = Tokens::new();
+    value.to_tokens(&mut tokens);
+    tokens.as_str().to_string()
+}
+
+/// Convert the model variables bound to the properties with `bind` to the values expected by the setters.
+fn wrap_bindings(widget: &mut Widget, relm_crate: &Tokens) {
+    if let Gtk(ref gtk_widget) = widget.widget {
+        for (property, value) in &gtk_widget.bindings {
+            let value = quote! {
+                #relm_crate::ToWidgetValue::to_widget_value(&(#value))
+            };
+            let value = parse_expr(value.as_str()).expect("parse_expr() in wrap_bindings()");
+            widget.properties.insert(property.clone(), value);
+        }
+    }
+    for child in &mut widget.children {
+        wrap_bindings(child, relm_crate);
+    }
+    for for_loop in &mut widget.loops {
+        wrap_bindings(&mut for_loop.child, relm_crate);
+    }
+    for condition in &mut widget.conditions {
+        for branch in &mut condition.branches {
+            if let Some(ref mut child) = branch.child {
+                wrap_bindings(child, relm_crate);
+            }
+        }
     }
 }
 
-fn gen_set_child_prop_calls(widget: &Widget) -> Option<ImplItem> {
+fn gen_set_child_prop_calls(widget: &Widget, gtk_crate: &Tokens) -> Option<ImplItem> {
     let mut tokens = Tokens::new();
     let widget_name = &widget.name;
     for (key, value) in &widget.child_properties {
@@ -545,8 +933,10 @@ fn gen_set_child_prop_calls(widget: &Widget) -> Option<ImplItem> {
     }
     if !widget.child_properties.is_empty() {
         Some(block_to_impl_item(quote! {
-            fn on_add<W: ::gtk::IsA<::gtk::Widget> + ::gtk::IsA<::gtk::Object>>(&self, parent: W) {
-                let parent: gtk::Box = ::gtk::Cast::downcast(::gtk::Cast::upcast::<::gtk::Widget>(parent))
+            fn on_add<W: #gtk_crate::IsA<#gtk_crate::Widget> + #gtk_crate::IsA<#gtk_crate::Object>>(&self, parent: W)
+            {
+                let parent: #gtk_crate::Widget = #gtk_crate::Cast::upcast(parent);
+                let parent: #gtk_crate::Box = #gtk_crate::Cast::downcast(parent)
                     .expect("the parent of a widget with child properties must be a gtk::Box");
                 #tokens
             }
@@ -556,3 +946,82 @@ fn gen_set_child_prop_calls(widget: &Widget) -> Option<ImplItem> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use syn::{Ident, parse_item};
+    use syn::ItemKind::Impl;
+
+    use walker::ModelPath;
+    use super::{WidgetOptions, get_helper_methods};
+
+    fn path(idents: &[&str]) -> ModelPath {
+        idents.iter().map(|ident| Ident::new(*ident)).collect()
+    }
+
+    #[test]
+    fn helper_methods() {
+        let item = parse_item("
+            impl Win {
+                fn title(&self) -> String {
+                    format!(\"{} ({})\", self.name(), self.model.count)
+                }
+
+                fn name(&self) -> String {
+                    self.full_name()
+                }
+
+                fn full_name(&self) -> String {
+                    self.model.user.name.clone()
+                }
+
+                fn cycle(&self) -> bool {
+                    self.model.flag && self.cycle()
+                }
+
+                fn update_title(&mut self) {
+                    self.model.count += 1;
+                }
+            }").expect("item");
+        let items =
+            match item.node {
+                Impl(_, _, _, _, _, items) => items,
+                _ => vec![],
+            };
+        let helper_methods = get_helper_methods(&items);
+        assert_eq!(helper_methods.len(), 4);
+        // The paths read through the calls to the other helper methods are propagated.
+        assert_eq!(helper_methods[&Ident::new("title")], vec![path(&["count"]), path(&["user", "name"])]);
+        assert_eq!(helper_methods[&Ident::new("name")], vec![path(&["user", "name"])]);
+        assert_eq!(helper_methods[&Ident::new("full_name")], vec![path(&["user", "name"])]);
+        assert_eq!(helper_methods[&Ident::new("cycle")], vec![path(&["flag"])]);
+    }
+
+    #[test]
+    fn widget_options() {
+        let options = WidgetOptions::parse("").expect("options");
+        assert_eq!(options.crate_path.as_str(), ":: relm");
+        assert_eq!(options.gtk_path.as_str(), ":: gtk");
+
+        let options = WidgetOptions::parse("debug, crate = \"::relm_fork\", gtk = \"gtk_fork::gtk\"")
+            .expect("options");
+        assert!(options.debug);
+        assert_eq!(options.crate_path.as_str(), ":: relm_fork");
+        assert_eq!(options.gtk_path.as_str(), "gtk_fork :: gtk");
+
+        // The attribute arguments may be given with their parentheses.
+        let options = WidgetOptions::parse("(gtk = \"::gtk_fork\",)").expect("options");
+        assert_eq!(options.gtk_path.as_str(), ":: gtk_fork");
+    }
+
+    #[test]
+    fn widget_options_errors() {
+        let error = |attributes| WidgetOptions::parse(attributes).expect_err("error").to_string();
+        assert_eq!(error("verbose"), "unknown option `verbose` in #[widget], expected `debug`, `crate` or `gtk`");
+        assert_eq!(error("debug = true"),
+                   "unknown option `debug = true` in #[widget], expected `debug`, `crate` or `gtk`");
+        assert_eq!(error("crate = relm"),
+                   "expected a path string as in `crate = \"::relm\"` in #[widget], found `crate = relm`");
+        assert!(error("gtk = \"gtk::\"").starts_with("invalid path `gtk::` in #[widget]"));
+    }
+}
@@ -20,21 +20,27 @@
  */
 
 use std::collections::HashMap;
+use std::env;
 use std::fs::File;
 use std::io::Read;
 use std::mem;
+use std::path::{self, PathBuf};
 use std::sync::Mutex;
 
 use quote::{Tokens, ToTokens};
-use syn::{self, Expr, Path, Ty, parse_expr, parse_item, parse_path};
+use syn::{self, Expr, Path, Ty, parse_expr, parse_item, parse_path, parse_token_trees};
 use syn::Delimited;
 use syn::DelimToken::{Brace, Bracket, Paren};
 use syn::ItemKind::Mac;
 use syn::Lit::Str;
 use syn::StrStyle::Cooked;
 use syn::TokenTree::{self, Token};
-use syn::Token::{At, Colon, Comma, Dot, Eq, FatArrow, Gt, Ident, Literal, Lt, ModSep, Pound};
+use syn::Token::{At, Colon, Comma, Dot, Eq, FatArrow, Gt, Ident, Literal, Lt, ModSep, Not, Pound};
 
+use error::{Error, Result};
+use glade::glade_to_view;
+use walker::take_expr;
+use self::BranchCondition::*;
 use self::DefaultParam::*;
 use self::EventValue::*;
 use self::EventValueReturn::*;
@@ -84,14 +90,51 @@ impl Event {
     }
 }
 
+/// A branch of an `if` or a `match`, whose widget is created when the branch is active.
+#[derive(Debug)]
+pub struct Branch {
+    pub child: Option<Widget>,
+    pub condition: BranchCondition,
+}
+
+#[derive(Debug)]
+pub enum BranchCondition {
+    Arm(Tokens, Option<Expr>),
+    Else,
+    If(Expr),
+    IfLet(Tokens, Expr),
+}
+
+/// An `if` or a `match` creating the widget of its active branch.
+#[derive(Debug)]
+pub struct Condition {
+    pub branches: Vec<Branch>,
+    pub matched: Option<Expr>,
+    pub name: syn::Ident,
+    pub position: usize, // Index among the children, loops and conditions of the container.
+}
+
+/// A `for` loop creating a child widget for each item of a collection.
+#[derive(Debug)]
+pub struct ForLoop {
+    pub child: Widget,
+    pub collection: Expr,
+    pub key: Expr,
+    pub name: syn::Ident,
+    pub pattern: Tokens,
+    pub position: usize, // Index among the children, loops and conditions of the container.
+}
+
 #[derive(Debug)]
 pub struct Widget {
     pub child_events: ChildEvents, // TODO: does it make sense for a relm widget?
     pub child_properties: HashMap<String, Expr>, // TODO: does it make sense for a relm widget?
     pub children: Vec<Widget>,
+    pub conditions: Vec<Condition>,
     pub container_type: Option<Option<String>>, // TODO: Why two Options?
     pub init_parameters: Vec<Expr>,
     pub is_container: bool,
+    pub loops: Vec<ForLoop>,
     pub name: syn::Ident,
     pub parent_id: Option<String>,
     pub properties: HashMap<String, Expr>,
@@ -108,9 +151,11 @@ impl Widget {
             child_events,
             child_properties,
             children,
+            conditions: vec![],
             container_type: None,
             init_parameters,
             is_container: false,
+            loops: vec![],
             name: syn::Ident::new(name),
             parent_id: None,
             properties,
@@ -131,9 +176,11 @@ impl Widget {
             child_events,
             child_properties,
             children,
+            conditions: vec![],
             container_type: None,
             init_parameters,
             is_container: false,
+            loops: vec![],
             name: syn::Ident::new(name),
             parent_id: None,
             properties,
@@ -149,10 +196,21 @@ pub enum EitherWidget {
     Relm(RelmWidget),
 }
 
+/// Binding of a property to the property of another widget, as in `sensitive: bind !check.active`.
+#[derive(Debug)]
+pub struct PropertyBinding {
+    pub invert: bool,
+    pub source: syn::Ident,
+    pub source_property: String,
+    pub transform: Option<Expr>,
+}
+
 #[derive(Debug)]
 pub struct GtkWidget {
+    pub bindings: HashMap<String, Expr>,
     pub construct_properties: HashMap<syn::Ident, Expr>,
     pub events: HashMap<String, Event>,
+    pub property_bindings: HashMap<String, PropertyBinding>,
     pub relm_name: Option<Ty>,
     pub save: bool,
 }
@@ -160,8 +218,10 @@ pub struct GtkWidget {
 impl GtkWidget {
     fn new() -> Self {
         GtkWidget {
+            bindings: HashMap::new(),
             construct_properties: HashMap::new(),
             events: HashMap::new(),
+            property_bindings: HashMap::new(),
             relm_name: None,
             save: false,
         }
@@ -173,6 +233,8 @@ pub struct RelmWidget {
     pub events: HashMap<String, Vec<Event>>,
     pub gtk_events: HashMap<String, Event>,
     pub messages: HashMap<String, Expr>,
+    // Names of the init parameters given as MyWidget(label: "x", max: 10), empty when they are positional.
+    pub param_names: Vec<syn::Ident>,
 }
 
 impl RelmWidget {
@@ -181,6 +243,7 @@ impl RelmWidget {
             events: HashMap::new(),
             gtk_events: HashMap::new(),
             messages: HashMap::new(),
+            param_names: vec![],
         }
     }
 }
@@ -197,81 +260,153 @@ fn last_segment_lowercase(path: &Path) -> bool {
     }
 }
 
-pub fn parse(tokens: &[TokenTree]) -> Widget {
+pub fn parse(tokens: &[TokenTree], gtk_path: &str) -> Result<Widget> {
     let tokens =
-        if let Token(Literal(Str(ref relm_view_file, _))) = tokens[0] {
-            // TODO: also support glade file.
-            let mut file = File::open(relm_view_file).expect("File::open() in parse()");
+        if let Some(relm_view_file) = view_file(tokens)? {
             let mut file_content = String::new();
-            file.read_to_string(&mut file_content).expect("read_to_string() in parse()");
-            let item = parse_item(&file_content).expect("parse_item() in parse()");
-            if let Mac(syn::Mac { tts, .. }) = item.node {
-                if let TokenTree::Delimited(Delimited { ref tts, .. }) = tts[0] {
-                    tts.clone()
-                }
-                else {
-                    panic!("Expected delimited macro")
-                }
-            }
-            else {
-                panic!("Expecting a macro")
-            }
+            File::open(&relm_view_file)
+                .and_then(|mut file| file.read_to_string(&mut file_content))
+                .map_err(|error| Error::new(format!("cannot read the view file {}: {}", relm_view_file, error)))?;
+            parse_view_file_content(&relm_view_file, &file_content, gtk_path)?
         }
         else {
             tokens.to_vec()
         };
-    let (mut widget, _, parent_id) = parse_child(&tokens, true);
+    let (mut widget, _, parent_id) = parse_child(&tokens, true)?;
     widget.parent_id = parent_id;
-    widget
+    Ok(widget)
 }
 
-fn parse_widget(tokens: &[TokenTree], save: bool) -> (Widget, &[TokenTree]) {
-    let (gtk_type, mut tokens) = parse_qualified_name(tokens);
+/// Get the path of the view file used in `view!("examples/buttons.relm")`.
+/// A relative path is resolved against the directory of the crate being compiled.
+pub fn view_file(tokens: &[TokenTree]) -> Result<Option<String>> {
+    if let Some(&Token(Literal(Str(ref relm_view_file, _)))) = tokens.first() {
+        let path = path::Path::new(relm_view_file);
+        let path =
+            if path.is_relative() {
+                let directory = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from)
+                    .or_else(|_| env::current_dir())
+                    .map_err(|error| Error::new(format!("cannot find the view file {}: {}", relm_view_file, error)))?;
+                directory.join(path)
+            }
+            else {
+                path.to_path_buf()
+            };
+        let path = path.to_str()
+            .ok_or_else(|| Error::new(format!("the path of the view file {} is not valid UTF-8", relm_view_file)))?;
+        Ok(Some(path.to_string()))
+    }
+    else {
+        Ok(None)
+    }
+}
+
+/// Parse the content of a view file, which is either a Glade file or a file containing the view! macro.
+/// The widgets of a Glade file are converted to gtk types prefixed with `gtk_path`.
+pub fn parse_view_file_content(path: &str, file_content: &str, gtk_path: &str) -> Result<Vec<TokenTree>> {
+    if path.ends_with(".glade") || path.ends_with(".ui") {
+        let view = glade_to_view(file_content, gtk_path)
+            .map_err(|error| Error::new(format!("cannot convert the Glade file {}: {}", path, error)))?;
+        parse_token_trees(&view)
+            .map_err(|error| Error::new(format!("cannot convert the Glade file {}: {}", path, error)))
+    }
+    else {
+        parse_view_file(file_content)
+    }
+}
+
+pub fn parse_view_file(file_content: &str) -> Result<Vec<TokenTree>> {
+    let item = parse_item(file_content)
+        .map_err(|error| Error::new(format!("cannot parse the view file: {}", error)))?;
+    if let Mac(syn::Mac { tts, .. }) = item.node {
+        if let Some(&TokenTree::Delimited(Delimited { ref tts, .. })) = tts.first() {
+            return Ok(tts.clone());
+        }
+    }
+    Err(Error::new("expected a view! macro in the view file"))
+}
+
+fn parse_widget(tokens: &[TokenTree], save: bool) -> Result<(Widget, &[TokenTree])> {
+    let (gtk_type, mut tokens) = parse_qualified_name(tokens)?;
     let mut gtk_widget = GtkWidget::new();
     let mut init_parameters = vec![];
     let mut children = vec![];
     let mut properties = HashMap::new();
     let mut child_properties = HashMap::new();
     let mut child_events = HashMap::new();
+    let mut conditions = vec![];
+    let mut loops = vec![];
     gtk_widget.save = save;
     if let TokenTree::Delimited(Delimited { delim: Paren, ref tts }) = tokens[0] {
-        if let TokenTree::Delimited(Delimited { delim: Brace, ref tts }) = tts[0] {
-            gtk_widget.construct_properties = parse_hash(tts);
+        if let Some(&TokenTree::Delimited(Delimited { delim: Brace, ref tts })) = tts.first() {
+            gtk_widget.construct_properties = parse_hash(tts)?;
         }
         else {
-            init_parameters = parse_comma_list(tts);
+            init_parameters = parse_comma_list(tts)?;
         }
         tokens = &tokens[1..];
     }
-    if let TokenTree::Delimited(Delimited { delim: Brace, ref tts }) = tokens[0] {
+    if let Some(&TokenTree::Delimited(Delimited { delim: Brace, ref tts })) = tokens.first() {
         let mut tts = &tts[..];
         while !tts.is_empty() {
-            if tts[0] == Token(Pound) || try_parse_name(tts).is_some() {
-                let (child, new_tts, _) = parse_child(tts, false);
+            if is_for_loop(tts) {
+                let (mut for_loop, new_tts) = parse_for_loop(tts)?;
+                tts = new_tts;
+                for_loop.position = children.len() + loops.len() + conditions.len();
+                loops.push(for_loop);
+            }
+            else if is_condition(tts) {
+                let (mut condition, new_tts) = parse_condition(tts)?;
+                tts = new_tts;
+                condition.position = children.len() + loops.len() + conditions.len();
+                conditions.push(condition);
+            }
+            else if tts[0] == Token(Pound) || try_parse_name(tts).is_some() {
+                let (child, new_tts, _) = parse_child(tts, false)?;
                 tts = new_tts;
                 children.push(child);
             }
             else {
                 // Property or event.
-                let (ident, _) = parse_ident(tts);
+                let (ident, _) = parse_ident(tts, "a property, an event or a child widget")?;
                 tts = &tts[1..];
-                match tts[0] {
-                    Token(Colon) => {
-                        tts = parse_value_or_child_properties(tts, ident, &mut child_properties, &mut properties);
+                // The properties are not known here: only the capitalized names are reported, since a
+                // property is written in snake_case; rustc reports the other ones as missing setters.
+                if tts.first() == Some(&Token(Colon)) && ident.starts_with(char::is_uppercase) {
+                    return Err(Error::new(format!("unknown property `{}` of `{}` in view! macro: the properties of \
+                                                  the gtk widgets are written in snake_case", ident,
+                                                  display_path(&gtk_type))));
+                }
+                match tts.first() {
+                    Some(&Token(Colon)) if is_binding(tts) && !is_model_binding(tts) => {
+                        let (binding, new_tts) = parse_property_binding(&tts[2..])?;
+                        gtk_widget.property_bindings.insert(ident, binding);
+                        tts = new_tts;
+                    },
+                    Some(&Token(Colon)) if is_binding(tts) => {
+                        let (value, new_tts, _) = parse_value(&tts[2..]);
+                        // The value is converted for the setter when generating the code.
+                        let value = tokens_to_expr(value)?;
+                        properties.insert(ident.clone(), value.clone());
+                        gtk_widget.bindings.insert(ident, value);
+                        tts = new_tts;
                     },
-                    Token(Dot) => {
+                    Some(&Token(Colon)) => {
+                        tts = parse_value_or_child_properties(tts, ident, &mut child_properties, &mut properties)?;
+                    },
+                    Some(&Token(Dot)) => {
                         let child_name = ident;
-                        let (ident, new_tts) = parse_ident(&tts[1..]);
-                        let (event, new_tts) = parse_event(new_tts, DefaultOneParam);
+                        let (ident, new_tts) = parse_ident(&tts[1..], "the event of the child widget")?;
+                        let (event, new_tts) = parse_event(new_tts, DefaultOneParam)?;
                         child_events.insert((child_name, ident), event);
                         tts = new_tts;
                     },
-                    TokenTree::Delimited(Delimited { delim: Paren, .. }) | Token(FatArrow) => {
-                        let (event, new_tts) = parse_event(tts, DefaultOneParam);
+                    Some(&TokenTree::Delimited(Delimited { delim: Paren, .. })) | Some(&Token(FatArrow)) => {
+                        let (event, new_tts) = parse_event(tts, DefaultOneParam)?;
                         gtk_widget.events.insert(ident, event);
                         tts = new_tts;
                     },
-                    _ => panic!("Expected `:` or `(` but found `{:?}` in view! macro", tts[0]),
+                    token => return Err(Error::unexpected(&format!("`:`, `=>` or `(` after `{}`", ident), token)),
                 }
             }
 
@@ -281,47 +416,256 @@ fn parse_widget(tokens: &[TokenTree], save: bool) -> (Widget, &[TokenTree]) {
         }
     }
     else {
-        panic!("Expected {{ but found `{:?}` in view! macro", tokens[0]);
+        return Err(Error::unexpected(&format!("`{{` after `{}`", display_path(&gtk_type)), tokens.first()));
     }
-    let widget = Widget::new_gtk(gtk_widget, gtk_type, init_parameters, children, properties, child_properties,
-                                 child_events);
-    (widget, &tokens[1..])
+    let mut widget = Widget::new_gtk(gtk_widget, gtk_type, init_parameters, children, properties, child_properties,
+                                     child_events);
+    widget.conditions = conditions;
+    widget.loops = loops;
+    Ok((widget, &tokens[1..]))
 }
 
-fn parse_child(mut tokens: &[TokenTree], root: bool) -> (Widget, &[TokenTree], Option<String>) {
-    let (mut attributes, new_tokens) = parse_attributes(tokens);
+fn parse_child(mut tokens: &[TokenTree], root: bool) -> Result<(Widget, &[TokenTree], Option<String>)> {
+    let (mut attributes, new_tokens) = parse_attributes(tokens)?;
     let container_type = attributes.remove("container")
         .map(|typ| typ.map(str::to_string));
     tokens = new_tokens;
     let name = attributes.get("name").and_then(|name| *name);
     let (mut widget, new_tokens) =
         if tokens.get(1) == Some(&Token(ModSep)) {
-            parse_widget(tokens, name.is_some() || root)
+            parse_widget(tokens, name.is_some() || root)?
         }
         else {
-            parse_relm_widget(tokens)
+            parse_relm_widget(tokens)?
         };
     if let Some(name) = name {
         widget.name = syn::Ident::new(name);
     }
-    widget.is_container = !widget.children.is_empty();
+    widget.is_container = !widget.children.is_empty() || !widget.loops.is_empty() || !widget.conditions.is_empty();
     widget.container_type = container_type;
     let parent_id = attributes.get("parent").and_then(|opt_str| opt_str.map(str::to_string));
-    (widget, new_tokens, parent_id)
+    Ok((widget, new_tokens, parent_id))
+}
+
+fn is_condition(tokens: &[TokenTree]) -> bool {
+    tokens[0] == Token(Ident(syn::Ident::new("if"))) || tokens[0] == Token(Ident(syn::Ident::new("match")))
+}
+
+fn parse_condition(tokens: &[TokenTree]) -> Result<(Condition, &[TokenTree])> {
+    if tokens[0] == Token(Ident(syn::Ident::new("match"))) {
+        parse_match(tokens)
+    }
+    else {
+        parse_if(tokens)
+    }
+}
+
+/*
+ * Parse a condition of the form:
+ * if self.model.logged_in {
+ *     Widget
+ * }
+ * else if let Some(ref error) = self.model.error {
+ *     Widget
+ * }
+ * else {
+ * }
+ */
+fn parse_if(mut tokens: &[TokenTree]) -> Result<(Condition, &[TokenTree])> {
+    let mut branches = vec![];
+    loop {
+        let (condition_tokens, body, rest) = split_at_brace(&tokens[1..])?;
+        branches.push(Branch {
+            child: parse_branch_body(body)?,
+            condition: parse_if_condition(condition_tokens)?,
+        });
+        tokens = rest;
+        if tokens.first() != Some(&Token(Ident(syn::Ident::new("else")))) {
+            break;
+        }
+        match tokens.get(1) {
+            Some(&Token(Ident(ref ident))) if ident == "if" => tokens = &tokens[1..],
+            Some(&TokenTree::Delimited(Delimited { delim: Brace, ref tts })) => {
+                branches.push(Branch {
+                    child: parse_branch_body(tts)?,
+                    condition: Else,
+                });
+                tokens = &tokens[2..];
+                break;
+            },
+            token => return Err(Error::unexpected("`if` or `{` after `else`", token)),
+        }
+    }
+    let condition = Condition {
+        branches,
+        matched: None,
+        name: syn::Ident::new(gen_name("__relm_branch".to_string())),
+        position: 0,
+    };
+    Ok((condition, tokens))
+}
+
+fn parse_if_condition(tokens: &[TokenTree]) -> Result<BranchCondition> {
+    if tokens.first() == Some(&Token(Ident(syn::Ident::new("let")))) {
+        let index = tokens.iter().position(|token| *token == Token(Eq))
+            .ok_or_else(|| Error::new("expected `=` after `if let` in view! macro"))?;
+        Ok(IfLet(slice_to_tokens(&tokens[1..index]), tokens_to_expr(slice_to_tokens(&tokens[index + 1..]))?))
+    }
+    else {
+        Ok(If(tokens_to_expr(slice_to_tokens(tokens))?))
+    }
 }
 
-fn parse_ident(tokens: &[TokenTree]) -> (String, &[TokenTree]) {
-    match tokens[0] {
-        Token(Ident(ref ident)) => {
-            (ident.to_string(), &tokens[1..])
+/*
+ * Parse a condition of the form:
+ * match self.model.state {
+ *     State::Loading => Widget,
+ *     State::Error(ref error) if !error.is_empty() => Widget,
+ *     _ => {},
+ * }
+ */
+fn parse_match(tokens: &[TokenTree]) -> Result<(Condition, &[TokenTree])> {
+    let (matched, arms, rest) = split_at_brace(&tokens[1..])?;
+    let mut branches = vec![];
+    let mut tts = &arms[..];
+    while !tts.is_empty() {
+        let arrow = match tts.iter().position(|token| *token == Token(FatArrow)) {
+            Some(arrow) => arrow,
+            None => return Err(Error::unexpected("`=>` in match arm", tts.first())),
+        };
+        let pattern = &tts[..arrow];
+        let condition =
+            match pattern.iter().position(|token| *token == Token(Ident(syn::Ident::new("if")))) {
+                Some(index) => Arm(slice_to_tokens(&pattern[..index]),
+                    Some(tokens_to_expr(slice_to_tokens(&pattern[index + 1..]))?)),
+                None => Arm(slice_to_tokens(pattern), None),
+            };
+        tts = &tts[arrow + 1..];
+        let (child, rest) =
+            if let Some(&TokenTree::Delimited(Delimited { delim: Brace, tts: ref body })) = tts.first() {
+                (parse_branch_body(body)?, &tts[1..])
+            }
+            else {
+                let (child, rest, _) = parse_child(tts, false)?;
+                (Some(child), rest)
+            };
+        tts = rest;
+        branches.push(Branch {
+            child,
+            condition,
+        });
+        if tts.first() == Some(&Token(Comma)) {
+            tts = &tts[1..];
+        }
+    }
+    let condition = Condition {
+        branches,
+        matched: Some(tokens_to_expr(slice_to_tokens(matched))?),
+        name: syn::Ident::new(gen_name("__relm_branch".to_string())),
+        position: 0,
+    };
+    Ok((condition, rest))
+}
+
+/// Parse the widget of a branch, which can be empty.
+fn parse_branch_body(tokens: &[TokenTree]) -> Result<Option<Widget>> {
+    if tokens.is_empty() {
+        Ok(None)
+    }
+    else {
+        let (child, rest, _) = parse_child(tokens, false)?;
+        let rest = if rest.first() == Some(&Token(Comma)) { &rest[1..] } else { rest };
+        if !rest.is_empty() {
+            return Err(Error::unexpected("a single widget in the branch", rest.first()));
+        }
+        Ok(Some(child))
+    }
+}
+
+/// Split the tokens before the first `{ }` block, the content of this block and the tokens after.
+fn split_at_brace(tokens: &[TokenTree]) -> Result<(&[TokenTree], &[TokenTree], &[TokenTree])> {
+    for (index, token) in tokens.iter().enumerate() {
+        if let TokenTree::Delimited(Delimited { delim: Brace, ref tts }) = *token {
+            return Ok((&tokens[..index], tts, &tokens[index + 1..]));
+        }
+    }
+    Err(Error::new("expected `{` in view! macro"))
+}
+
+fn is_for_loop(tokens: &[TokenTree]) -> bool {
+    tokens[0] == Token(Ident(syn::Ident::new("for")))
+}
+
+/*
+ * Parse a loop of the form:
+ * for item in self.model.items, key: item.id {
+ *     Widget
+ * }
+ */
+fn parse_for_loop(tokens: &[TokenTree]) -> Result<(ForLoop, &[TokenTree])> {
+    let mut tokens = &tokens[1..];
+    let mut pattern = Tokens::new();
+    while tokens.first() != Some(&Token(Ident(syn::Ident::new("in")))) {
+        if tokens.is_empty() {
+            return Err(Error::new("expected `in` in the for loop in view! macro"));
+        }
+        tokens[0].to_tokens(&mut pattern);
+        tokens = &tokens[1..];
+    }
+    tokens = &tokens[1..];
+    let mut collection = Tokens::new();
+    while tokens.first() != Some(&Token(Comma)) {
+        match tokens.first() {
+            Some(&TokenTree::Delimited(Delimited { delim: Brace, .. })) | None =>
+                return Err(Error::new("expected `, key: expression` after the collection of the for loop in view! \
+                                       macro")),
+            Some(token) => token.to_tokens(&mut collection),
+        }
+        tokens = &tokens[1..];
+    }
+    let (ident, new_tokens) = parse_ident(&tokens[1..], "`key:`")?;
+    if ident != "key" || new_tokens.first() != Some(&Token(Colon)) {
+        return Err(Error::unexpected("`key:`", tokens.get(1)));
+    }
+    tokens = &new_tokens[1..];
+    let mut key = Tokens::new();
+    loop {
+        match tokens.first() {
+            Some(&TokenTree::Delimited(Delimited { delim: Brace, ref tts })) => {
+                let (child, rest, _) = parse_child(tts, false)?;
+                let rest = if rest.first() == Some(&Token(Comma)) { &rest[1..] } else { rest };
+                if !rest.is_empty() {
+                    return Err(Error::unexpected("a single widget in the for loop", rest.first()));
+                }
+                let for_loop = ForLoop {
+                    child,
+                    collection: tokens_to_expr(collection)?,
+                    key: tokens_to_expr(key)?,
+                    name: syn::Ident::new(gen_name("__relm_loop".to_string())),
+                    pattern,
+                    position: 0,
+                };
+                return Ok((for_loop, &tokens[1..]));
+            },
+            Some(token) => token.to_tokens(&mut key),
+            None => return Err(Error::new("expected `{` after the key of the for loop in view! macro")),
+        }
+        tokens = &tokens[1..];
+    }
+}
+
+fn parse_ident<'a>(tokens: &'a [TokenTree], expected: &str) -> Result<(String, &'a [TokenTree])> {
+    match tokens.first() {
+        Some(&Token(Ident(ref ident))) => {
+            Ok((ident.to_string(), &tokens[1..]))
         },
-        _ => panic!("Expected ident but found `{:?}` in view! macro", tokens[0]),
+        token => Err(Error::unexpected(expected, token)),
     }
 }
 
-fn parse_qualified_name(tokens: &[TokenTree]) -> (Path, &[TokenTree]) {
+fn parse_qualified_name(tokens: &[TokenTree]) -> Result<(Path, &[TokenTree])> {
     try_parse_name(tokens)
-        .unwrap_or_else(|| panic!("Expected qualified name but found `{:?}` in view! macro", tokens[0]))
+        .ok_or_else(|| Error::unexpected("a widget type", tokens.first()))
 }
 
 fn try_parse_name(mut tokens: &[TokenTree]) -> Option<(Path, &[TokenTree])> {
@@ -340,8 +684,8 @@ fn try_parse_name(mut tokens: &[TokenTree]) -> Option<(Path, &[TokenTree])> {
         path_string.push_str(&toks.to_string());
         tokens = &tokens[1..];
     }
-    match tokens[0] {
-        TokenTree::Delimited(_) | Token(Comma) => {
+    match tokens.first() {
+        Some(&TokenTree::Delimited(_)) | Some(&Token(Comma)) => {
             if let Ok(path) = parse_path(&path_string) {
                 if !last_segment_lowercase(&path) {
                     return Some((path, tokens));
@@ -353,7 +697,7 @@ fn try_parse_name(mut tokens: &[TokenTree]) -> Option<(Path, &[TokenTree])> {
     None
 }
 
-fn parse_comma_ident_list(tokens: &[TokenTree]) -> Vec<syn::Ident> {
+fn parse_comma_ident_list(tokens: &[TokenTree]) -> Result<Vec<syn::Ident>> {
     let mut params = vec![];
     let mut param = Tokens::new();
     for token in tokens {
@@ -363,11 +707,11 @@ fn parse_comma_ident_list(tokens: &[TokenTree]) -> Vec<syn::Ident> {
                 param = Tokens::new();
             },
             Token(ref token) => token.to_tokens(&mut param),
-            _ => panic!("Expecting Token, but found: `{:?}`", token),
+            _ => return Err(Error::unexpected("a parameter name", Some(token))),
         }
     }
     params.push(syn::Ident::new(param.as_str()));
-    params
+    Ok(params)
 }
 
 enum HashState {
@@ -378,7 +722,7 @@ enum HashState {
 
 use self::HashState::*;
 
-fn parse_hash(tokens: &[TokenTree]) -> HashMap<syn::Ident, Expr> {
+fn parse_hash(tokens: &[TokenTree]) -> Result<HashMap<syn::Ident, Expr>> {
     let mut params = HashMap::new();
     let mut current_param = Tokens::new();
     let mut state = InName;
@@ -392,7 +736,7 @@ fn parse_hash(tokens: &[TokenTree]) -> HashMap<syn::Ident, Expr> {
                     state = AfterName;
                 }
                 else {
-                    panic!("Expected ident, but found `{:?}` in view! macro", token);
+                    return Err(Error::unexpected("a construct property name", Some(token)));
                 }
             },
             AfterName => {
@@ -400,13 +744,13 @@ fn parse_hash(tokens: &[TokenTree]) -> HashMap<syn::Ident, Expr> {
                     state = InValue;
                 }
                 else {
-                    panic!("Expected colon, but found `{:?}` in view! macro", token);
+                    return Err(Error::unexpected(&format!("`:` after `{}`", name), Some(token)));
                 }
             },
             InValue => {
                 if *token == Token(Comma) {
                     let ident = mem::replace(&mut name, syn::Ident::new(""));
-                    params.insert(ident, tokens_to_expr(current_param));
+                    params.insert(ident, tokens_to_expr(current_param)?);
                     current_param = Tokens::new();
                     state = InName;
                 }
@@ -417,103 +761,173 @@ fn parse_hash(tokens: &[TokenTree]) -> HashMap<syn::Ident, Expr> {
         }
     }
     // FIXME: could be an empty hash.
-    params.insert(name, tokens_to_expr(current_param));
-    params
+    params.insert(name, tokens_to_expr(current_param)?);
+    Ok(params)
 }
 
-fn parse_comma_list(tokens: &[TokenTree]) -> Vec<Expr> {
+fn parse_comma_list(mut tokens: &[TokenTree]) -> Result<Vec<Expr>> {
     let mut params = vec![];
-    let mut current_param = Tokens::new();
-    for token in tokens {
-        if *token == Token(Comma) {
-            params.push(tokens_to_expr(current_param));
-            current_param = Tokens::new();
+    while !tokens.is_empty() {
+        let (param, _, rest) = take_expr(tokens, &[Comma]);
+        params.push(tokens_to_expr(slice_to_tokens(param))?);
+        tokens = rest;
+    }
+    Ok(params)
+}
+
+fn is_named_parameters(tokens: &[TokenTree]) -> bool {
+    match tokens.first() {
+        Some(&Token(Ident(_))) => tokens.get(1) == Some(&Token(Colon)),
+        _ => false,
+    }
+}
+
+/// Parse the named parameters of a relm widget, like `label: "x", max: 10`.
+fn parse_named_parameters(mut tokens: &[TokenTree]) -> Result<(Vec<syn::Ident>, Vec<Expr>)> {
+    let mut names: Vec<syn::Ident> = vec![];
+    let mut params = vec![];
+    while !tokens.is_empty() {
+        let (name, rest) = parse_ident(tokens, "a parameter name")?;
+        if rest.first() != Some(&Token(Colon)) {
+            return Err(Error::unexpected(&format!("`:` after the parameter `{}`", name), rest.first()));
         }
-        else {
-            token.to_tokens(&mut current_param);
+        if names.iter().any(|ident| ident == &name) {
+            return Err(Error::new(format!("parameter `{}` specified more than once in view! macro", name)));
         }
+        let (value, _, rest) = take_expr(&rest[1..], &[Comma]);
+        names.push(syn::Ident::new(name));
+        params.push(tokens_to_expr(slice_to_tokens(value))?);
+        tokens = rest;
     }
-    // FIXME: could be an empty list.
-    params.push(tokens_to_expr(current_param));
-    params
+    Ok((names, params))
 }
 
-fn parse_event(mut tokens: &[TokenTree], default_param: DefaultParam) -> (Event, &[TokenTree]) {
+fn parse_event(mut tokens: &[TokenTree], default_param: DefaultParam) -> Result<(Event, &[TokenTree])> {
     let mut event = Event::new();
     if default_param == DefaultNoParam {
         event.params.clear();
     }
-    if let TokenTree::Delimited(Delimited { delim: Paren, ref tts }) = tokens[0] {
-        event.params = parse_comma_ident_list(tts);
+    if let Some(&TokenTree::Delimited(Delimited { delim: Paren, ref tts })) = tokens.first() {
+        event.params = parse_comma_ident_list(tts)?;
         tokens = &tokens[1..];
     }
-    tokens = try_parse_shared_values(tokens, &mut event);
-    if tokens[0] != Token(FatArrow) {
-        panic!("Expected `=>` but found `{:?}` in view! macro", tokens[0]);
+    tokens = try_parse_shared_values(tokens, &mut event)?;
+    if tokens.first() != Some(&Token(FatArrow)) {
+        return Err(Error::unexpected("`=>`", tokens.first()));
     }
     tokens = &tokens[1..];
-    tokens = parse_message_sent(tokens, &mut event);
-    (event, tokens)
+    tokens = parse_message_sent(tokens, &mut event)?;
+    Ok((event, tokens))
 }
 
-fn parse_message_sent<'a>(tokens: &'a [TokenTree], event: &mut Event) -> &'a [TokenTree] {
+fn parse_message_sent<'a>(tokens: &'a [TokenTree], event: &mut Event) -> Result<&'a [TokenTree]> {
     // Message sent to another widget.
     if tokens.len() >= 2 && tokens[1] == Token(At) {
-        let (event_value, new_tokens, use_self) = parse_event_value(&tokens[2..]);
+        let (event_value, new_tokens, use_self) = parse_event_value(&tokens[2..])?;
         event.use_self = use_self;
-        let (ident, _) = parse_ident(tokens);
+        let (ident, _) = parse_ident(tokens, "a widget name before `@`")?;
         let mut ident_tokens = Tokens::new();
         ident_tokens.append(ident);
         event.value = ForeignWidget(ident_tokens, event_value);
-        new_tokens
+        Ok(new_tokens)
     }
     // Message sent to the same widget.
     else {
-        let (event_value, new_tokens, use_self) = parse_event_value(tokens);
+        let (event_value, new_tokens, use_self) = parse_event_value(tokens)?;
         event.use_self = use_self;
         event.value = CurrentWidget(event_value);
-        new_tokens
+        Ok(new_tokens)
     }
 }
 
-fn parse_event_value(tokens: &[TokenTree]) -> (EventValueReturn, &[TokenTree], bool) {
-    if Token(Ident(syn::Ident::new("return"))) == tokens[0] {
-        let (value, tokens, use_self) = parse_value(&tokens[1..]);
-        (CallReturn(value), tokens, use_self)
-    }
-    else if let TokenTree::Delimited(Delimited { delim: Paren, ref tts }) = tokens[0] {
-        let (value1, new_tts, use_self1) = parse_value(tts);
-        if new_tts[0] != Token(Comma) {
-            panic!("Expected `,` but found `{:?}` in view! macro", new_tts[0]);
-        }
-        let (value2, _, use_self2) = parse_value(&new_tts[1..]);
-        (Return(value1, value2), &tokens[1..], use_self1 || use_self2)
-    }
-    else {
-        let (value, tokens, use_self) = parse_value(tokens);
-        (WithoutReturn(value), tokens, use_self)
+fn parse_event_value(tokens: &[TokenTree]) -> Result<(EventValueReturn, &[TokenTree], bool)> {
+    match tokens.first() {
+        Some(&Token(Ident(ref ident))) if ident == "return" => {
+            let (value, tokens, use_self) = parse_value(&tokens[1..]);
+            Ok((CallReturn(value), tokens, use_self))
+        },
+        Some(&TokenTree::Delimited(Delimited { delim: Paren, ref tts })) => {
+            let (value1, new_tts, use_self1) = parse_value(tts);
+            if new_tts.first() != Some(&Token(Comma)) {
+                return Err(Error::unexpected("`,` in the return value of the event", new_tts.first()));
+            }
+            let (value2, _, use_self2) = parse_value(&new_tts[1..]);
+            Ok((Return(value1, value2), &tokens[1..], use_self1 || use_self2))
+        },
+        Some(_) => {
+            let (value, tokens, use_self) = parse_value(tokens);
+            Ok((WithoutReturn(value), tokens, use_self))
+        },
+        None => Err(Error::unexpected("a message after `=>`", None)),
     }
 }
 
 fn parse_value_or_child_properties<'a>(tokens: &'a [TokenTree], ident: String,
-    child_properties: &mut HashMap<String, Expr>, properties: &mut HashMap<String, Expr>) -> &'a [TokenTree]
+    child_properties: &mut HashMap<String, Expr>, properties: &mut HashMap<String, Expr>) -> Result<&'a [TokenTree]>
 {
-    match tokens[1] {
-        TokenTree::Delimited(Delimited { delim: Brace, tts: ref child_tokens }) => {
-            let props = parse_child_properties(child_tokens);
+    match tokens.get(1) {
+        Some(&TokenTree::Delimited(Delimited { delim: Brace, tts: ref child_tokens })) => {
+            let props = parse_child_properties(child_tokens)?;
             for (key, value) in props {
-                child_properties.insert(key, tokens_to_expr(value));
+                child_properties.insert(key, tokens_to_expr(value)?);
             }
-            &tokens[2..]
+            Ok(&tokens[2..])
         },
-        _ => {
+        Some(_) => {
             let (value, tts, _) = parse_value(&tokens[1..]);
-            properties.insert(ident, tokens_to_expr(value));
-            tts
+            properties.insert(ident, tokens_to_expr(value)?);
+            Ok(tts)
         },
+        None => Err(Error::unexpected(&format!("a value for the property `{}`", ident), None)),
     }
 }
 
+/// Check if the property value starts with `bind`, as in `text: bind self.model.text`.
+fn is_binding(tokens: &[TokenTree]) -> bool {
+    tokens.len() > 2 && tokens[1] == Token(Ident(syn::Ident::new("bind"))) && tokens[2] != Token(Comma)
+}
+
+/// Check if the binding is to a model variable, as in `text: bind self.model.text`, instead of to
+/// the property of another widget.
+fn is_model_binding(tokens: &[TokenTree]) -> bool {
+    tokens[2] == Token(Ident(syn::Ident::new("self")))
+}
+
+/// Parse a binding to the property of another widget: `[!]widget.property [with transform]`.
+fn parse_property_binding(tokens: &[TokenTree]) -> Result<(PropertyBinding, &[TokenTree])> {
+    let (invert, tokens) =
+        if tokens[0] == Token(Not) {
+            (true, &tokens[1..])
+        }
+        else {
+            (false, tokens)
+        };
+    let (source, tokens) = parse_ident(tokens, "a widget name after `bind`")?;
+    if tokens.first() != Some(&Token(Dot)) {
+        return Err(Error::unexpected(&format!("`.` after `{}` in the binding", source), tokens.first()));
+    }
+    let (source_property, tokens) = parse_ident(&tokens[1..], &format!("a property name after `{}.`", source))?;
+    let (transform, tokens) =
+        if tokens.first() == Some(&Token(Ident(syn::Ident::new("with")))) {
+            if invert {
+                return Err(Error::new(format!("cannot use both `!` and `with` in the binding of `{}.{}` in view! \
+                                               macro", source, source_property)));
+            }
+            let (transform, tokens, _) = parse_value(&tokens[1..]);
+            (Some(tokens_to_expr(transform)?), tokens)
+        }
+        else {
+            (None, tokens)
+        };
+    let binding = PropertyBinding {
+        invert,
+        source: syn::Ident::new(source),
+        source_property,
+        transform,
+    };
+    Ok((binding, tokens))
+}
+
 fn parse_value(tokens: &[TokenTree]) -> (Tokens, &[TokenTree], bool) {
     let mut current_param = Tokens::new();
     let mut i = 0;
@@ -542,12 +956,22 @@ fn gen_widget_name(path: &Path) -> String {
         else {
             name.to_lowercase()
         };
-    let mut hashmap = NAMES_INDEX.lock().expect("lock() in gen_widget_name()");
+    gen_name(name)
+}
+
+fn gen_name(name: String) -> String {
+    let mut hashmap = NAMES_INDEX.lock().expect("lock() in gen_name()");
     let index = hashmap.entry(name.clone()).or_insert(0);
     *index += 1;
     format!("{}{}", name, index)
 }
 
+/// Format a path for the error messages, like `gtk::Button`.
+fn display_path(path: &Path) -> String {
+    let segments: Vec<_> = path.segments.iter().map(|segment| segment.ident.as_ref()).collect();
+    segments.join("::")
+}
+
 fn path_to_string(path: &Path) -> String {
     let mut string = String::new();
     for segment in &path.segments {
@@ -556,21 +980,21 @@ fn path_to_string(path: &Path) -> String {
     string
 }
 
-fn parse_attributes(mut tokens: &[TokenTree]) -> (HashMap<&str, Option<&str>>, &[TokenTree]) {
+fn parse_attributes(mut tokens: &[TokenTree]) -> Result<(HashMap<&str, Option<&str>>, &[TokenTree])> {
     let mut attributes = HashMap::new();
-    while tokens[0] == Token(Pound) {
+    while tokens.first() == Some(&Token(Pound)) {
         tokens = &tokens[1..];
-        if let TokenTree::Delimited(Delimited { delim: Bracket, ref tts }) = tokens[0] {
+        if let Some(&TokenTree::Delimited(Delimited { delim: Bracket, ref tts })) = tokens.first() {
             tokens = &tokens[1..];
-            if let Token(Ident(ref ident)) = tts[0] {
+            if let Some(&Token(Ident(ref ident))) = tts.first() {
                 let name = ident.as_ref();
                 let value =
                     if let Some(&Token(Eq)) = tts.get(1) {
-                        if let Token(Literal(Str(ref name, Cooked))) = tts[2] {
+                        if let Some(&Token(Literal(Str(ref name, Cooked)))) = tts.get(2) {
                             Some(name.as_str())
                         }
                         else {
-                            None
+                            return Err(Error::unexpected(&format!("a string after `#[{} =`", name), tts.get(2)));
                         }
                     }
                     else {
@@ -579,45 +1003,62 @@ fn parse_attributes(mut tokens: &[TokenTree]) -> (HashMap<&str, Option<&str>>, &
                 attributes.insert(name, value);
             }
         }
+        else {
+            return Err(Error::unexpected("`[` after `#`", tokens.first()));
+        }
+    }
+    if tokens.is_empty() {
+        return Err(Error::unexpected("a widget", None));
     }
-    (attributes, tokens)
+    Ok((attributes, tokens))
 }
 
-fn parse_child_properties(mut tokens: &[TokenTree]) -> HashMap<String, Tokens> {
+fn parse_child_properties(mut tokens: &[TokenTree]) -> Result<HashMap<String, Tokens>> {
     // TODO: panic if the same child properties is set twice.
     // TODO: same for normal properties?
     let mut properties = HashMap::new();
     while !tokens.is_empty() {
-        let (ident, _) = parse_ident(tokens);
-        tokens = &tokens[1..];
-        if let Token(Colon) = tokens[0] {
+        let (ident, new_tokens) = parse_ident(tokens, "a child property name")?;
+        tokens = new_tokens;
+        if tokens.first() == Some(&Token(Colon)) {
             tokens = &tokens[1..];
             let (value, new_tokens, _) = parse_value(tokens);
             tokens = new_tokens;
             properties.insert(ident, value);
         }
+        else {
+            return Err(Error::unexpected(&format!("`:` after the child property `{}`", ident), tokens.first()));
+        }
 
         if tokens.first() == Some(&Token(Comma)) {
             tokens = &tokens[1..];
         }
     }
-    properties
+    Ok(properties)
 }
 
-fn parse_relm_widget(tokens: &[TokenTree]) -> (Widget, &[TokenTree]) {
-    let (relm_type, mut tokens) = parse_qualified_name(tokens);
+fn parse_relm_widget(tokens: &[TokenTree]) -> Result<(Widget, &[TokenTree])> {
+    let (relm_type, mut tokens) = parse_qualified_name(tokens)?;
     let mut relm_widget = RelmWidget::new();
     let mut init_parameters = vec![];
     let mut children = vec![];
     let mut properties = HashMap::new();
     let mut child_properties = HashMap::new();
     let mut child_events = HashMap::new();
+    let mut conditions = vec![];
+    let mut loops = vec![];
     if let TokenTree::Delimited(Delimited { delim: Paren, ref tts }) = tokens[0] {
-        let parameters = parse_comma_list(tts);
-        init_parameters = parameters;
+        if is_named_parameters(tts) {
+            let (names, parameters) = parse_named_parameters(tts)?;
+            relm_widget.param_names = names;
+            init_parameters = parameters;
+        }
+        else {
+            init_parameters = parse_comma_list(tts)?;
+        }
         tokens = &tokens[1..];
     }
-    if let TokenTree::Delimited(Delimited { delim: Brace, ref tts }) = tokens[0] {
+    if let Some(&TokenTree::Delimited(Delimited { delim: Brace, ref tts })) = tokens.first() {
         let mut tts = &tts[..];
         while !tts.is_empty() {
             let is_child =
@@ -632,17 +1073,33 @@ fn parse_relm_widget(tokens: &[TokenTree]) -> (Widget, &[TokenTree]) {
                 else {
                     false
                 };
-            if tts[0] == Token(Pound) || is_child {
-                let (child, new_tts, _) = parse_child(tts, false);
+            if is_for_loop(tts) {
+                let (mut for_loop, new_tts) = parse_for_loop(tts)?;
+                tts = new_tts;
+                for_loop.position = children.len() + loops.len() + conditions.len();
+                loops.push(for_loop);
+            }
+            else if is_condition(tts) {
+                let (mut condition, new_tts) = parse_condition(tts)?;
+                tts = new_tts;
+                condition.position = children.len() + loops.len() + conditions.len();
+                conditions.push(condition);
+            }
+            else if tts[0] == Token(Pound) || is_child {
+                let (child, new_tts, _) = parse_child(tts, false)?;
                 tts = new_tts;
                 children.push(child);
             }
             else {
                 // Property or event.
-                let (ident, _) = parse_ident(tts);
+                let (ident, _) = parse_ident(tts, "a property, a message, an event or a child widget")?;
                 tts = &tts[1..];
-                match tts[0] {
-                    Token(Colon) => {
+                match tts.first() {
+                    Some(&Token(Colon)) if is_binding(tts) => {
+                        return Err(Error::new(format!("binding the property `{}` is only supported on gtk widgets \
+                                                       in view! macro", ident)));
+                    },
+                    Some(&Token(Colon)) => {
                         let properties_or_signals =
                             if ident.chars().next().map(|char| char.is_lowercase()) == Some(false) {
                                 // Uppercase is a msg to send.
@@ -652,31 +1109,32 @@ fn parse_relm_widget(tokens: &[TokenTree]) -> (Widget, &[TokenTree]) {
                                 // Lowercase is a gtk property.
                                 &mut properties
                             };
-                        tts = parse_value_or_child_properties(tts, ident, &mut child_properties, properties_or_signals);
+                        tts = parse_value_or_child_properties(tts, ident, &mut child_properties,
+                                                              properties_or_signals)?;
                     },
-                    Token(Dot) => {
+                    Some(&Token(Dot)) => {
                         let child_name = ident;
-                        let (ident, new_tts) = parse_ident(&tts[1..]);
-                        let (event, new_tts) = parse_event(new_tts, DefaultOneParam);
+                        let (ident, new_tts) = parse_ident(&tts[1..], "the event of the child widget")?;
+                        let (event, new_tts) = parse_event(new_tts, DefaultOneParam)?;
                         child_events.insert((child_name, ident), event);
                         tts = new_tts;
                     },
-                    TokenTree::Delimited(Delimited { delim: Paren, .. }) | Token(FatArrow) => {
+                    Some(&TokenTree::Delimited(Delimited { delim: Paren, .. })) | Some(&Token(FatArrow)) => {
                         if ident.chars().next().map(|char| char.is_lowercase()) == Some(false) {
                             // Uppercase is a msg.
-                            let (event, new_tts) = parse_event(&tts[0..], DefaultNoParam);
+                            let (event, new_tts) = parse_event(&tts[0..], DefaultNoParam)?;
                             let entry = relm_widget.events.entry(ident).or_insert_with(Vec::new);
                             entry.push(event);
                             tts = new_tts;
                         }
                         else {
                             // Lowercase is a gtk event.
-                            let (event, new_tts) = parse_event(tts, DefaultOneParam);
+                            let (event, new_tts) = parse_event(tts, DefaultOneParam)?;
                             relm_widget.gtk_events.insert(ident, event);
                             tts = new_tts;
                         }
                     },
-                    _ => panic!("Expected `:`, `=>` or `(` but found `{:?}` in view! macro", tts[0]),
+                    token => return Err(Error::unexpected(&format!("`:`, `=>` or `(` after `{}`", ident), token)),
                 }
             }
 
@@ -685,27 +1143,113 @@ fn parse_relm_widget(tokens: &[TokenTree]) -> (Widget, &[TokenTree]) {
             }
         }
     }
-    let widget = Widget::new_relm(relm_widget, relm_type, init_parameters, children, properties, child_properties,
-                                  child_events);
-    (widget, &tokens[1..])
+    else {
+        return Err(Error::unexpected(&format!("`{{` after `{}`", display_path(&relm_type)), tokens.first()));
+    }
+    let mut widget = Widget::new_relm(relm_widget, relm_type, init_parameters, children, properties, child_properties,
+                                      child_events);
+    widget.conditions = conditions;
+    widget.loops = loops;
+    Ok((widget, &tokens[1..]))
 }
 
-fn try_parse_shared_values<'a>(tokens: &'a [TokenTree], event: &mut Event) -> &'a [TokenTree] {
-    if tokens[0] == Token(Ident(syn::Ident::new("with"))) {
-        if let TokenTree::Delimited(Delimited { delim: Paren, ref tts }) = tokens[1] {
-            event.shared_values = parse_comma_ident_list(tts);
+fn try_parse_shared_values<'a>(tokens: &'a [TokenTree], event: &mut Event) -> Result<&'a [TokenTree]> {
+    if tokens.first() == Some(&Token(Ident(syn::Ident::new("with")))) {
+        if let Some(&TokenTree::Delimited(Delimited { delim: Paren, ref tts })) = tokens.get(1) {
+            event.shared_values = parse_comma_ident_list(tts)?;
         }
         else {
-            panic!("Expected `(` after `with` but found `{:?}` in view! macro", tokens[1]);
+            return Err(Error::unexpected("`(` after `with`", tokens.get(1)));
         }
-        &tokens[2..]
+        Ok(&tokens[2..])
     }
     else {
-        tokens
+        Ok(tokens)
+    }
+}
+
+fn slice_to_tokens(tokens: &[TokenTree]) -> Tokens {
+    let mut result = Tokens::new();
+    for token in tokens {
+        token.to_tokens(&mut result);
     }
+    result
+}
+
+fn tokens_to_expr(tokens: Tokens) -> Result<Expr> {
+    parse_expr(tokens.as_str())
+        .map_err(|_| Error::new(format!("expected an expression, found `{}` in view! macro", tokens.as_str())))
 }
 
-fn tokens_to_expr(tokens: Tokens) -> Expr {
-    let string: String = tokens.parse().expect("parse::<String>() in tokens_to_expr");
-    parse_expr(&string).expect("parse_expr in tokens_to_expr")
+#[cfg(test)]
+mod tests {
+    use quote::{Tokens, ToTokens};
+    use syn::{Expr, parse_token_trees};
+
+    use super::{Widget, parse};
+    use super::EitherWidget::{Gtk, Relm};
+
+    fn error(view: &str) -> String {
+        parse(&parse_token_trees(view).expect("tokens"), "gtk").expect_err("error").to_string()
+    }
+
+    fn exprs(exprs: &[Expr]) -> Vec<String> {
+        exprs.iter()
+            .map(|expr| {
+                let mut tokens = Tokens::new();
+                expr.to_tokens(&mut tokens);
+                tokens.as_str().replace(' ', "")
+            })
+            .collect()
+    }
+
+    fn param_names(widget: &Widget) -> Vec<String> {
+        match widget.widget {
+            Relm(ref relm_widget) => relm_widget.param_names.iter().map(|name| name.to_string()).collect(),
+            Gtk(_) => vec![],
+        }
+    }
+
+    fn widget(view: &str) -> Widget {
+        parse(&parse_token_trees(view).expect("tokens"), "gtk").expect("widget")
+    }
+
+    #[test]
+    fn named_parameters() {
+        let widget = widget("Counter(label: convert::<A, B>(x), step: |a, b| a + b, max: 10,) {}");
+        assert_eq!(param_names(&widget), vec!["label", "step", "max"]);
+        assert_eq!(exprs(&widget.init_parameters), vec!["convert::<A,B>(x)", "|a,b|a+b", "10"]);
+
+        assert_eq!(error("Counter(label: x, label: y) {}"),
+            "parameter `label` specified more than once in view! macro");
+        assert_eq!(error("Counter(label: x, max 10) {}"),
+            "expected `:` after the parameter `max`, found `10` in view! macro");
+    }
+
+    #[test]
+    fn named_parameter_colons() {
+        let my_widget = widget("MyWidget(label: \"x\", max: 10) {}");
+        assert_eq!(param_names(&my_widget), vec!["label", "max"]);
+        assert_eq!(exprs(&my_widget.init_parameters), vec!["\"x\"", "10"]);
+        // Only the `:` following a complete expression separates a parameter from the next name.
+        let size_widget = widget("MyWidget(size: Size { width: ::std::i32::MAX, height: 10 }, max: 10) {}");
+        assert_eq!(param_names(&size_widget), vec!["size", "max"]);
+        assert_eq!(exprs(&size_widget.init_parameters), vec!["Size{width:::std::i32::MAX,height:10}", "10"]);
+    }
+
+    #[test]
+    fn positional_parameters() {
+        let counter = widget("Counter(convert::<A, B>(x), |a, b| a + b) {}");
+        assert_eq!(exprs(&counter.init_parameters), vec!["convert::<A,B>(x)", "|a,b|a+b"]);
+        let button = widget("gtk::Button(label::<A, B>(),) {}");
+        assert_eq!(exprs(&button.init_parameters), vec!["label::<A,B>()"]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("gtk::Button { Label: \"+\" }"), "unknown property `Label` of `gtk::Button` in view! macro: \
+            the properties of the gtk widgets are written in snake_case");
+        assert_eq!(error("gtk::Button { label \"+\" }"), "expected `:`, `=>` or `(` after `label`, found `\"+\"` in \
+            view! macro");
+    }
 }
@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Static layout of the view files, which is reloaded at runtime in debug builds.
+//!
+//! Only the widgets that can be found at runtime are reloaded, i.e. the root widget and the
+//! widgets having a `#[name]` attribute, and only their literal properties and child properties:
+//! the widget tree is not reloaded.
+
+use syn::Ident;
+
+use error::Result;
+use glade::literal_value;
+use parser::{Widget, parse, parse_view_file_content};
+use parser::EitherWidget::Gtk;
+
+/// A literal property of a widget from a view file, in its GtkBuilder representation.
+#[derive(Debug)]
+pub struct StaticProperty {
+    /// Whether this is a child property.
+    pub child: bool,
+    /// The name of the property, like `label`.
+    pub name: String,
+    /// The value of the property, like `True` or `vertical`.
+    pub value: String,
+    /// The name of the widget, or `None` for the root widget.
+    pub widget: Option<String>,
+}
+
+/// Get the literal properties of the widgets of a view file.
+pub fn static_properties(path: &str, content: &str) -> Result<Vec<StaticProperty>> {
+    // Only the properties are reloaded, so the path of the gtk types does not matter.
+    let tokens = parse_view_file_content(path, content, "gtk")?;
+    let widget = parse(&tokens, "gtk")?;
+    let mut properties = vec![];
+    for (name, widget) in reloadable_widgets(&widget) {
+        let name = name.map(|name| name.to_string());
+        for (property, value) in &widget.properties {
+            if let Some(value) = literal_value(value) {
+                properties.push(StaticProperty {
+                    child: false,
+                    name: property.replace('_', "-"),
+                    value,
+                    widget: name.clone(),
+                });
+            }
+        }
+        for (property, value) in &widget.child_properties {
+            if let Some(value) = literal_value(value) {
+                properties.push(StaticProperty {
+                    child: true,
+                    name: property.replace('_', "-"),
+                    value,
+                    widget: name.clone(),
+                });
+            }
+        }
+    }
+    Ok(properties)
+}
+
+/// Get the gtk widgets which are reloaded, with their name (`None` for the root widget).
+pub fn reloadable_widgets(widget: &Widget) -> Vec<(Option<&Ident>, &Widget)> {
+    let mut widgets = vec![];
+    if let Gtk(_) = widget.widget {
+        widgets.push((None, widget));
+    }
+    add_named_widgets(widget, &mut widgets);
+    widgets
+}
+
+fn add_named_widgets<'a>(widget: &'a Widget, widgets: &mut Vec<(Option<&'a Ident>, &'a Widget)>) {
+    for child in &widget.children {
+        if let Gtk(ref gtk_widget) = child.widget {
+            if gtk_widget.save {
+                widgets.push((Some(&child.name), child));
+            }
+        }
+        add_named_widgets(child, widgets);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::static_properties;
+
+    fn properties(content: &str) -> Vec<String> {
+        let mut properties: Vec<_> = static_properties("view.relm", content).expect("properties").iter()
+            .map(|property| {
+                let widget =
+                    match property.widget {
+                        Some(ref widget) => widget.as_str(),
+                        None => "root",
+                    };
+                let kind = if property.child { "child " } else { "" };
+                format!("{} {}{}={}", widget, kind, property.name, property.value)
+            })
+            .collect();
+        properties.sort();
+        properties
+    }
+
+    #[test]
+    fn modified_file() {
+        let view = |orientation: &str, spacing: &str, text: &str| format!("view! {{
+            gtk::Box {{
+                orientation: {},
+                spacing: {},
+                #[name=\"label\"]
+                gtk::Label {{
+                    child: {{
+                        expand: true,
+                    }},
+                    text: {},
+                }},
+                gtk::Button {{
+                    clicked => Increment,
+                    label: \"+\",
+                }},
+            }}
+        }}", orientation, spacing, text);
+        assert_eq!(properties(&view("Vertical", "5", "\"0\"")), vec![
+            "label child expand=True", "label text=0", "root orientation=vertical", "root spacing=5"]);
+        // The widgets without a name are not reloaded and the properties using the model are only
+        // updated when recompiling.
+        assert_eq!(properties(&view("Horizontal", "-10", "&self.model.counter.to_string()")), vec![
+            "label child expand=True", "root orientation=horizontal", "root spacing=-10"]);
+    }
+}
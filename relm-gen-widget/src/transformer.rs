@@ -21,11 +21,14 @@
 
 //! Transformer to transform the self.model by the actual model identifier.
 
+use quote::{Tokens, ToTokens};
 use syn;
-use syn::{Expr, Ident, parse_path};
+use syn::{Delimited, Expr, Ident, Mac, TokenTree, parse_path, parse_token_trees};
 use syn::fold::{Folder, noop_fold_expr};
 use syn::ExprKind::{Field, Path};
 
+use walker::{parse_macro_arg, split_macro_args};
+
 pub struct Transformer {
     model_ident: String,
 }
@@ -59,4 +62,80 @@ impl Folder for Transformer {
         }
         noop_fold_expr(self, expr)
     }
+
+    fn fold_mac(&mut self, mac: Mac) -> Mac {
+        fold_macro_args(self, mac)
+    }
+}
+
+/// Transformer to replace self by the identifier of the widget struct, in the expressions
+/// evaluated after its creation.
+pub struct SelfTransformer {
+    self_ident: String,
+}
+
+impl SelfTransformer {
+    pub fn new(self_ident: &str) -> Self {
+        SelfTransformer {
+            self_ident: self_ident.to_string(),
+        }
+    }
+}
+
+impl Folder for SelfTransformer {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        if let Path(None, syn::Path { ref segments, .. }) = expr.node {
+            if segments.len() == 1 && segments[0].ident == Ident::new("self") {
+                let path = parse_path(&self.self_ident).expect("self path");
+                return Expr {
+                    node: Path(None, path),
+                    attrs: vec![],
+                };
+            }
+        }
+        noop_fold_expr(self, expr)
+    }
+
+    fn fold_mac(&mut self, mac: Mac) -> Mac {
+        fold_macro_args(self, mac)
+    }
+}
+
+/// Fold the arguments of a macro invocation which are expressions, like `self.model.count` in
+/// `format!("{}", self.model.count)`.
+pub fn fold_macro_args<F: Folder>(folder: &mut F, mac: Mac) -> Mac {
+    let args = split_macro_args(&mac);
+    if args.is_empty() {
+        return mac;
+    }
+    let mut new_tts = vec![];
+    for (arg, separator) in args {
+        match parse_macro_arg(&arg) {
+            Some(expr) => {
+                let new_expr = folder.fold_expr(expr.clone());
+                if new_expr == expr {
+                    new_tts.extend(arg);
+                }
+                else {
+                    let mut tokens = Tokens::new();
+                    new_expr.to_tokens(&mut tokens);
+                    new_tts.extend(parse_token_trees(tokens.as_str()).expect("parse_token_trees() in fold_macro_args"));
+                }
+            },
+            None => new_tts.extend(arg),
+        }
+        new_tts.extend(separator);
+    }
+    let delim =
+        match mac.tts[0] {
+            TokenTree::Delimited(Delimited { ref delim, .. }) => delim.clone(),
+            _ => unreachable!(),
+        };
+    Mac {
+        path: mac.path,
+        tts: vec![TokenTree::Delimited(Delimited {
+            delim,
+            tts: new_tts,
+        })],
+    }
 }
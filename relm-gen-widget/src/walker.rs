@@ -21,36 +21,255 @@
 
 //! Visitor to get all the model attribute used in an expression.
 
+use std::collections::HashMap;
+
+use quote::{Tokens, ToTokens};
 use syn;
-use syn::{Expr, Ident};
-use syn::ExprKind::{Field, Path};
+use syn::{Delimited, Expr, Ident, Mac, Token, TokenTree, parse_expr};
+use syn::ExprKind::{Field, MethodCall, Path};
+use syn::Token::{Comma, Semi};
 use syn::visit::{Visitor, walk_expr};
 
-pub struct ModelVariableVisitor {
-    pub idents: Vec<Ident>,
+/// Path of a model variable: `self.model.user.name` is `[user, name]`.
+pub type ModelPath = Vec<Ident>;
+
+/// Map the `&self` helper methods to the model variables they read.
+pub type HelperMethods = HashMap<Ident, Vec<ModelPath>>;
+
+pub struct ModelVariableVisitor<'a> {
+    helper_methods: &'a HelperMethods,
+    pub paths: Vec<ModelPath>,
+    pub self_methods: Vec<Ident>,
 }
 
-impl ModelVariableVisitor {
-    pub fn new() -> Self {
+impl<'a> ModelVariableVisitor<'a> {
+    pub fn new(helper_methods: &'a HelperMethods) -> Self {
         ModelVariableVisitor {
-            idents: vec![],
+            helper_methods,
+            paths: vec![],
+            self_methods: vec![],
+        }
+    }
+
+    fn add_path(&mut self, path: ModelPath) {
+        if !self.paths.contains(&path) {
+            self.paths.push(path);
         }
     }
 }
 
-impl Visitor for ModelVariableVisitor {
+impl<'a> Visitor for ModelVariableVisitor<'a> {
     fn visit_expr(&mut self, expr: &Expr) {
-        if let Field(ref obj, ref field) = expr.node {
-            if let Field(ref expr, ref model_ident) = obj.node {
-                if let Expr { node: Path(None, syn::Path { ref segments, .. }), .. } = **expr {
-                    if *model_ident == Ident::new("model") &&
-                        segments.get(0).map(|segment| &segment.ident) == Some(&Ident::new("self"))
-                    {
-                        self.idents.push(field.clone());
+        if let Some(path) = model_path(expr) {
+            // Do not visit the sub-expressions to only keep the full path.
+            self.add_path(path);
+            return;
+        }
+        if let MethodCall(ref method, _, ref args) = expr.node {
+            if args.first().map(is_self) == Some(true) {
+                if let Some(paths) = self.helper_methods.get(method) {
+                    for path in paths.clone() {
+                        self.add_path(path);
                     }
                 }
+                if !self.self_methods.contains(method) {
+                    self.self_methods.push(method.clone());
+                }
             }
         }
         walk_expr(self, expr);
     }
+
+    fn visit_mac(&mut self, mac: &Mac) {
+        for (arg, _) in split_macro_args(mac) {
+            if let Some(expr) = parse_macro_arg(&arg) {
+                self.visit_expr(&expr);
+            }
+        }
+    }
+}
+
+/// Split the arguments of a macro invocation like `format!("{}", self.model.count)` or
+/// `vec![0; self.model.count]` at the commas and semicolons, keeping the separators.
+/// Return nothing if the macro is not invoked with a single delimited group of tokens.
+/// See [`take_expr`](fn.take_expr.html) for the separators which are kept in an argument.
+pub fn split_macro_args(mac: &Mac) -> Vec<(Vec<TokenTree>, Option<TokenTree>)> {
+    let mut args = vec![];
+    if mac.tts.len() == 1 {
+        if let TokenTree::Delimited(Delimited { tts: ref all_tts, .. }) = mac.tts[0] {
+            let mut tts = &all_tts[..];
+            while !tts.is_empty() {
+                let (arg, separator, rest) = take_expr(tts, &[Comma, Semi]);
+                args.push((arg.to_vec(), separator.cloned()));
+                tts = rest;
+            }
+        }
+    }
+    args
+}
+
+/// Split the tokens of an expression from the separator following it and the rest of the tokens.
+/// A separator only ends the expression when the tokens before it form an expression, so that the
+/// commas in `foo::<A, B>(x)` and `|a, b| a + b` are kept.
+/// The tokens before a separator which form an expression on their own still end it: `a < b, c > (d)`
+/// is split after `a < b`.
+pub fn take_expr<'a>(tts: &'a [TokenTree], separators: &[Token]) -> (&'a [TokenTree], Option<&'a TokenTree>,
+    &'a [TokenTree])
+{
+    for (index, token) in tts.iter().enumerate() {
+        if let TokenTree::Token(ref token) = *token {
+            if separators.contains(token) && parse_macro_arg(&tts[..index]).is_some() {
+                return (&tts[..index], Some(&tts[index]), &tts[index + 1..]);
+            }
+        }
+    }
+    (tts, None, &[])
+}
+
+/// Parse a macro argument as an expression, if it is one.
+pub fn parse_macro_arg(arg: &[TokenTree]) -> Option<Expr> {
+    let mut tokens = Tokens::new();
+    for token in arg {
+        token.to_tokens(&mut tokens);
+    }
+    parse_expr(tokens.as_str()).ok()
+}
+
+/// Check if the expression calls a method on `self`, like `self.title()`.
+pub fn calls_self_method(expr: &Expr) -> bool {
+    let helper_methods = HashMap::new();
+    let mut visitor = ModelVariableVisitor::new(&helper_methods);
+    visitor.visit_expr(expr);
+    !visitor.self_methods.is_empty()
+}
+
+/// Get the path of the model variable `self.model.user.name`.
+pub fn model_path(expr: &Expr) -> Option<ModelPath> {
+    if let Field(ref obj, ref field) = expr.node {
+        if is_model(obj) {
+            return Some(vec![field.clone()]);
+        }
+        return model_path(obj).map(|mut path| {
+            path.push(field.clone());
+            path
+        });
+    }
+    None
+}
+
+/// Check if a modification of one path affects the other, i.e. if one is a prefix of the other.
+pub fn paths_overlap(path1: &[Ident], path2: &[Ident]) -> bool {
+    path1.iter().zip(path2).all(|(ident1, ident2)| ident1 == ident2)
+}
+
+fn is_model(expr: &Expr) -> bool {
+    if let Field(ref expr, ref ident) = expr.node {
+        return *ident == Ident::new("model") && is_self(expr);
+    }
+    false
+}
+
+pub fn is_self(expr: &Expr) -> bool {
+    if let Expr { node: Path(None, syn::Path { ref segments, .. }), .. } = *expr {
+        return segments.len() == 1 && segments[0].ident == Ident::new("self");
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use quote::{Tokens, ToTokens};
+    use syn::{Ident, parse_expr};
+    use syn::ExprKind::Mac;
+    use syn::visit::Visitor;
+
+    use super::{ModelPath, ModelVariableVisitor, model_path, paths_overlap, split_macro_args};
+
+    fn path(idents: &[&str]) -> ModelPath {
+        idents.iter().map(|ident| Ident::new(*ident)).collect()
+    }
+
+    fn model_paths(expr: &str) -> Vec<ModelPath> {
+        let helper_methods = HashMap::new();
+        let mut visitor = ModelVariableVisitor::new(&helper_methods);
+        visitor.visit_expr(&parse_expr(expr).expect("expr"));
+        visitor.paths
+    }
+
+    /// Get the arguments of the macro invocation, followed by their separator.
+    fn macro_args(code: &str) -> Vec<String> {
+        let expr = parse_expr(code).expect("expr");
+        let mac =
+            match expr.node {
+                Mac(mac) => mac,
+                _ => return vec![],
+            };
+        split_macro_args(&mac).into_iter()
+            .map(|(arg, separator)| {
+                let mut tokens = Tokens::new();
+                tokens.append_all(&arg);
+                separator.to_tokens(&mut tokens);
+                tokens.as_str().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn format_args() {
+        assert_eq!(macro_args("format!(\"{} {}\", self.model.count, name)"),
+            vec!["\"{} {}\" ,", "self . model . count ,", "name"]);
+        assert_eq!(macro_args("format!(\"{count}\", count = self.model.count,)"),
+            vec!["\"{count}\" ,", "count = self . model . count ,"]);
+        assert_eq!(macro_args("format!(\"\")"), vec!["\"\""]);
+    }
+
+    #[test]
+    fn vec_args() {
+        assert_eq!(macro_args("vec![0; self.model.count]"), vec!["0 ;", "self . model . count"]);
+        assert_eq!(macro_args("vec![]"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn args_with_commas() {
+        assert_eq!(macro_args("format!(\"{}\", convert::<A, B>(self.model.count))"),
+            vec!["\"{}\" ,", "convert :: < A , B > ( self . model . count )"]);
+        assert_eq!(macro_args("call!(|a, b| a + b, self.model.count)"),
+            vec!["| a , b | a + b ,", "self . model . count"]);
+    }
+
+    #[test]
+    fn macro_paths() {
+        assert_eq!(model_paths("format!(\"{} {x}\", self.model.a, x = self.model.b)"),
+            vec![path(&["a"]), path(&["b"])]);
+        assert_eq!(model_paths("vec![self.model.a; self.model.b.len()]"), vec![path(&["a"]), path(&["b"])]);
+        assert_eq!(model_paths("format!(\"{}\", convert::<A, B>(self.model.a))"), vec![path(&["a"])]);
+    }
+
+    #[test]
+    fn model_paths_of_fields() {
+        assert_eq!(model_path(&parse_expr("self.model.count").expect("expr")), Some(path(&["count"])));
+        assert_eq!(model_path(&parse_expr("self.model.user.name").expect("expr")), Some(path(&["user", "name"])));
+        assert_eq!(model_path(&parse_expr("self.model").expect("expr")), None);
+        assert_eq!(model_path(&parse_expr("model.count").expect("expr")), None);
+        assert_eq!(model_path(&parse_expr("self.other.count").expect("expr")), None);
+        assert_eq!(model_path(&parse_expr("self.model.items[0]").expect("expr")), None);
+    }
+
+    #[test]
+    fn overlapping_paths() {
+        assert!(paths_overlap(&path(&["user"]), &path(&["user", "name"])));
+        assert!(paths_overlap(&path(&["user", "name"]), &path(&["user"])));
+        assert!(paths_overlap(&path(&["user", "name"]), &path(&["user", "name"])));
+        assert!(!paths_overlap(&path(&["user", "name"]), &path(&["user", "age"])));
+        assert!(!paths_overlap(&path(&["user"]), &path(&["count"])));
+    }
+
+    #[test]
+    fn visited_paths() {
+        // Only the full path is kept.
+        assert_eq!(model_paths("self.model.user.name.len()"), vec![path(&["user", "name"])]);
+        assert_eq!(model_paths("self.model.a + self.model.b + self.model.a"), vec![path(&["a"]), path(&["b"])]);
+    }
 }
@@ -41,8 +41,16 @@ extern crate relm_core;
 
 mod into;
 mod macros;
+mod main_loop;
+#[cfg(debug_assertions)]
+mod recorder;
+mod runtime;
 mod stream;
+mod time;
 
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::{Rc, Weak};
 use std::time::SystemTime;
 
 use futures::{Future, Stream};
@@ -51,6 +59,12 @@ use futures_glib::{Executor, MainContext};
 pub use relm_core::EventStream;
 
 pub use into::{IntoOption, IntoPair};
+pub use main_loop::{ExitStatus, quit, run_loop, run_main_loop};
+#[cfg(debug_assertions)]
+pub use recorder::{Change, Entry, Recorder, diff};
+pub use runtime::TestRuntime;
+use runtime::TestQueue;
+pub use time::{Interval, Timeout, advance_virtual_clock, now, use_virtual_clock};
 use stream::ToStream;
 
 macro_rules! relm_connect {
@@ -85,15 +99,19 @@ macro_rules! relm_connect_ignore {
 
 /// Handle connection of futures to send messages to the [`update()`](trait.Update.html#tymethod.update) method.
 pub struct Relm<UPDATE: Update> {
-    executor: Executor,
+    component: Rc<RefCell<Weak<RefCell<UPDATE>>>>,
+    executor: Option<Executor>,
     stream: EventStream<UPDATE::Msg>,
+    test_queue: Option<Rc<RefCell<TestQueue<UPDATE::Msg>>>>,
 }
 
 impl<UPDATE: Update> Clone for Relm<UPDATE> {
     fn clone(&self) -> Self {
         Relm {
+            component: self.component.clone(),
             executor: self.executor.clone(),
             stream: self.stream.clone(),
+            test_queue: self.test_queue.clone(),
         }
     }
 }
@@ -102,8 +120,19 @@ impl<UPDATE: Update> Relm<UPDATE> {
     /// Create a new relm stream handler.
     pub fn new(executor: Executor, stream: EventStream<UPDATE::Msg>) -> Self {
         Relm {
-            executor,
+            component: Rc::new(RefCell::new(Weak::new())),
+            executor: Some(executor),
             stream,
+            test_queue: None,
+        }
+    }
+
+    fn new_test(stream: EventStream<UPDATE::Msg>, test_queue: Rc<RefCell<TestQueue<UPDATE::Msg>>>) -> Self {
+        Relm {
+            component: Rc::new(RefCell::new(Weak::new())),
+            executor: None,
+            stream,
+            test_queue: Some(test_queue),
         }
     }
 
@@ -169,38 +198,72 @@ impl<UPDATE: Update> Relm<UPDATE> {
     }
 
     /// Connect the future `to_stream` and spawn it on the tokio main loop.
+    ///
+    /// In a [`TestRuntime`](struct.TestRuntime.html), the future is not spawned: the test fakes
+    /// its outcome instead.
     pub fn connect_exec<CALLBACK, FAILCALLBACK, STREAM, TOSTREAM>(&self, to_stream: TOSTREAM, callback: CALLBACK,
             failure_callback: FAILCALLBACK)
         where CALLBACK: Fn(STREAM::Item) -> UPDATE::Msg + 'static,
               FAILCALLBACK: Fn(STREAM::Error) -> UPDATE::Msg + 'static,
               STREAM: Stream + 'static,
+              STREAM::Error: 'static,
+              STREAM::Item: 'static,
               TOSTREAM: ToStream<STREAM, Item=STREAM::Item, Error=STREAM::Error> + 'static,
               UPDATE: 'static,
               UPDATE::Msg: 'static,
     {
-        self.exec(self.connect(to_stream, callback, failure_callback));
+        if let Some(ref test_queue) = self.test_queue {
+            test_queue.borrow_mut().connect(callback, Some(failure_callback));
+        }
+        else {
+            self.exec(self.connect(to_stream, callback, failure_callback));
+        }
     }
 
     /// Connect the future `to_stream` and spawn it on the tokio main loop, ignoring any error.
     pub fn connect_exec_ignore_err<CALLBACK, STREAM, TOSTREAM>(&self, to_stream: TOSTREAM, callback: CALLBACK)
         where CALLBACK: Fn(STREAM::Item) -> UPDATE::Msg + 'static,
               STREAM: Stream + 'static,
+              STREAM::Error: 'static,
+              STREAM::Item: 'static,
               TOSTREAM: ToStream<STREAM, Item=STREAM::Item, Error=STREAM::Error> + 'static,
               UPDATE: 'static,
               UPDATE::Msg: 'static,
     {
-        self.exec(self.connect_ignore_err(to_stream, callback));
+        if let Some(ref test_queue) = self.test_queue {
+            test_queue.borrow_mut().connect(callback, None::<fn(STREAM::Error) -> UPDATE::Msg>);
+        }
+        else {
+            self.exec(self.connect_ignore_err(to_stream, callback));
+        }
     }
 
     /// Spawn a future in the tokio event loop.
     pub fn exec<FUTURE: Future<Item=(), Error=()> + 'static>(&self, future: FUTURE) {
-        // NOTE: no error can be returned from execute(), hence unwrap().
-        self.executor.execute(future).unwrap();
+        match self.executor {
+            // NOTE: no error can be returned from execute(), hence unwrap().
+            Some(ref executor) => executor.execute(future).unwrap(),
+            None => {
+                if let Some(ref test_queue) = self.test_queue {
+                    test_queue.borrow_mut().exec(future);
+                }
+            },
+        }
     }
 
     /// Get the handle of this stream.
+    ///
+    /// ## Panics
+    /// This function panics when called from a component running in a
+    /// [`TestRuntime`](struct.TestRuntime.html).
     pub fn executor(&self) -> &Executor {
-        &self.executor
+        self.executor.as_ref().expect("no executor in a test runtime")
+    }
+
+    /// Quit the innermost main loop with the exit status `code`.
+    /// See [`quit()`](fn.quit.html).
+    pub fn quit(&self, code: i32) {
+        quit(code);
     }
 
     /// Get the event stream of this stream.
@@ -208,6 +271,23 @@ impl<UPDATE: Update> Relm<UPDATE> {
     pub fn stream(&self) -> &EventStream<UPDATE::Msg> {
         &self.stream
     }
+
+    /// Call `callback` with the component, unless it is busy handling a message or it is not
+    /// initialized yet.
+    /// Return whether `callback` was called.
+    ///
+    /// This is used internally by the library for the two-way bindings.
+    #[doc(hidden)]
+    pub fn with_component<CALLBACK: FnOnce(&mut UPDATE)>(&self, callback: CALLBACK) -> bool {
+        let component = self.component.borrow().upgrade();
+        if let Some(component) = component {
+            if let Ok(mut component) = component.try_borrow_mut() {
+                callback(&mut *component);
+                return true;
+            }
+        }
+        false
+    }
 }
 
 /// Trait for a basic (non-widget) component.
@@ -243,6 +323,21 @@ pub trait UpdateNew: Update {
     fn new(_relm: &Relm<Self>, _model: Self::Model) -> Self;
 }
 
+/// Trait to implement to be able to record and restore the model of a component.
+///
+/// The models are only recorded in debug builds, but this trait is always available so that the
+/// implementations do not depend on the build profile.
+pub trait Record: Update
+    where Self::Model: Clone + Debug,
+{
+    /// Get a copy of the current model.
+    fn snapshot(&self) -> Self::Model;
+
+    /// Replace the current model by `model`.
+    /// For a widget, this method should also update the view to reflect the new model.
+    fn restore(&mut self, model: Self::Model);
+}
+
 /// Format trait for enum variants.
 ///
 /// `DisplayVariant` is similar to `Debug`, but only works on enum and does not list the
@@ -286,15 +381,86 @@ where UPDATE: Update + UpdateNew + 'static
 
 /// Initialize a component by creating its subscriptions and dispatching the messages from the
 /// stream.
-pub fn init_component<UPDATE>(stream: &EventStream<UPDATE::Msg>, mut component: UPDATE, executor: &Executor,
+pub fn init_component<UPDATE>(stream: &EventStream<UPDATE::Msg>, component: UPDATE, executor: &Executor,
     relm: &Relm<UPDATE>)
     where UPDATE: Update + 'static,
           UPDATE::Msg: DisplayVariant + 'static,
+{
+    init_shared_component(stream, Rc::new(RefCell::new(component)), executor, relm, |_| ());
+}
+
+/// Initialize a component like [`init_component()`](fn.init_component.html), but keep it shared so
+/// that it can be reached outside of the `update()` method.
+/// `after_update` is called after each message is handled.
+///
+/// This is used internally by the library.
+#[doc(hidden)]
+pub fn init_shared_component<UPDATE, CALLBACK>(stream: &EventStream<UPDATE::Msg>, component: Rc<RefCell<UPDATE>>,
+    executor: &Executor, relm: &Relm<UPDATE>, after_update: CALLBACK)
+    where CALLBACK: Fn(&mut UPDATE) + 'static,
+          UPDATE: Update + 'static,
+          UPDATE::Msg: DisplayVariant + 'static,
 {
     let stream = stream.clone();
-    component.subscriptions(relm);
+    *relm.component.borrow_mut() = Rc::downgrade(&component);
+    component.borrow_mut().subscriptions(relm);
+    let event_future = stream.for_each(move |event| {
+        let mut component = component.borrow_mut();
+        update_component(&mut *component, event);
+        after_update(&mut *component);
+        Ok(())
+    });
+    // NOTE: no error can be returned from execute(), hence unwrap().
+    executor.execute(event_future).unwrap();
+}
+
+/// Initialize a component like [`init_component()`](fn.init_component.html) while recording every
+/// message it receives, with the resulting model, in `recorder`.
+///
+/// This function is only available in debug builds.
+#[cfg(debug_assertions)]
+pub fn init_component_recorded<UPDATE>(stream: &EventStream<UPDATE::Msg>, component: UPDATE,
+    executor: &Executor, relm: &Relm<UPDATE>, recorder: &Recorder<UPDATE>)
+    where UPDATE: Record + 'static,
+          UPDATE::Model: Clone + Debug + 'static,
+          UPDATE::Msg: DisplayVariant + 'static,
+{
+    init_shared_component_recorded(stream, Rc::new(RefCell::new(component)), executor, relm, recorder, |_| ());
+}
+
+/// Initialize a shared component like
+/// [`init_component_recorded()`](fn.init_component_recorded.html).
+///
+/// This is used internally by the library.
+#[cfg(debug_assertions)]
+#[doc(hidden)]
+pub fn init_shared_component_recorded<UPDATE, CALLBACK>(stream: &EventStream<UPDATE::Msg>,
+    component: Rc<RefCell<UPDATE>>, executor: &Executor, relm: &Relm<UPDATE>, recorder: &Recorder<UPDATE>,
+    after_update: CALLBACK)
+    where CALLBACK: Fn(&mut UPDATE) + 'static,
+          UPDATE: Record + 'static,
+          UPDATE::Model: Clone + Debug + 'static,
+          UPDATE::Msg: DisplayVariant + 'static,
+{
+    let stream = stream.clone();
+    *relm.component.borrow_mut() = Rc::downgrade(&component);
+    component.borrow_mut().subscriptions(relm);
+    recorder.record("init", component.borrow().snapshot());
+    {
+        let component = Rc::downgrade(&component);
+        recorder.set_restore(move |model| {
+            if let Some(component) = component.upgrade() {
+                component.borrow_mut().restore(model);
+            }
+        });
+    }
+    let recorder = recorder.clone();
     let event_future = stream.for_each(move |event| {
-        update_component(&mut component, event);
+        let variant = event.display_variant();
+        let mut component = component.borrow_mut();
+        update_component(&mut *component, event);
+        recorder.record(variant, component.snapshot());
+        after_update(&mut *component);
         Ok(())
     });
     // NOTE: no error can be returned from execute(), hence unwrap().
@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Main loops owned by relm, which can be nested and return an exit status.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use futures_glib::MainLoop;
+
+thread_local! {
+    static LOOPS: RefCell<Vec<(Rc<Fn()>, Rc<Cell<Option<i32>>>)>> = RefCell::new(vec![]);
+}
+
+/// The exit status of a main loop.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExitStatus {
+    code: i32,
+}
+
+impl ExitStatus {
+    /// Get the code given to [`quit()`](fn.quit.html).
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// Check if the code is 0.
+    pub fn success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+/// Quit the innermost main loop started by [`run_main_loop()`](fn.run_main_loop.html), which will
+/// return `code`.
+/// This function does nothing if no such loop is running.
+pub fn quit(code: i32) {
+    LOOPS.with(|loops| {
+        if let Some(&(ref quit_loop, ref exit_code)) = loops.borrow().last() {
+            exit_code.set(Some(code));
+            quit_loop();
+        }
+    });
+}
+
+/// Run a new main loop until [`quit()`](fn.quit.html) is called.
+///
+/// This can be called from an `update()` method to wait for the result of a modal flow: the
+/// messages are still dispatched to every component while the nested loop is running.
+pub fn run_main_loop() -> ExitStatus {
+    let main_loop = MainLoop::new(None);
+    let quit_loop = main_loop.clone();
+    run_loop(move || main_loop.run(), move || quit_loop.quit())
+}
+
+/// Call `run`, which runs a loop until `quit_loop` is called, with `quit_loop` being called by
+/// [`quit()`](fn.quit.html) in the meantime.
+/// This is used to run the `gtk::Application`s like the other main loops.
+///
+/// The exit status is 0 when the loop was stopped without calling [`quit()`](fn.quit.html).
+#[doc(hidden)]
+pub fn run_loop<RUN: FnOnce(), QUIT: Fn() + 'static>(run: RUN, quit_loop: QUIT) -> ExitStatus {
+    let code = Rc::new(Cell::new(None));
+    LOOPS.with(|loops| loops.borrow_mut().push((Rc::new(quit_loop), code.clone())));
+    run();
+    LOOPS.with(|loops| {
+        let _ = loops.borrow_mut().pop();
+    });
+    ExitStatus {
+        code: code.get().unwrap_or(0),
+    }
+}
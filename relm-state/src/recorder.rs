@@ -0,0 +1,316 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Recorder of the messages dispatched to a component, used by the time-travel debugger.
+//!
+//! The recorder is only compiled in debug builds.
+
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use super::Update;
+
+use self::Change::*;
+
+/// A line of the difference between two model snapshots.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// Line only present in the new snapshot.
+    Added(String),
+    /// Line only present in the old snapshot.
+    Removed(String),
+    /// Line present in both snapshots.
+    Same(String),
+}
+
+/// A message dispatched to a component with the model as it was after the call to `update()`.
+#[derive(Clone)]
+pub struct Entry<MODEL> {
+    /// The variant of the message.
+    pub msg: &'static str,
+    /// The model after the message was handled.
+    pub model: MODEL,
+}
+
+struct History<UPDATE: Update> {
+    entries: Vec<Entry<UPDATE::Model>>,
+    observers: Vec<Rc<Fn(usize)>>,
+    restore: Option<Rc<Fn(UPDATE::Model)>>,
+}
+
+/// History of the messages and model snapshots of a component.
+///
+/// The first entry is the initial model of the component.
+pub struct Recorder<UPDATE: Update> {
+    history: Rc<RefCell<History<UPDATE>>>,
+}
+
+impl<UPDATE: Update> Clone for Recorder<UPDATE> {
+    fn clone(&self) -> Self {
+        Recorder {
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl<UPDATE: Update> Default for Recorder<UPDATE>
+    where UPDATE::Model: Clone + Debug,
+{
+    fn default() -> Self {
+        Recorder::new()
+    }
+}
+
+impl<UPDATE: Update> Recorder<UPDATE>
+    where UPDATE::Model: Clone + Debug,
+{
+    /// Create an empty recorder.
+    pub fn new() -> Self {
+        Recorder {
+            history: Rc::new(RefCell::new(History {
+                entries: vec![],
+                observers: vec![],
+                restore: None,
+            })),
+        }
+    }
+
+    /// Get the changes to the model made by the message at `index`.
+    pub fn diff(&self, index: usize) -> Vec<Change> {
+        let history = self.history.borrow();
+        match history.entries.get(index) {
+            Some(entry) => {
+                let new = format!("{:#?}", entry.model);
+                let old =
+                    if index > 0 {
+                        format!("{:#?}", history.entries[index - 1].model)
+                    }
+                    else {
+                        String::new()
+                    };
+                diff(&old, &new)
+            },
+            None => vec![],
+        }
+    }
+
+    /// Get the entry at `index`.
+    pub fn entry(&self, index: usize) -> Option<Entry<UPDATE::Model>> {
+        self.history.borrow().entries.get(index).cloned()
+    }
+
+    /// Check if nothing was recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.history.borrow().entries.is_empty()
+    }
+
+    /// Get the number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.history.borrow().entries.len()
+    }
+
+    /// Add an observer to the recorder.
+    /// This callback will be called with the new number of entries every time the history changes.
+    pub fn observe<CALLBACK: Fn(usize) + 'static>(&self, callback: CALLBACK) {
+        self.history.borrow_mut().observers.push(Rc::new(callback));
+    }
+
+    /// Restore the model recorded at `index` in the component and forget the entries after it, so
+    /// that the next messages are recorded from there.
+    pub fn replay_from(&self, index: usize) {
+        let (model, restore) = {
+            let mut history = self.history.borrow_mut();
+            if index >= history.entries.len() {
+                return;
+            }
+            history.entries.truncate(index + 1);
+            (history.entries[index].model.clone(), history.restore.clone())
+        };
+        if let Some(restore) = restore {
+            restore(model);
+        }
+        self.notify();
+    }
+
+    #[doc(hidden)]
+    pub fn record(&self, msg: &'static str, model: UPDATE::Model) {
+        self.history.borrow_mut().entries.push(Entry {
+            msg,
+            model,
+        });
+        self.notify();
+    }
+
+    #[doc(hidden)]
+    pub fn set_restore<CALLBACK: Fn(UPDATE::Model) + 'static>(&self, callback: CALLBACK) {
+        self.history.borrow_mut().restore = Some(Rc::new(callback));
+    }
+
+    fn notify(&self) {
+        let (len, observers) = {
+            let history = self.history.borrow();
+            (history.entries.len(), history.observers.clone())
+        };
+        for observer in observers {
+            observer(len);
+        }
+    }
+}
+
+/// Compute the line-by-line difference between `old` and `new`.
+pub fn diff(old: &str, new: &str) -> Vec<Change> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    // Length of the longest common subsequence of old[i..] and new[j..].
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] =
+                if old[i] == new[j] {
+                    lengths[i + 1][j + 1] + 1
+                }
+                else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+        }
+    }
+    let mut changes = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        }
+        else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            changes.push(Removed(old[i].to_string()));
+            i += 1;
+        }
+        else {
+            changes.push(Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().map(|line| Removed(line.to_string())));
+    changes.extend(new[j..].iter().map(|line| Added(line.to_string())));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use {DisplayVariant, Relm, Update};
+    use super::{Recorder, diff};
+    use super::Change::*;
+
+    struct Counter;
+
+    enum Msg {
+        Increment,
+    }
+
+    impl DisplayVariant for Msg {
+        fn display_variant(&self) -> &'static str {
+            match *self {
+                Msg::Increment => "Increment",
+            }
+        }
+    }
+
+    impl Update for Counter {
+        type Model = i32;
+        type ModelParam = ();
+        type Msg = Msg;
+
+        fn model(_: &Relm<Self>, _: ()) -> i32 {
+            0
+        }
+
+        fn update(&mut self, _event: Msg) {
+        }
+    }
+
+    fn recorder(models: &[i32]) -> Recorder<Counter> {
+        let recorder = Recorder::new();
+        for &model in models {
+            recorder.record("Increment", model);
+        }
+        recorder
+    }
+
+    #[test]
+    fn diff_same() {
+        assert_eq!(diff("a\nb", "a\nb"), vec![Same("a".to_string()), Same("b".to_string())]);
+    }
+
+    #[test]
+    fn diff_added_and_removed() {
+        assert_eq!(diff("a\nb\nc", "a\nx\nc\nd"), vec![
+            Same("a".to_string()),
+            Removed("b".to_string()),
+            Added("x".to_string()),
+            Same("c".to_string()),
+            Added("d".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn diff_empty() {
+        assert_eq!(diff("", "a"), vec![Added("a".to_string())]);
+        assert_eq!(diff("a", ""), vec![Removed("a".to_string())]);
+        assert_eq!(diff("", ""), vec![]);
+    }
+
+    #[test]
+    fn recorder_diff() {
+        let recorder = recorder(&[0, 1]);
+        assert_eq!(recorder.diff(0), vec![Added("0".to_string())]);
+        assert_eq!(recorder.diff(1), vec![Removed("0".to_string()), Added("1".to_string())]);
+        assert_eq!(recorder.diff(2), vec![]);
+    }
+
+    #[test]
+    fn replay_from() {
+        let recorder = recorder(&[0, 1, 2]);
+        let restored = Rc::new(RefCell::new(None));
+        let lengths = Rc::new(RefCell::new(vec![]));
+        {
+            let restored = restored.clone();
+            recorder.set_restore(move |model| *restored.borrow_mut() = Some(model));
+            let lengths = lengths.clone();
+            recorder.observe(move |len| lengths.borrow_mut().push(len));
+        }
+
+        recorder.replay_from(1);
+        assert_eq!(*restored.borrow(), Some(1));
+        assert_eq!(*lengths.borrow(), vec![2]);
+        assert_eq!(recorder.len(), 2);
+        assert_eq!(recorder.entry(1).map(|entry| entry.model), Some(1));
+
+        // Out of bounds: nothing happens.
+        recorder.replay_from(5);
+        assert_eq!(recorder.len(), 2);
+        assert_eq!(*lengths.borrow(), vec![2]);
+    }
+}
@@ -0,0 +1,417 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Synchronous runtime to test `Update` components without a main loop.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use futures::{Async, Future};
+use futures::executor::{self, Notify, Spawn};
+use relm_core::EventStream;
+
+use super::{DisplayVariant, Relm, Update, UpdateNew};
+
+type Callback<MSG> = Box<Fn(Box<Any>) -> Result<MSG, Box<Any>>>;
+
+struct Connection<MSG> {
+    failure: Option<Callback<MSG>>,
+    success: Callback<MSG>,
+}
+
+/// Futures spawned by a component running in a `TestRuntime`.
+#[doc(hidden)]
+pub struct TestQueue<MSG> {
+    connections: Vec<Connection<MSG>>,
+    futures: Vec<Spawn<Box<Future<Item=(), Error=()>>>>,
+}
+
+impl<MSG> TestQueue<MSG> {
+    fn new() -> Self {
+        TestQueue {
+            connections: vec![],
+            futures: vec![],
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn connect<ERROR, ITEM, CALLBACK, FAILCALLBACK>(&mut self, success_callback: CALLBACK,
+        failure_callback: Option<FAILCALLBACK>)
+        where CALLBACK: Fn(ITEM) -> MSG + 'static,
+              FAILCALLBACK: Fn(ERROR) -> MSG + 'static,
+              ERROR: 'static,
+              ITEM: 'static,
+    {
+        let failure = failure_callback.map(|callback| {
+            Box::new(move |error: Box<Any>| error.downcast::<ERROR>().map(|error| callback(*error)))
+                as Callback<MSG>
+        });
+        self.connections.push(Connection {
+            failure,
+            success: Box::new(move |item: Box<Any>| item.downcast::<ITEM>().map(|item| success_callback(*item))),
+        });
+    }
+
+    #[doc(hidden)]
+    pub fn exec<FUTURE: Future<Item=(), Error=()> + 'static>(&mut self, future: FUTURE) {
+        self.futures.push(executor::spawn(Box::new(future)));
+    }
+}
+
+struct NoopNotify;
+
+impl Notify for NoopNotify {
+    fn notify(&self, _id: usize) {
+    }
+}
+
+/// Runtime driving an `Update` component synchronously, without GTK+ or a glib main context.
+///
+/// Messages sent to the component are handled before `send()` returns.
+/// The futures connected with [`Relm::connect_exec()`](struct.Relm.html#method.connect_exec) are
+/// not run: they stay pending until the test fakes their outcome with
+/// [`resolve()`](#method.resolve) or [`reject()`](#method.reject).
+/// The futures spawned with [`Relm::exec()`](struct.Relm.html#method.exec) are polled by
+/// [`run_futures()`](#method.run_futures).
+///
+/// ```
+/// # extern crate relm_state;
+/// #
+/// # use relm_state::{DisplayVariant, Relm, TestRuntime, Update, UpdateNew};
+/// #
+/// # pub enum Msg {
+/// #     Increment,
+/// # }
+/// #
+/// # impl DisplayVariant for Msg {
+/// #     fn display_variant(&self) -> &'static str {
+/// #         "Increment"
+/// #     }
+/// # }
+/// #
+/// # pub struct Counter {
+/// #     count: i32,
+/// # }
+/// #
+/// # impl Update for Counter {
+/// #     type Model = i32;
+/// #     type ModelParam = i32;
+/// #     type Msg = Msg;
+/// #
+/// #     fn model(_: &Relm<Self>, count: i32) -> i32 {
+/// #         count
+/// #     }
+/// #
+/// #     fn update(&mut self, _event: Msg) {
+/// #         self.count += 1;
+/// #     }
+/// # }
+/// #
+/// # impl UpdateNew for Counter {
+/// #     fn new(_: &Relm<Self>, count: i32) -> Self {
+/// #         Counter {
+/// #             count,
+/// #         }
+/// #     }
+/// # }
+/// #
+/// # fn main() {
+/// let mut runtime = TestRuntime::<Counter>::new(41);
+/// runtime.send(Msg::Increment);
+/// assert_eq!(runtime.component().count, 42);
+/// assert_eq!(runtime.messages(), vec!["Increment"]);
+/// # }
+/// ```
+pub struct TestRuntime<UPDATE: Update> {
+    component: UPDATE,
+    messages: Rc<RefCell<Vec<&'static str>>>,
+    queue: Rc<RefCell<TestQueue<UPDATE::Msg>>>,
+    relm: Relm<UPDATE>,
+    stream: Spawn<EventStream<UPDATE::Msg>>,
+}
+
+impl<UPDATE> TestRuntime<UPDATE>
+    where UPDATE: Update + UpdateNew,
+          UPDATE::Msg: DisplayVariant + 'static,
+{
+    /// Create the component with `model_param`, connect its subscriptions and handle the
+    /// messages they emitted.
+    pub fn new(model_param: UPDATE::ModelParam) -> Self {
+        let stream = EventStream::new();
+        let messages = Rc::new(RefCell::new(vec![]));
+        {
+            let messages = messages.clone();
+            stream.observe(move |msg: &UPDATE::Msg| messages.borrow_mut().push(msg.display_variant()));
+        }
+        let queue = Rc::new(RefCell::new(TestQueue::new()));
+        let relm = Relm::new_test(stream.clone(), queue.clone());
+        let model = UPDATE::model(&relm, model_param);
+        let mut component = UPDATE::new(&relm, model);
+        component.subscriptions(&relm);
+        let mut runtime = TestRuntime {
+            component,
+            messages,
+            queue,
+            relm,
+            stream: executor::spawn(stream),
+        };
+        runtime.dispatch();
+        runtime
+    }
+}
+
+impl<UPDATE> TestRuntime<UPDATE>
+    where UPDATE: Update,
+          UPDATE::Msg: DisplayVariant + 'static,
+{
+    /// Get the component.
+    pub fn component(&self) -> &UPDATE {
+        &self.component
+    }
+
+    /// Get the component mutably.
+    pub fn component_mut(&mut self) -> &mut UPDATE {
+        &mut self.component
+    }
+
+    /// Get the variants of the messages emitted so far, in order.
+    pub fn messages(&self) -> Vec<&'static str> {
+        self.messages.borrow().clone()
+    }
+
+    /// Get the number of connected futures still waiting to be faked.
+    pub fn pending_connections(&self) -> usize {
+        self.queue.borrow().connections.len()
+    }
+
+    /// Fake the failure of the oldest pending connection whose error type is `ERROR`.
+    /// Return `false` if no such connection was found.
+    pub fn reject<ERROR: 'static>(&mut self, error: ERROR) -> bool {
+        let mut error: Box<Any> = Box::new(error);
+        let len = self.queue.borrow().connections.len();
+        for index in 0..len {
+            let result =
+                match self.queue.borrow().connections[index].failure {
+                    Some(ref failure) => failure(error),
+                    None => continue,
+                };
+            match result {
+                Ok(msg) => {
+                    let _ = self.queue.borrow_mut().connections.remove(index);
+                    self.send(msg);
+                    return true;
+                },
+                Err(value) => error = value,
+            }
+        }
+        false
+    }
+
+    /// Get the `Relm` handle given to the component.
+    pub fn relm(&self) -> &Relm<UPDATE> {
+        &self.relm
+    }
+
+    /// Fake the success of the oldest pending connection whose item type is `ITEM`.
+    /// Return `false` if no such connection was found.
+    ///
+    /// The connection is not pending anymore after this call, even if it was connected to a
+    /// `Stream`.
+    pub fn resolve<ITEM: 'static>(&mut self, item: ITEM) -> bool {
+        let mut item: Box<Any> = Box::new(item);
+        let len = self.queue.borrow().connections.len();
+        for index in 0..len {
+            let result = (self.queue.borrow().connections[index].success)(item);
+            match result {
+                Ok(msg) => {
+                    let _ = self.queue.borrow_mut().connections.remove(index);
+                    self.send(msg);
+                    return true;
+                },
+                Err(value) => item = value,
+            }
+        }
+        false
+    }
+
+    /// Poll the futures spawned by the component until none of them can make progress.
+    pub fn run_futures(&mut self) {
+        loop {
+            let notify = Arc::new(NoopNotify);
+            let futures: Vec<_> = self.queue.borrow_mut().futures.drain(..).collect();
+            let len = futures.len();
+            let mut pending = vec![];
+            for mut future in futures {
+                if let Ok(Async::NotReady) = future.poll_future_notify(&notify, 0) {
+                    pending.push(future);
+                }
+            }
+            let progress = pending.len() < len;
+            self.queue.borrow_mut().futures.extend(pending);
+            self.dispatch();
+            if !progress {
+                break;
+            }
+        }
+    }
+
+    /// Send `msg` to the component and handle it, as well as all the messages it causes.
+    pub fn send(&mut self, msg: UPDATE::Msg) {
+        self.relm.stream().emit(msg);
+        self.dispatch();
+    }
+
+    fn dispatch(&mut self) {
+        let notify = Arc::new(NoopNotify);
+        while let Ok(Async::Ready(Some(msg))) = self.stream.poll_stream_notify(&notify, 0) {
+            self.component.update(msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+
+    use {DisplayVariant, Relm, TestRuntime, Update, UpdateNew};
+    use self::Msg::*;
+
+    struct Model {
+        count: i32,
+        error: Option<String>,
+        value: Option<u32>,
+    }
+
+    enum Msg {
+        Chain,
+        Failed(String),
+        Fetch,
+        Fetched(u32),
+        Increment,
+        Spawn,
+    }
+
+    impl DisplayVariant for Msg {
+        fn display_variant(&self) -> &'static str {
+            match *self {
+                Chain => "Chain",
+                Failed(_) => "Failed",
+                Fetch => "Fetch",
+                Fetched(_) => "Fetched",
+                Increment => "Increment",
+                Spawn => "Spawn",
+            }
+        }
+    }
+
+    struct Fetcher {
+        model: Model,
+        relm: Relm<Fetcher>,
+    }
+
+    impl Update for Fetcher {
+        type Model = Model;
+        type ModelParam = i32;
+        type Msg = Msg;
+
+        fn model(_: &Relm<Self>, count: i32) -> Model {
+            Model {
+                count,
+                error: None,
+                value: None,
+            }
+        }
+
+        fn update(&mut self, event: Msg) {
+            match event {
+                Chain => {
+                    self.relm.stream().emit(Increment);
+                    self.relm.stream().emit(Increment);
+                },
+                Failed(error) => self.model.error = Some(error),
+                Fetch => self.relm.connect_exec(future::ok::<u32, String>(42), Fetched, Failed),
+                Fetched(value) => self.model.value = Some(value),
+                Increment => self.model.count += 1,
+                Spawn => {
+                    let stream = self.relm.stream().clone();
+                    self.relm.exec(future::lazy(move || {
+                        stream.emit(Increment);
+                        Ok(())
+                    }));
+                },
+            }
+        }
+    }
+
+    impl UpdateNew for Fetcher {
+        fn new(relm: &Relm<Self>, model: Model) -> Self {
+            Fetcher {
+                model,
+                relm: relm.clone(),
+            }
+        }
+    }
+
+    #[test]
+    fn send_handles_emitted_messages() {
+        let mut runtime = TestRuntime::<Fetcher>::new(0);
+        runtime.send(Chain);
+        assert_eq!(runtime.component().model.count, 2);
+        assert_eq!(runtime.messages(), vec!["Chain", "Increment", "Increment"]);
+    }
+
+    #[test]
+    fn resolve() {
+        let mut runtime = TestRuntime::<Fetcher>::new(0);
+        runtime.send(Fetch);
+        assert_eq!(runtime.pending_connections(), 1);
+        // The item type does not match.
+        assert!(!runtime.resolve("value".to_string()));
+        assert_eq!(runtime.pending_connections(), 1);
+        assert!(runtime.resolve(42u32));
+        assert_eq!(runtime.pending_connections(), 0);
+        assert_eq!(runtime.component().model.value, Some(42));
+        assert_eq!(runtime.messages(), vec!["Fetch", "Fetched"]);
+    }
+
+    #[test]
+    fn reject() {
+        let mut runtime = TestRuntime::<Fetcher>::new(0);
+        runtime.send(Fetch);
+        // The error type does not match.
+        assert!(!runtime.reject(13u32));
+        assert!(runtime.reject("error".to_string()));
+        assert_eq!(runtime.pending_connections(), 0);
+        assert_eq!(runtime.component().model.error, Some("error".to_string()));
+        assert_eq!(runtime.component().model.value, None);
+    }
+
+    #[test]
+    fn run_futures() {
+        let mut runtime = TestRuntime::<Fetcher>::new(0);
+        runtime.send(Spawn);
+        assert_eq!(runtime.component().model.count, 0);
+        runtime.run_futures();
+        assert_eq!(runtime.component().model.count, 1);
+    }
+}
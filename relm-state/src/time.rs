@@ -0,0 +1,255 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Timers using a clock that can be replaced by a virtual one in tests.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::time::{Duration, SystemTime};
+
+use futures::{Async, Future, Poll, Stream};
+use futures::task::{self, Task};
+use futures_glib;
+
+thread_local! {
+    static VIRTUAL_CLOCK: RefCell<Option<VirtualClock>> = RefCell::new(None);
+}
+
+struct VirtualClock {
+    elapsed: Duration,
+    start: SystemTime,
+    timers: Vec<Weak<RefCell<VirtualTimer>>>,
+}
+
+struct VirtualTimer {
+    deadline: Option<Duration>,
+    due: usize,
+    period: Option<Duration>,
+    task: Option<Task>,
+}
+
+impl VirtualTimer {
+    fn poll(&mut self) -> Async<()> {
+        if self.due > 0 {
+            self.due -= 1;
+            Async::Ready(())
+        }
+        else {
+            self.task = Some(task::current());
+            Async::NotReady
+        }
+    }
+}
+
+fn new_virtual_timer(duration: Duration, period: Option<Duration>) -> Option<Rc<RefCell<VirtualTimer>>> {
+    VIRTUAL_CLOCK.with(|clock| {
+        clock.borrow_mut().as_mut().map(|clock| {
+            let timer = Rc::new(RefCell::new(VirtualTimer {
+                deadline: Some(clock.elapsed + duration),
+                due: 0,
+                period,
+                task: None,
+            }));
+            clock.timers.push(Rc::downgrade(&timer));
+            timer
+        })
+    })
+}
+
+/// Use a virtual clock for the timers created afterwards in the current thread.
+///
+/// The virtual clock only moves forward when calling
+/// [`advance_virtual_clock()`](fn.advance_virtual_clock.html).
+pub fn use_virtual_clock() {
+    VIRTUAL_CLOCK.with(|clock| {
+        *clock.borrow_mut() = Some(VirtualClock {
+            elapsed: Duration::from_secs(0),
+            start: SystemTime::now(),
+            timers: vec![],
+        });
+    });
+}
+
+/// Move the virtual clock forward by `duration` and wake up the timers that are due.
+///
+/// ## Panics
+/// This function panics when [`use_virtual_clock()`](fn.use_virtual_clock.html) was not called.
+pub fn advance_virtual_clock(duration: Duration) {
+    let tasks = VIRTUAL_CLOCK.with(|clock| {
+        let mut clock = clock.borrow_mut();
+        let clock = clock.as_mut().expect("advance_virtual_clock() called without use_virtual_clock()");
+        clock.elapsed += duration;
+        clock.timers.retain(|timer| timer.upgrade().is_some());
+        let mut tasks = vec![];
+        for timer in &clock.timers {
+            if let Some(timer) = timer.upgrade() {
+                let mut timer = timer.borrow_mut();
+                let mut fired = false;
+                while let Some(deadline) = timer.deadline {
+                    if deadline > clock.elapsed {
+                        break;
+                    }
+                    timer.due += 1;
+                    timer.deadline = timer.period.map(|period| deadline + period);
+                    fired = true;
+                }
+                if fired {
+                    tasks.extend(timer.task.take());
+                }
+            }
+        }
+        tasks
+    });
+    for task in tasks {
+        task.notify();
+    }
+}
+
+/// Get the current time of the clock used by the timers.
+pub fn now() -> SystemTime {
+    VIRTUAL_CLOCK.with(|clock| {
+        match *clock.borrow() {
+            Some(ref clock) => clock.start + clock.elapsed,
+            None => SystemTime::now(),
+        }
+    })
+}
+
+/// A stream yielding `()` at a fixed period.
+pub struct Interval {
+    inner: Inner<futures_glib::Interval>,
+}
+
+enum Inner<TIMER> {
+    Real(TIMER),
+    Virtual(Rc<RefCell<VirtualTimer>>),
+}
+
+impl Interval {
+    /// Create a new stream yielding every `duration`.
+    pub fn new(duration: Duration) -> Self {
+        let inner =
+            match new_virtual_timer(duration, Some(duration)) {
+                Some(timer) => Inner::Virtual(timer),
+                None => Inner::Real(futures_glib::Interval::new(duration)),
+            };
+        Interval {
+            inner,
+        }
+    }
+}
+
+impl Stream for Interval {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<()>, ()> {
+        match self.inner {
+            Inner::Real(ref mut interval) => interval.poll(),
+            Inner::Virtual(ref timer) => Ok(timer.borrow_mut().poll().map(Some)),
+        }
+    }
+}
+
+/// A future resolving after a delay.
+pub struct Timeout {
+    inner: Inner<futures_glib::Timeout>,
+}
+
+impl Timeout {
+    /// Create a new future resolving after `duration`.
+    pub fn new(duration: Duration) -> Self {
+        let inner =
+            match new_virtual_timer(duration, None) {
+                Some(timer) => Inner::Virtual(timer),
+                None => Inner::Real(futures_glib::Timeout::new(duration)),
+            };
+        Timeout {
+            inner,
+        }
+    }
+}
+
+impl Future for Timeout {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        match self.inner {
+            Inner::Real(ref mut timeout) => timeout.poll(),
+            Inner::Virtual(ref timer) => Ok(timer.borrow_mut().poll()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::Async;
+    use futures::executor::{self, Notify};
+
+    use super::{Interval, Timeout, advance_virtual_clock, now, use_virtual_clock};
+
+    struct NoopNotify;
+
+    impl Notify for NoopNotify {
+        fn notify(&self, _id: usize) {
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        use_virtual_clock();
+        let notify = Arc::new(NoopNotify);
+        let mut timeout = executor::spawn(Timeout::new(Duration::from_secs(2)));
+        assert_eq!(timeout.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        advance_virtual_clock(Duration::from_secs(1));
+        assert_eq!(timeout.poll_future_notify(&notify, 0), Ok(Async::NotReady));
+        advance_virtual_clock(Duration::from_secs(1));
+        assert_eq!(timeout.poll_future_notify(&notify, 0), Ok(Async::Ready(())));
+    }
+
+    #[test]
+    fn interval() {
+        use_virtual_clock();
+        let notify = Arc::new(NoopNotify);
+        let mut interval = executor::spawn(Interval::new(Duration::from_secs(1)));
+        assert_eq!(interval.poll_stream_notify(&notify, 0), Ok(Async::NotReady));
+        // Every period elapsed yields one item.
+        advance_virtual_clock(Duration::from_millis(3500));
+        for _ in 0..3 {
+            assert_eq!(interval.poll_stream_notify(&notify, 0), Ok(Async::Ready(Some(()))));
+        }
+        assert_eq!(interval.poll_stream_notify(&notify, 0), Ok(Async::NotReady));
+        advance_virtual_clock(Duration::from_millis(500));
+        assert_eq!(interval.poll_stream_notify(&notify, 0), Ok(Async::Ready(Some(()))));
+    }
+
+    #[test]
+    fn virtual_now() {
+        use_virtual_clock();
+        let start = now();
+        advance_virtual_clock(Duration::from_secs(5));
+        assert_eq!(now().duration_since(start).ok(), Some(Duration::from_secs(5)));
+    }
+}
@@ -1,7 +1,11 @@
 extern crate glib_sys;
 extern crate gtk;
+extern crate relm_state;
+
+use std::time::Duration;
 
 use gtk::ButtonExt;
+pub use relm_state::use_virtual_clock;
 
 #[macro_export]
 macro_rules! assert_text {
@@ -19,6 +23,20 @@ pub fn click<B: ButtonExt>(button: &B) {
 
 pub fn run_loop() {
     unsafe { glib_sys::g_usleep(1000) };
+    drain_loop();
+}
+
+/// Move the virtual clock forward by `duration`, fire the timers that are due and process the
+/// resulting events.
+///
+/// [`use_virtual_clock()`](fn.use_virtual_clock.html) must be called before creating the
+/// component under test.
+pub fn advance_time(duration: Duration) {
+    relm_state::advance_virtual_clock(duration);
+    drain_loop();
+}
+
+fn drain_loop() {
     while gtk::events_pending() {
         gtk::main_iteration();
     }
@@ -0,0 +1,244 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::cell::RefCell;
+use std::env;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gio::{
+    self,
+    ActionMapExt,
+    ApplicationExt,
+    FileExt,
+    SimpleAction,
+    SimpleActionExt,
+};
+use glib::Cast;
+use gtk::{self, WindowExt};
+
+use relm_state::run_loop;
+use super::{Component, DisplayVariant, create_executor, create_widget, init_widget};
+use widget::Widget;
+
+type Callback<ARG, MSG> = Rc<Fn(ARG) -> MSG>;
+
+/// A `gtk::Application` running a relm `Widget` as its main window.
+///
+/// The application is single-instance: launching it again activates the running instance, which
+/// receives the message given to [`activate()`](#method.activate) or [`open()`](#method.open).
+///
+/// ```no_run
+/// # extern crate gtk;
+/// # #[macro_use]
+/// # extern crate relm;
+/// # #[macro_use]
+/// # extern crate relm_derive;
+/// #
+/// # use std::path::PathBuf;
+/// #
+/// # use gtk::{Window, WindowType};
+/// # use relm::{Application, Relm, Update, Widget};
+/// #
+/// # struct Win {
+/// #     window: Window,
+/// # }
+/// #
+/// # impl Update for Win {
+/// #     type Model = ();
+/// #     type ModelParam = Vec<String>;
+/// #     type Msg = Msg;
+/// #
+/// #     fn model(_: &Relm<Self>, _: Vec<String>) -> () {
+/// #     }
+/// #
+/// #     fn update(&mut self, event: Msg) {
+/// #     }
+/// # }
+/// #
+/// # impl Widget for Win {
+/// #     type Root = Window;
+/// #
+/// #     fn root(&self) -> Self::Root {
+/// #         self.window.clone()
+/// #     }
+/// #
+/// #     fn view(relm: &Relm<Self>, _model: ()) -> Self {
+/// #         Win {
+/// #             window: Window::new(WindowType::Toplevel),
+/// #         }
+/// #     }
+/// # }
+/// #
+/// # #[derive(Msg)]
+/// # enum Msg {
+/// #     Activate,
+/// #     Open(Vec<PathBuf>),
+/// #     Quit,
+/// # }
+/// #
+/// # fn main() {
+/// let code = Application::<Win>::new("org.example.App").unwrap()
+///     .activate(|| Msg::Activate)
+///     .open(Msg::Open)
+///     .action("quit", || Msg::Quit)
+///     .run(|args| args.to_vec());
+/// std::process::exit(code);
+/// # }
+/// ```
+pub struct Application<WIDGET: Widget> {
+    actions: Vec<(String, Callback<(), WIDGET::Msg>)>,
+    activate: Option<Callback<(), WIDGET::Msg>>,
+    app: gtk::Application,
+    open: Option<Callback<Vec<PathBuf>, WIDGET::Msg>>,
+}
+
+impl<WIDGET> Application<WIDGET>
+    where WIDGET: Widget + 'static,
+          WIDGET::Msg: DisplayVariant + 'static,
+{
+    /// Create a new application identified by `app_id`, e.g. `org.example.App`.
+    pub fn new(app_id: &str) -> Result<Self, ()> {
+        gtk::init().map_err(|_| ())?;
+        let app = gtk::Application::new(Some(app_id), gio::APPLICATION_HANDLES_OPEN).map_err(|_| ())?;
+        Ok(Application {
+            actions: vec![],
+            activate: None,
+            app,
+            open: None,
+        })
+    }
+
+    /// Add an application-level action called `name` (usable as `app.name` in menus and
+    /// accelerators) which sends the message returned by `callback` to the root widget.
+    pub fn action<CALLBACK: Fn() -> WIDGET::Msg + 'static>(mut self, name: &str, callback: CALLBACK) -> Self {
+        self.actions.push((name.to_string(), Rc::new(move |()| callback())));
+        self
+    }
+
+    /// Send the message returned by `callback` to the root widget every time the application is
+    /// activated, i.e. when it is launched without files.
+    pub fn activate<CALLBACK: Fn() -> WIDGET::Msg + 'static>(mut self, callback: CALLBACK) -> Self {
+        self.activate = Some(Rc::new(move |()| callback()));
+        self
+    }
+
+    /// Get the underlying GTK+ application.
+    pub fn application(&self) -> &gtk::Application {
+        &self.app
+    }
+
+    /// Send the message returned by `callback` to the root widget when files are passed on the
+    /// command line.
+    pub fn open<CALLBACK: Fn(Vec<PathBuf>) -> WIDGET::Msg + 'static>(mut self, callback: CALLBACK) -> Self {
+        self.open = Some(Rc::new(callback));
+        self
+    }
+
+    /// Run the application and return the exit code of the process.
+    ///
+    /// The application stops when its last window is closed or when [`quit()`](fn.quit.html) is
+    /// called, in which case the code given to `quit()` is returned.
+    ///
+    /// The root widget is created when the application starts, with the model parameter returned
+    /// by `model_param` from the command-line arguments.
+    pub fn run<PARAM: Fn(&[String]) -> WIDGET::ModelParam + 'static>(self, model_param: PARAM) -> i32 {
+        ::futures_glib::init();
+
+        let args: Vec<String> = env::args().collect();
+        let component: Rc<RefCell<Option<Component<WIDGET>>>> = Rc::new(RefCell::new(None));
+
+        {
+            let args = args.clone();
+            let component = component.clone();
+            let _ = self.app.connect_startup(move |app| {
+                let executor = create_executor();
+                let (widget, child, relm) = create_widget::<WIDGET>(&executor, model_param(&args));
+                let window: Option<gtk::Window> = widget.widget().clone().upcast::<gtk::Widget>().downcast().ok();
+                if let Some(window) = window {
+                    app.add_window(&window);
+                }
+                init_widget::<WIDGET>(&widget, child, &executor, &relm);
+                *component.borrow_mut() = Some(widget);
+            });
+        }
+
+        for (name, callback) in self.actions {
+            let action = SimpleAction::new(&name, None);
+            let component = component.clone();
+            let _ = action.connect_activate(move |_, _| emit(&component, callback(())));
+            self.app.add_action(&action);
+        }
+
+        {
+            let activate = self.activate;
+            let component = component.clone();
+            let _ = self.app.connect_activate(move |_| {
+                present(&component);
+                if let Some(ref activate) = activate {
+                    emit(&component, activate(()));
+                }
+            });
+        }
+
+        {
+            let open = self.open;
+            let component = component.clone();
+            let _ = self.app.connect_open(move |_, files, _| {
+                present(&component);
+                if let Some(ref open) = open {
+                    let paths = files.iter()
+                        .filter_map(|file| file.get_path())
+                        .collect();
+                    emit(&component, open(paths));
+                }
+            });
+        }
+
+        let mut code = 0;
+        let application = self.app;
+        let app = application.clone();
+        let status = run_loop(|| code = application.run(&args), move || app.quit());
+        *component.borrow_mut() = None;
+        if status.success() {
+            code
+        }
+        else {
+            status.code()
+        }
+    }
+}
+
+fn emit<WIDGET: Widget>(component: &Rc<RefCell<Option<Component<WIDGET>>>>, msg: WIDGET::Msg) {
+    if let Some(ref component) = *component.borrow() {
+        component.emit(msg);
+    }
+}
+
+fn present<WIDGET: Widget>(component: &Rc<RefCell<Option<Component<WIDGET>>>>) {
+    if let Some(ref component) = *component.borrow() {
+        let window: Option<gtk::Window> = component.widget().clone().upcast::<gtk::Widget>().downcast().ok();
+        if let Some(window) = window {
+            window.present();
+        }
+    }
+}
+
@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Conversions and signal connection used by the bindings of the `view!` macro, i.e.
+//! `text: bind self.model.text` and `sensitive: bind check.active`.
+
+use std::mem::transmute;
+
+use glib::Value;
+use glib::object::Downcast;
+use glib::signal::connect;
+use glib::translate::{ToGlibPtr, from_glib_none};
+use glib::value::{FromValueOptional, ToValue};
+use glib_sys::{GFALSE, gboolean, gpointer};
+use gobject_sys::{
+    G_BINDING_INVERT_BOOLEAN,
+    G_BINDING_SYNC_CREATE,
+    GBinding,
+    GObject,
+    GParamSpec,
+    GValue,
+    g_object_bind_property,
+    g_object_bind_property_full,
+    g_value_transform,
+};
+use gtk::{self, IsA, Object};
+
+type TransformCallback = Box<Fn(&Value) -> Option<Value> + 'static>;
+
+/// Convert a model variable to the value expected by the setter of a widget property.
+pub trait ToWidgetValue<'a> {
+    /// The type of the value expected by the setter.
+    type Output;
+
+    /// Convert the model variable.
+    fn to_widget_value(&'a self) -> Self::Output;
+}
+
+impl<'a> ToWidgetValue<'a> for String {
+    type Output = &'a str;
+
+    fn to_widget_value(&'a self) -> &'a str {
+        self
+    }
+}
+
+/// Convert the value returned by the getter of a widget property to a model variable.
+pub trait FromWidgetValue<VALUE> {
+    /// Convert the value of the property.
+    fn from_widget_value(value: VALUE) -> Self;
+}
+
+impl FromWidgetValue<Option<String>> for String {
+    fn from_widget_value(value: Option<String>) -> Self {
+        value.unwrap_or_default()
+    }
+}
+
+impl FromWidgetValue<String> for String {
+    fn from_widget_value(value: String) -> Self {
+        value
+    }
+}
+
+macro_rules! impl_widget_value {
+    ($($typ:ty),*) => {
+        $(
+            impl<'a> ToWidgetValue<'a> for $typ {
+                type Output = $typ;
+
+                fn to_widget_value(&'a self) -> $typ {
+                    *self
+                }
+            }
+
+            impl FromWidgetValue<$typ> for $typ {
+                fn from_widget_value(value: $typ) -> Self {
+                    value
+                }
+            }
+        )*
+    };
+}
+
+impl_widget_value!(bool, f32, f64, i32, i64, u32, u64);
+
+/// Call `callback` every time the `property` of `object` changes.
+///
+/// This is used internally by the library for the two-way bindings.
+#[doc(hidden)]
+pub fn connect_notify<OBJECT, CALLBACK>(object: &OBJECT, property: &str, callback: CALLBACK)
+    where CALLBACK: Fn(&OBJECT) + 'static,
+          OBJECT: IsA<Object> + 'static,
+{
+    let signal = format!("notify::{}", property);
+    let callback: Box<Box<Fn(&OBJECT) + 'static>> = Box::new(Box::new(callback));
+    unsafe {
+        let object: *mut GObject = ToGlibPtr::<*mut GObject>::to_glib_none(object).0;
+        let _ = connect(object, &signal, transmute(notify_trampoline::<OBJECT> as usize),
+            Box::into_raw(callback) as *mut _);
+    }
+}
+
+unsafe extern "C" fn notify_trampoline<OBJECT>(this: *mut GObject, _param_spec: *mut GParamSpec, callback: gpointer)
+    where OBJECT: IsA<Object> + 'static,
+{
+    let callback: &&(Fn(&OBJECT) + 'static) = transmute(callback);
+    let object: gtk::Object = from_glib_none(this);
+    callback(&object.downcast_unchecked());
+}
+
+/// Bind the `target_property` of `target` to the `source_property` of `source` with a `GBinding`,
+/// optionally inverting the boolean value.
+///
+/// This is used internally by the library for the widget-to-widget bindings.
+#[doc(hidden)]
+pub fn bind_property<SOURCE, TARGET>(source: &SOURCE, source_property: &str, target: &TARGET, target_property: &str,
+    invert: bool)
+    where SOURCE: IsA<Object>,
+          TARGET: IsA<Object>,
+{
+    let flags =
+        if invert {
+            G_BINDING_SYNC_CREATE | G_BINDING_INVERT_BOOLEAN
+        }
+        else {
+            G_BINDING_SYNC_CREATE
+        };
+    unsafe {
+        let _ = g_object_bind_property(ToGlibPtr::<*mut GObject>::to_glib_none(source).0,
+            source_property.to_glib_none().0, ToGlibPtr::<*mut GObject>::to_glib_none(target).0,
+            target_property.to_glib_none().0, flags);
+    }
+}
+
+/// Bind the `target_property` of `target` to the `source_property` of `source` with a `GBinding`
+/// converting the value with `transform`.
+///
+/// This is used internally by the library for the widget-to-widget bindings.
+#[doc(hidden)]
+pub fn bind_property_transform<SOURCE, TARGET, CALLBACK, INPUT, OUTPUT>(source: &SOURCE, source_property: &str,
+    target: &TARGET, target_property: &str, transform: CALLBACK)
+    where CALLBACK: Fn(INPUT) -> OUTPUT + 'static,
+          INPUT: for<'a> FromValueOptional<'a>,
+          OUTPUT: ToValue,
+          SOURCE: IsA<Object>,
+          TARGET: IsA<Object>,
+{
+    let callback: Box<TransformCallback> = Box::new(Box::new(move |value: &Value| {
+        value.get().map(|value| transform(value).to_value())
+    }));
+    unsafe {
+        let _ = g_object_bind_property_full(ToGlibPtr::<*mut GObject>::to_glib_none(source).0,
+            source_property.to_glib_none().0, ToGlibPtr::<*mut GObject>::to_glib_none(target).0,
+            target_property.to_glib_none().0, G_BINDING_SYNC_CREATE, Some(transform_trampoline), None,
+            Box::into_raw(callback) as gpointer, Some(destroy_transform));
+    }
+}
+
+unsafe extern "C" fn transform_trampoline(_binding: *mut GBinding, source: *const GValue, target: *mut GValue,
+    callback: gpointer) -> gboolean
+{
+    let callback: &TransformCallback = transmute(callback);
+    let source: Value = from_glib_none(source);
+    match callback(&source) {
+        Some(value) => g_value_transform(value.to_glib_none().0, target),
+        // The value could not be converted to the type expected by the transform closure.
+        None => GFALSE,
+    }
+}
+
+unsafe extern "C" fn destroy_transform(callback: gpointer) {
+    let _ = Box::<TransformCallback>::from_raw(callback as *mut _);
+}
@@ -19,10 +19,83 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use glib::Cast;
+use gtk::{self, ContainerExt, WidgetExt};
+
 use super::{EventStream, Widget};
+use self::Hook::*;
+
+/// Lifecycle event of a widget.
+#[doc(hidden)]
+pub enum Hook {
+    Destroy,
+    Hide,
+    Remove(gtk::Widget),
+    Show,
+}
+
+/// Calls the lifecycle hooks of a widget.
+///
+/// When the widget is busy handling a message, the hooks are delayed until the end of its `update()`
+/// method.
+#[doc(hidden)]
+pub struct Lifecycle<WIDGET> {
+    destroyed: Cell<bool>,
+    pending: RefCell<Vec<Hook>>,
+    widget: Weak<RefCell<WIDGET>>,
+}
+
+impl<WIDGET: Widget> Lifecycle<WIDGET> {
+    #[doc(hidden)]
+    pub fn new(widget: Weak<RefCell<WIDGET>>) -> Self {
+        Lifecycle {
+            destroyed: Cell::new(false),
+            pending: RefCell::new(vec![]),
+            widget,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn call(&self, hook: Hook) {
+        if let Destroy = hook {
+            if self.destroyed.replace(true) {
+                return;
+            }
+        }
+        if let Some(widget) = self.widget.upgrade() {
+            match widget.try_borrow_mut() {
+                Ok(mut widget) => call_hook(&mut *widget, hook),
+                Err(_) => self.pending.borrow_mut().push(hook),
+            };
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn call_pending(&self, widget: &mut WIDGET) {
+        let hooks: Vec<_> = self.pending.borrow_mut().drain(..).collect();
+        for hook in hooks {
+            call_hook(widget, hook);
+        }
+    }
+}
+
+fn call_hook<WIDGET: Widget>(widget: &mut WIDGET, hook: Hook) {
+    match hook {
+        Destroy => widget.on_destroy(),
+        Hide => widget.on_hide(),
+        Remove(parent) => widget.on_remove(parent),
+        Show => widget.on_show(),
+    }
+}
 
 /// Widget that was added by the `ContainerWidget::add_widget()` method.
 ///
+/// A `Component` is a reference-counted handle: cloning it is cheap and the component stays alive
+/// as long as at least one clone exists.
+///
 /// ## Warning
 /// You must keep your components as long as you want them to send/receive events.
 /// Common practice is to store `Component`s in the `Widget` struct (see the [communication
@@ -30,40 +103,109 @@ use super::{EventStream, Widget};
 /// The `#[widget]` attribute takes care of storing them in the struct automatically (see the
 /// [communication-attribute example](https://github.com/antoyo/relm/blob/master/examples/communication-attribute.rs)).
 #[must_use]
-#[derive(Clone)]
 pub struct Component<WIDGET: Widget> {
+    inner: Rc<ComponentInner<WIDGET>>,
+}
+
+struct ComponentInner<WIDGET: Widget> {
+    lifecycle: Rc<Lifecycle<WIDGET>>,
     stream: EventStream<WIDGET::Msg>,
     widget: WIDGET::Root,
 }
 
-impl<WIDGET: Widget> Drop for Component<WIDGET> {
+impl<WIDGET: Widget> Drop for ComponentInner<WIDGET> {
     fn drop(&mut self) {
+        if cfg!(debug_assertions) && !self.lifecycle.destroyed.get() && self.widget.get_mapped() {
+            warn!("A component was dropped while its widget is still displayed: it will not respond to events \
+                   anymore. Keep a handle to the component or call Component::destroy().");
+        }
+        self.lifecycle.call(Destroy);
         let _ = self.stream.close();
     }
 }
 
+impl<WIDGET: Widget> Clone for Component<WIDGET> {
+    fn clone(&self) -> Self {
+        Component {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
 impl<WIDGET: Widget> Component<WIDGET> {
     #[doc(hidden)]
-    pub fn new(stream: EventStream<WIDGET::Msg>, widget: WIDGET::Root) -> Self {
+    pub fn new(stream: EventStream<WIDGET::Msg>, widget: WIDGET::Root, lifecycle: Rc<Lifecycle<WIDGET>>) -> Self {
         Component {
-            stream,
-            widget,
+            inner: Rc::new(ComponentInner {
+                lifecycle,
+                stream,
+                widget,
+            }),
+        }
+    }
+
+    /// Destroy the component for every handle: remove its widget from its parent, destroy it and
+    /// stop processing messages.
+    pub fn destroy(&self) {
+        let widget = self.widget();
+        if let Some(parent) = widget.get_parent() {
+            if let Ok(container) = parent.downcast::<gtk::Container>() {
+                container.remove(widget);
+            }
+        }
+        widget.destroy();
+        self.inner.lifecycle.call(Destroy);
+        let _ = self.inner.stream.close();
+    }
+
+    /// Create a weak handle to this component, which does not keep it alive.
+    pub fn downgrade(&self) -> WeakComponent<WIDGET> {
+        WeakComponent {
+            inner: Rc::downgrade(&self.inner),
         }
     }
 
     /// Emit a message of the widget stream.
     pub fn emit(&self, msg: WIDGET::Msg) {
-        self.stream.emit(msg);
+        self.inner.stream.emit(msg);
+    }
+
+    #[doc(hidden)]
+    pub fn lifecycle(&self) -> &Rc<Lifecycle<WIDGET>> {
+        &self.inner.lifecycle
     }
 
     /// Get the event stream of the component.
     /// This is used internally by the library.
     pub fn stream(&self) -> &EventStream<WIDGET::Msg> {
-        &self.stream
+        &self.inner.stream
     }
 
     /// Get the widget of the component.
     pub fn widget(&self) -> &WIDGET::Root {
-        &self.widget
+        &self.inner.widget
+    }
+}
+
+/// Weak handle to a `Component`, created by
+/// [`Component::downgrade()`](struct.Component.html#method.downgrade).
+pub struct WeakComponent<WIDGET: Widget> {
+    inner: Weak<ComponentInner<WIDGET>>,
+}
+
+impl<WIDGET: Widget> Clone for WeakComponent<WIDGET> {
+    fn clone(&self) -> Self {
+        WeakComponent {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<WIDGET: Widget> WeakComponent<WIDGET> {
+    /// Get a strong handle to the component if it is still alive.
+    pub fn upgrade(&self) -> Option<Component<WIDGET>> {
+        self.inner.upgrade().map(|inner| Component {
+            inner,
+        })
     }
 }
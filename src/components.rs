@@ -0,0 +1,611 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Keyed collections of child components.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::cmp;
+use std::rc::Rc;
+use std::slice;
+
+use glib::{Cast, ToValue};
+use gtk::{self, ContainerExt, IsA, Object, WidgetExt};
+
+use super::{Component, DisplayVariant, Relm};
+use component::Hook;
+use container::{Container, ContainerComponent, ContainerWidget};
+use widget::Widget;
+
+/// Container in which a [`Components`](struct.Components.html) collection can add its children.
+///
+/// This is implemented for the GTK+ containers and for the relm `ContainerComponent`s.
+pub trait ChildContainer {
+    /// Create a relm `Widget` and add it to this container.
+    fn add_child<CHILDWIDGET, WIDGET>(&self, relm: &Relm<WIDGET>, model_param: CHILDWIDGET::ModelParam)
+            -> Component<CHILDWIDGET>
+        where CHILDWIDGET: Widget + 'static,
+              CHILDWIDGET::Msg: DisplayVariant + 'static,
+              CHILDWIDGET::Root: IsA<gtk::Widget> + IsA<Object> + WidgetExt,
+              WIDGET: Widget;
+}
+
+impl<W: ContainerWidget> ChildContainer for W {
+    fn add_child<CHILDWIDGET, WIDGET>(&self, relm: &Relm<WIDGET>, model_param: CHILDWIDGET::ModelParam)
+            -> Component<CHILDWIDGET>
+        where CHILDWIDGET: Widget + 'static,
+              CHILDWIDGET::Msg: DisplayVariant + 'static,
+              CHILDWIDGET::Root: IsA<gtk::Widget> + IsA<Object> + WidgetExt,
+              WIDGET: Widget,
+    {
+        self.add_widget::<CHILDWIDGET, WIDGET>(relm, model_param)
+    }
+}
+
+impl<CONTAINER: Container> ChildContainer for ContainerComponent<CONTAINER>
+    where CONTAINER::Container: ContainerExt + IsA<gtk::Widget> + IsA<Object>,
+{
+    fn add_child<CHILDWIDGET, WIDGET>(&self, relm: &Relm<WIDGET>, model_param: CHILDWIDGET::ModelParam)
+            -> Component<CHILDWIDGET>
+        where CHILDWIDGET: Widget + 'static,
+              CHILDWIDGET::Msg: DisplayVariant + 'static,
+              CHILDWIDGET::Root: IsA<gtk::Widget> + IsA<Object> + WidgetExt,
+              WIDGET: Widget,
+    {
+        self.add_widget::<CHILDWIDGET, WIDGET>(relm, model_param)
+    }
+}
+
+/// Collection of child components identified by a key, owned by a parent widget.
+///
+/// The order of the collection is kept in sync with the order of the widgets in their container.
+/// Reordering requires a container with a `position` child property, like `gtk::Box`.
+pub struct Components<KEY, WIDGET: Widget> {
+    children: Vec<(KEY, Component<WIDGET>)>,
+}
+
+impl<KEY: PartialEq, WIDGET: Widget + 'static> Components<KEY, WIDGET> {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Components {
+            children: vec![],
+        }
+    }
+
+    /// Remove all the children.
+    pub fn clear(&mut self) {
+        for (_, component) in self.children.drain(..) {
+            detach(component);
+        }
+    }
+
+    /// Check whether a child with this `key` exists.
+    pub fn contains_key(&self, key: &KEY) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Get the child with this `key`.
+    pub fn get(&self, key: &KEY) -> Option<&Component<WIDGET>> {
+        self.position(key).map(|index| &self.children[index].1)
+    }
+
+    /// Create a child with `model_param`, add it to `container` and insert it at `index` in the
+    /// collection.
+    /// If a child with the same `key` already exists, it is removed first and `index` is clamped
+    /// to the number of remaining children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of children.
+    pub fn insert<CONTAINER, PARENTWIDGET>(&mut self, container: &CONTAINER, relm: &Relm<PARENTWIDGET>,
+        index: usize, key: KEY, model_param: WIDGET::ModelParam) -> &Component<WIDGET>
+        where CONTAINER: ChildContainer,
+              PARENTWIDGET: Widget,
+              WIDGET::Msg: DisplayVariant + 'static,
+              WIDGET::Root: IsA<gtk::Widget> + IsA<Object> + WidgetExt,
+    {
+        assert!(index <= self.children.len(), "insertion index (is {}) should be <= len (is {})", index,
+            self.children.len());
+        let _ = self.remove(&key);
+        let index = cmp::min(index, self.children.len());
+        let component = container.add_child::<WIDGET, PARENTWIDGET>(relm, model_param);
+        self.children.insert(index, (key, component));
+        self.sync_position(index);
+        &self.children[index].1
+    }
+
+    /// Check whether the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Iterate over the keys and children, in order.
+    pub fn iter(&self) -> ComponentsIter<KEY, WIDGET> {
+        ComponentsIter {
+            iter: self.children.iter(),
+        }
+    }
+
+    /// Iterate over the keys, in order.
+    pub fn keys<'a>(&'a self) -> Box<Iterator<Item=&'a KEY> + 'a> {
+        Box::new(self.children.iter().map(|&(ref key, _)| key))
+    }
+
+    /// Get the number of children.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Move the child with this `key` to `index`.
+    /// Return `false` if there is no such child.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than or equal to the number of children.
+    pub fn move_to(&mut self, key: &KEY, index: usize) -> bool {
+        assert!(index < self.children.len(), "index (is {}) should be < len (is {})", index, self.children.len());
+        match self.position(key) {
+            Some(current) => {
+                let child = self.children.remove(current);
+                self.children.insert(index, child);
+                self.sync_position(index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Get the index of the child with this `key`.
+    pub fn position(&self, key: &KEY) -> Option<usize> {
+        self.children.iter().position(|&(ref child_key, _)| child_key == key)
+    }
+
+    /// Create a child with `model_param`, add it to `container` and append it to the collection.
+    pub fn push<CONTAINER, PARENTWIDGET>(&mut self, container: &CONTAINER, relm: &Relm<PARENTWIDGET>, key: KEY,
+        model_param: WIDGET::ModelParam) -> &Component<WIDGET>
+        where CONTAINER: ChildContainer,
+              PARENTWIDGET: Widget,
+              WIDGET::Msg: DisplayVariant + 'static,
+              WIDGET::Root: IsA<gtk::Widget> + IsA<Object> + WidgetExt,
+    {
+        let _ = self.remove(&key);
+        let index = self.children.len();
+        self.insert(container, relm, index, key, model_param)
+    }
+
+    /// Remove the child with this `key`: its widget is removed from its container and destroyed
+    /// and its stream is closed.
+    /// Return `false` if there is no such child.
+    pub fn remove(&mut self, key: &KEY) -> bool {
+        match self.position(key) {
+            Some(index) => {
+                let (_, component) = self.children.remove(index);
+                detach(component);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Move the widget of the child at `index` in its container, so that it is placed right after
+    /// the widget of the previous child (or before the widget of the next child).
+    fn sync_position(&self, index: usize) {
+        let root = |component: &Component<WIDGET>| component.widget().clone().upcast::<gtk::Widget>();
+        let previous = index.checked_sub(1).map(|index| root(&self.children[index].1));
+        let next = self.children.get(index + 1).map(|child| root(&child.1));
+        move_widget(&root(&self.children[index].1), previous.as_ref(), next.as_ref());
+    }
+}
+
+impl<KEY: PartialEq, WIDGET: Widget + 'static> Default for Components<KEY, WIDGET> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, KEY, WIDGET: Widget> IntoIterator for &'a Components<KEY, WIDGET> {
+    type IntoIter = ComponentsIter<'a, KEY, WIDGET>;
+    type Item = (&'a KEY, &'a Component<WIDGET>);
+
+    fn into_iter(self) -> Self::IntoIter {
+        ComponentsIter {
+            iter: self.children.iter(),
+        }
+    }
+}
+
+/// Iterator over the keys and children of a `Components` collection.
+pub struct ComponentsIter<'a, KEY: 'a, WIDGET: Widget + 'a> {
+    iter: slice::Iter<'a, (KEY, Component<WIDGET>)>,
+}
+
+impl<'a, KEY, WIDGET: Widget> Iterator for ComponentsIter<'a, KEY, WIDGET> {
+    type Item = (&'a KEY, &'a Component<WIDGET>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|&(ref key, ref component)| (key, component))
+    }
+}
+
+/// Place of the children of a `for` loop or of a condition in the `view!` macro among the other
+/// children of their container: right after the static widget declared before them and the
+/// children of the loops and conditions declared between this widget and them.
+#[doc(hidden)]
+pub struct DynamicPosition {
+    len: Rc<Cell<usize>>,
+    preceding: Vec<Rc<Cell<usize>>>,
+    previous: Option<gtk::Widget>,
+}
+
+impl DynamicPosition {
+    #[doc(hidden)]
+    pub fn new(previous: Option<gtk::Widget>, preceding: &[&DynamicPosition]) -> Self {
+        DynamicPosition {
+            len: Rc::new(Cell::new(0)),
+            preceding: preceding.iter().map(|position| position.len.clone()).collect(),
+            previous,
+        }
+    }
+
+    /// Move `widget` in its container so that it is the child at `index` among these children.
+    fn place(&self, widget: &gtk::Widget, index: usize) {
+        let container =
+            match widget.get_parent().and_then(|parent| parent.downcast::<gtk::Container>().ok()) {
+                Some(container) => container,
+                None => return,
+            };
+        let start = self.previous.as_ref()
+            .and_then(|previous| container.get_children().iter().position(|child| child == previous))
+            .map_or(0, |position| position + 1);
+        let preceding: usize = self.preceding.iter().map(|len| len.get()).sum();
+        let position = start + preceding + index;
+        container.child_set_property(widget, "position", &(position as i32).to_value());
+    }
+}
+
+/// Children created by a `for` loop in the `view!` macro, identified by their key.
+#[doc(hidden)]
+pub struct LoopChildren<CHILD> {
+    children: Vec<(Box<Any>, CHILD, gtk::Widget)>,
+    position: DynamicPosition,
+}
+
+impl<CHILD> LoopChildren<CHILD> {
+    #[doc(hidden)]
+    pub fn new(position: DynamicPosition) -> Self {
+        LoopChildren {
+            children: vec![],
+            position,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn get(&self, index: usize) -> &CHILD {
+        &self.children[index].1
+    }
+
+    #[doc(hidden)]
+    pub fn insert<KEY: Eq + 'static>(&mut self, index: usize, key: KEY, widget: gtk::Widget, child: CHILD) {
+        self.children.insert(index, (Box::new(key), child, widget));
+        self.position.len.set(self.children.len());
+        self.position.place(&self.children[index].2, index);
+    }
+
+    /// Move the child with this `key` to `index` if it exists at or after this index.
+    #[doc(hidden)]
+    pub fn place<KEY: Eq + 'static>(&mut self, index: usize, key: &KEY) -> bool {
+        let position = self.children[index..].iter()
+            .position(|&(ref child_key, _, _)| child_key.downcast_ref() == Some(key));
+        match position {
+            Some(0) => true,
+            Some(offset) => {
+                let child = self.children.remove(index + offset);
+                self.children.insert(index, child);
+                self.position.place(&self.children[index].2, index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn position(&self) -> &DynamicPosition {
+        &self.position
+    }
+
+    /// Remove and destroy the children after `len`.
+    #[doc(hidden)]
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.children.len() {
+            for (_, _, widget) in self.children.drain(len..) {
+                destroy_widget(&widget);
+            }
+            self.position.len.set(len);
+        }
+    }
+}
+
+/// Child created by the active branch of an `if` or a `match` in the `view!` macro.
+#[doc(hidden)]
+pub struct Branch {
+    current: Option<(usize, Box<Any>, gtk::Widget)>,
+    position: DynamicPosition,
+}
+
+impl Branch {
+    #[doc(hidden)]
+    pub fn new(position: DynamicPosition) -> Self {
+        Branch {
+            current: None,
+            position,
+        }
+    }
+
+    /// Remove and destroy the child of the active branch.
+    #[doc(hidden)]
+    pub fn clear(&mut self) {
+        if let Some((_, _, widget)) = self.current.take() {
+            destroy_widget(&widget);
+            self.position.len.set(0);
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn get<CHILD: 'static>(&self) -> &CHILD {
+        self.current.as_ref()
+            .and_then(|&(_, ref child, _)| child.downcast_ref())
+            .expect("child of the active branch")
+    }
+
+    #[doc(hidden)]
+    pub fn is_active(&self, index: usize) -> bool {
+        self.current.as_ref().map(|&(current, _, _)| current) == Some(index)
+    }
+
+    #[doc(hidden)]
+    pub fn position(&self) -> &DynamicPosition {
+        &self.position
+    }
+
+    #[doc(hidden)]
+    pub fn set<CHILD: 'static>(&mut self, index: usize, widget: gtk::Widget, child: CHILD) {
+        self.clear();
+        self.position.len.set(1);
+        self.position.place(&widget, 0);
+        self.current = Some((index, Box::new(child), widget));
+    }
+}
+
+/// Remove `widget` from its container and destroy it.
+fn destroy_widget(widget: &gtk::Widget) {
+    if let Some(container) = widget.get_parent().and_then(|parent| parent.downcast::<gtk::Container>().ok()) {
+        container.remove(widget);
+    }
+    widget.destroy();
+}
+
+/// Move `widget` in its container so that it is placed right after `previous` (or right before
+/// `next` when there is no previous widget).
+fn move_widget(widget: &gtk::Widget, previous: Option<&gtk::Widget>, next: Option<&gtk::Widget>) {
+    let container =
+        match widget.get_parent().and_then(|parent| parent.downcast::<gtk::Container>().ok()) {
+            Some(container) => container,
+            None => return,
+        };
+    let children = container.get_children();
+    let container_position = |sibling: &gtk::Widget| children.iter().position(|child| child == sibling);
+    let current = container_position(widget);
+    // The position is the one of the widget after it has been moved, i.e. after it has been
+    // removed from its current position.
+    let is_before = |position| current.map_or(false, |current| current < position);
+    let target =
+        if let Some(previous) = previous {
+            container_position(previous)
+                .map(|previous| if is_before(previous) { previous } else { previous + 1 })
+        }
+        else {
+            next.and_then(|next| container_position(next))
+                .map(|next| if is_before(next) { next - 1 } else { next })
+        };
+    if let Some(target) = target {
+        container.child_set_property(widget, "position", &(target as i32).to_value());
+    }
+}
+
+/// Remove the widget of `component` from its container and destroy the component.
+fn detach<WIDGET: Widget>(component: Component<WIDGET>) {
+    if let Some(parent) = component.widget().get_parent() {
+        component.lifecycle().call(Hook::Remove(parent));
+    }
+    component.destroy();
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_glib;
+    use glib::Cast;
+    use gtk::{self, ContainerExt, Label, LabelExt};
+    use gtk::Orientation::Horizontal;
+
+    use {DisplayVariant, EventStream, Relm, Update, Widget, create_executor, init_gtk};
+    use super::{Branch, Components, DynamicPosition, LoopChildren};
+
+    struct Item {
+        label: Label,
+    }
+
+    struct Msg;
+
+    impl DisplayVariant for Msg {
+        fn display_variant(&self) -> &'static str {
+            "Msg"
+        }
+    }
+
+    impl Update for Item {
+        type Model = &'static str;
+        type ModelParam = &'static str;
+        type Msg = Msg;
+
+        fn model(_: &Relm<Self>, text: &'static str) -> &'static str {
+            text
+        }
+
+        fn update(&mut self, _event: Msg) {
+        }
+    }
+
+    impl Widget for Item {
+        type Root = Label;
+
+        fn root(&self) -> Self::Root {
+            self.label.clone()
+        }
+
+        fn view(_relm: &Relm<Self>, text: &'static str) -> Self {
+            Item {
+                label: Label::new(text),
+            }
+        }
+    }
+
+    fn texts(container: &gtk::Box) -> Vec<String> {
+        container.get_children().into_iter()
+            .filter_map(|child| child.downcast::<Label>().ok())
+            .filter_map(|label| label.get_text())
+            .collect()
+    }
+
+    fn keys(components: &Components<u32, Item>) -> Vec<u32> {
+        components.keys().cloned().collect()
+    }
+
+    // GTK+ can only be used from a single thread, so all the checks are done in the same test.
+    #[test]
+    fn gtk() {
+        futures_glib::init();
+        init_gtk();
+        let relm = Relm::<Item>::new(create_executor(), EventStream::new());
+        let container = gtk::Box::new(Horizontal, 0);
+        keyed_components(&relm, &container);
+        let container = gtk::Box::new(Horizontal, 0);
+        loop_children(&container);
+        let container = gtk::Box::new(Horizontal, 0);
+        dynamic_positions(&container);
+    }
+
+    fn keyed_components(relm: &Relm<Item>, container: &gtk::Box) {
+        let mut components = Components::new();
+        assert!(components.is_empty());
+        let _ = components.push(container, relm, 1, "a");
+        let _ = components.push(container, relm, 2, "b");
+        let _ = components.insert(container, relm, 0, 3, "c");
+        assert_eq!(keys(&components), vec![3, 1, 2]);
+        assert_eq!(texts(container), vec!["c", "a", "b"]);
+        assert_eq!(components.position(&1), Some(1));
+        assert!(components.get(&2).is_some());
+        assert!(!components.contains_key(&4));
+
+        assert!(components.move_to(&2, 0));
+        assert!(!components.move_to(&4, 0));
+        assert_eq!(keys(&components), vec![2, 3, 1]);
+        assert_eq!(texts(container), vec!["b", "c", "a"]);
+
+        // Replacing a child with an index equal to the number of children.
+        let _ = components.insert(container, relm, 3, 2, "d");
+        assert_eq!(keys(&components), vec![3, 1, 2]);
+        assert_eq!(texts(container), vec!["c", "a", "d"]);
+
+        assert!(components.remove(&1));
+        assert!(!components.remove(&1));
+        assert_eq!(keys(&components), vec![3, 2]);
+        assert_eq!(texts(container), vec!["c", "d"]);
+
+        components.clear();
+        assert_eq!(components.len(), 0);
+        assert!(texts(container).is_empty());
+    }
+
+    fn label(container: &gtk::Box, text: &str) -> Label {
+        let label = Label::new(text);
+        container.add(&label);
+        label
+    }
+
+    fn loop_children(container: &gtk::Box) {
+        let mut children = LoopChildren::new(DynamicPosition::new(None, &[]));
+        for (index, text) in ["a", "b", "c"].iter().enumerate() {
+            let label = label(container, text);
+            children.insert(index, text.to_string(), label.clone().upcast(), label);
+        }
+
+        // The children are compared by key, not by hash.
+        assert!(!children.place(0, &"d".to_string()));
+        assert!(!children.place(0, &1));
+        assert!(children.place(0, &"a".to_string()));
+        assert!(children.place(1, &"c".to_string()));
+        assert_eq!(children.get(1).get_text(), Some("c".to_string()));
+        assert_eq!(texts(container), vec!["a", "c", "b"]);
+
+        // A child placed before the index is not moved back.
+        assert!(!children.place(2, &"a".to_string()));
+
+        let label = label(container, "d");
+        children.insert(0, "d".to_string(), label.clone().upcast(), label);
+        assert_eq!(texts(container), vec!["d", "a", "c", "b"]);
+
+        children.truncate(2);
+        assert_eq!(texts(container), vec!["d", "a"]);
+    }
+
+    fn dynamic_positions(container: &gtk::Box) {
+        let first = label(container, "first");
+        let _ = label(container, "last");
+        let mut children = LoopChildren::new(DynamicPosition::new(Some(first.clone().upcast()), &[]));
+        let mut branch = Branch::new(DynamicPosition::new(Some(first.upcast()), &[children.position()]));
+
+        let label_x = label(container, "x");
+        branch.set(0, label_x.clone().upcast(), label_x);
+        assert_eq!(texts(container), vec!["first", "x", "last"]);
+
+        for (index, text) in ["a", "b"].iter().enumerate() {
+            let label = label(container, text);
+            children.insert(index, index, label.clone().upcast(), label);
+        }
+        assert_eq!(texts(container), vec!["first", "a", "b", "x", "last"]);
+
+        let label_y = label(container, "y");
+        branch.set(1, label_y.clone().upcast(), label_y);
+        assert!(branch.is_active(1));
+        assert_eq!(branch.get::<Label>().get_text(), Some("y".to_string()));
+        assert_eq!(texts(container), vec!["first", "a", "b", "y", "last"]);
+
+        children.truncate(0);
+        assert_eq!(texts(container), vec!["first", "y", "last"]);
+
+        let label = label(container, "c");
+        children.insert(0, 2, label.clone().upcast(), label);
+        assert_eq!(texts(container), vec!["first", "c", "y", "last"]);
+
+        branch.clear();
+        assert_eq!(texts(container), vec!["first", "c", "last"]);
+    }
+}
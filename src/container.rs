@@ -24,7 +24,8 @@ use gtk;
 use gtk::{ContainerExt, IsA, Object, WidgetExt};
 
 use relm_state::EventStream;
-use super::{Component, DisplayVariant, Relm, create_widget, init_component};
+use super::{Component, DisplayVariant, Relm, WeakComponent, create_widget, init_widget};
+use component::Hook;
 use widget::Widget;
 
 /// Struct for relm containers to add GTK+ and relm `Widget`s.
@@ -60,11 +61,27 @@ impl<WIDGET: Container + Widget> ContainerComponent<WIDGET> {
     {
         let (widget, component, child_relm) = create_widget::<CHILDWIDGET>(relm.executor(), model_param);
         let container = WIDGET::add_widget(self, &widget);
-        component.on_add(container);
-        init_component::<CHILDWIDGET>(widget.stream(), component, relm.executor(), &child_relm);
+        component.borrow().on_add(container);
+        init_widget::<CHILDWIDGET>(&widget, component, relm.executor(), &child_relm);
         widget
     }
 
+    /// Get the underlying component handle.
+    pub fn component(&self) -> &Component<WIDGET> {
+        &self.component
+    }
+
+    /// Destroy the component for every handle: remove its widget from its parent, destroy it and
+    /// stop processing messages.
+    pub fn destroy(&self) {
+        self.component.destroy();
+    }
+
+    /// Create a weak handle to this component, which does not keep it alive.
+    pub fn downgrade(&self) -> WeakComponent<WIDGET> {
+        self.component.downgrade()
+    }
+
     /// Emit a message of the widget stream.
     pub fn emit(&self, msg: WIDGET::Msg) {
         self.stream().emit(msg);
@@ -76,8 +93,6 @@ impl<WIDGET: Container + Widget> ContainerComponent<WIDGET> {
         self.component.stream()
     }
 
-    // TODO: add delete methods?
-
     /// Get the widget of the component.
     pub fn widget(&self) -> &WIDGET::Root {
         self.component.widget()
@@ -139,6 +154,11 @@ pub trait ContainerWidget {
               WIDGET: Widget;
 
     /// Remove a relm `Widget` from the current GTK+ container.
+    ///
+    /// The [`Widget::on_remove()`](trait.Widget.html#method.on_remove) hook of the widget is called.
+    /// Its [`Widget::on_destroy()`](trait.Widget.html#method.on_destroy) hook is only called when the
+    /// last clone of the `Component` is dropped, i.e. at the end of this call if `component` was the
+    /// last one.
     fn remove_widget<CHILDWIDGET>(&self, component: Component<CHILDWIDGET>)
         where CHILDWIDGET: Widget,
               CHILDWIDGET::Root: IsA<gtk::Widget>;
@@ -153,12 +173,12 @@ impl<W: Clone + ContainerExt + IsA<gtk::Widget> + IsA<Object>> ContainerWidget f
               WIDGET: Widget,
     {
         let (widget, component, child_relm) = create_widget::<CHILDWIDGET>(relm.executor(), model_param);
-        let container = component.container().clone();
-        let containers = component.other_containers();
-        let root = component.root().clone();
+        let container = component.borrow().container().clone();
+        let containers = component.borrow().other_containers();
+        let root = component.borrow().root().clone();
         self.add(&root);
-        component.on_add(self.clone());
-        init_component::<CHILDWIDGET>(widget.stream(), component, relm.executor(), &child_relm);
+        component.borrow().on_add(self.clone());
+        init_widget::<CHILDWIDGET>(&widget, component, relm.executor(), &child_relm);
         ContainerComponent::new(widget, container, containers)
     }
 
@@ -171,8 +191,8 @@ impl<W: Clone + ContainerExt + IsA<gtk::Widget> + IsA<Object>> ContainerWidget f
     {
         let (widget, component, child_relm) = create_widget::<CHILDWIDGET>(relm.executor(), model_param);
         self.add(widget.widget());
-        component.on_add(self.clone());
-        init_component::<CHILDWIDGET>(widget.stream(), component, relm.executor(), &child_relm);
+        component.borrow().on_add(self.clone());
+        init_widget::<CHILDWIDGET>(&widget, component, relm.executor(), &child_relm);
         widget
     }
 
@@ -181,5 +201,6 @@ impl<W: Clone + ContainerExt + IsA<gtk::Widget> + IsA<Object>> ContainerWidget f
               WIDGET::Root: IsA<gtk::Widget>,
     {
         self.remove(component.widget());
+        component.lifecycle().call(Hook::Remove(self.clone().upcast()));
     }
 }
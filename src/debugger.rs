@@ -0,0 +1,202 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Time-travel debugger: an inspector window to scrub through the messages recorded for a component.
+
+use std::fmt::Debug;
+
+use gtk::{
+    self,
+    Button,
+    ButtonExt,
+    ContainerExt,
+    Label,
+    LabelExt,
+    RangeExt,
+    Scale,
+    ScaleExt,
+    TextBufferExt,
+    TextView,
+    TextViewExt,
+    WidgetExt,
+    Window,
+    WindowType,
+};
+use gtk::Orientation::{Horizontal, Vertical};
+use relm_state::{Change, Record, Recorder};
+
+use super::{DisplayVariant, Relm, Update, Widget};
+use self::DebuggerMsg::*;
+
+#[doc(hidden)]
+pub enum DebuggerMsg {
+    Recorded(usize),
+    Replay,
+    Select(usize),
+}
+
+impl DisplayVariant for DebuggerMsg {
+    fn display_variant(&self) -> &'static str {
+        match *self {
+            Recorded(_) => "Recorded",
+            Replay => "Replay",
+            Select(_) => "Select",
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct DebuggerModel<UPDATE: Update> {
+    recorder: Recorder<UPDATE>,
+    selected: usize,
+}
+
+/// Inspector window showing the messages recorded for the component `UPDATE`.
+///
+/// The slider selects a message, the text view shows how this message changed the model and the
+/// button restores the model at this point so that the application continues from there.
+pub struct Debugger<UPDATE: Update> {
+    diff_view: TextView,
+    message_label: Label,
+    model: DebuggerModel<UPDATE>,
+    scale: Scale,
+    window: Window,
+}
+
+impl<UPDATE> Debugger<UPDATE>
+    where UPDATE: Record,
+          UPDATE::Model: Clone + Debug,
+{
+    fn show_entry(&self) {
+        let index = self.model.selected;
+        if let Some(entry) = self.model.recorder.entry(index) {
+            self.message_label.set_text(&format!("#{} {}", index, entry.msg));
+        }
+        let mut text = String::new();
+        for change in self.model.recorder.diff(index) {
+            let (prefix, line) =
+                match change {
+                    Change::Added(line) => ("+ ", line),
+                    Change::Removed(line) => ("- ", line),
+                    Change::Same(line) => ("  ", line),
+                };
+            text.push_str(prefix);
+            text.push_str(&line);
+            text.push('\n');
+        }
+        if let Some(buffer) = self.diff_view.get_buffer() {
+            buffer.set_text(&text);
+        }
+    }
+}
+
+impl<UPDATE> Update for Debugger<UPDATE>
+    where UPDATE: Record,
+          UPDATE::Model: Clone + Debug,
+{
+    type Model = DebuggerModel<UPDATE>;
+    type ModelParam = Recorder<UPDATE>;
+    type Msg = DebuggerMsg;
+
+    fn model(_: &Relm<Self>, recorder: Recorder<UPDATE>) -> Self::Model {
+        DebuggerModel {
+            recorder,
+            selected: 0,
+        }
+    }
+
+    fn subscriptions(&mut self, relm: &Relm<Self>) {
+        let stream = relm.stream().clone();
+        self.model.recorder.observe(move |len| stream.emit(Recorded(len)));
+    }
+
+    fn update(&mut self, event: DebuggerMsg) {
+        match event {
+            Recorded(len) => {
+                let last = len.saturating_sub(1);
+                // Follow the new messages, unless the user is looking at an older one.
+                let follow = self.model.selected + 1 >= last;
+                self.scale.set_range(0.0, last.max(1) as f64);
+                if follow {
+                    self.model.selected = last;
+                    self.scale.set_value(last as f64);
+                    self.show_entry();
+                }
+            },
+            Replay => self.model.recorder.replay_from(self.model.selected),
+            Select(index) => {
+                self.model.selected = index;
+                self.show_entry();
+            },
+        }
+    }
+}
+
+impl<UPDATE> Widget for Debugger<UPDATE>
+    where UPDATE: Record + 'static,
+          UPDATE::Model: Clone + Debug,
+{
+    type Root = Window;
+
+    fn root(&self) -> Self::Root {
+        self.window.clone()
+    }
+
+    fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
+        let window = Window::new(WindowType::Toplevel);
+        window.set_title("relm debugger");
+        window.set_default_size(400, 500);
+
+        let vbox = gtk::Box::new(Vertical, 0);
+
+        let scale = Scale::new_with_range(Horizontal, 0.0, 1.0, 1.0);
+        scale.set_digits(0);
+        vbox.add(&scale);
+
+        let message_label = Label::new(None);
+        vbox.add(&message_label);
+
+        let diff_view = TextView::new();
+        diff_view.set_editable(false);
+        diff_view.set_monospace(true);
+        diff_view.set_vexpand(true);
+        vbox.add(&diff_view);
+
+        let replay_button = Button::new_with_label("Replay from here");
+        vbox.add(&replay_button);
+
+        window.add(&vbox);
+        window.show_all();
+
+        connect!(relm, scale, connect_value_changed(scale), Select(scale.get_value() as usize));
+        connect!(relm, replay_button, connect_clicked(_), Replay);
+
+        let debugger = Debugger {
+            diff_view,
+            message_label,
+            model,
+            scale,
+            window,
+        };
+        debugger.show_entry();
+        debugger
+    }
+}
@@ -47,23 +47,16 @@
 )]
 
 /*
- * TODO: show a warning when a component is imediately destroyed.
  * FIXME: cannot add a trailing coma at the end of a initializer list.
- * TODO: switch from gtk::main() to MainLoop to avoid issues with nested loops.
  * TODO: prefix generated container name with _ to hide warnings.
  * TODO: remove the code generation related to using self in event handling.
  * TODO: remove the closure transformer code.
  *
  * TODO: move most of the examples in the tests/ directory.
- * TODO: add construct-only properties for relm widget (to replace initial parameters) to allow
- * setting them by name (or with default value).
- * TODO: find a way to do two-step initialization (to avoid using unitialized in model()).
- * TODO: consider using GBinding instead of manually adding calls to set_property().
  *
  * TODO: add a FAQ with the question related to getting an error when not importing the gtk traits.
  *
  * TODO: show a warning when components are destroyed after the end of call to Widget::view().
- * TODO: warn in the attribute when an event cycle is found.
  * TODO: add a Deref<Widget> for Component?
  * TODO: look at how Elm works with the <canvas> element.
  * TODO: the widget names should start with __relm_field_.
@@ -88,19 +81,35 @@
  */
 
 extern crate futures_glib;
+extern crate gio;
 extern crate glib;
 extern crate glib_sys;
 extern crate gobject_sys;
 extern crate gtk;
 extern crate gtk_sys;
 extern crate libc;
+#[macro_use]
+extern crate log;
 extern crate relm_core;
+#[cfg(all(debug_assertions, feature = "hot-reload"))]
+extern crate relm_gen_widget;
 extern crate relm_state;
 
+mod application;
+mod binding;
 mod component;
+mod components;
 mod container;
+#[cfg(debug_assertions)]
+mod debugger;
 mod macros;
+mod reload;
 mod widget;
+mod window;
+
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
 
 use futures_glib::Executor;
 #[doc(hidden)]
@@ -112,6 +121,7 @@ pub use glib::object::Downcast;
 #[doc(hidden)]
 pub use glib::translate::{FromGlibPtrNone, ToGlib, ToGlibPtr};
 use glib_sys::GType;
+use gtk::WidgetExt;
 #[doc(hidden)]
 pub use gobject_sys::{GParameter, g_object_newv};
 use gobject_sys::{GObject, GValue};
@@ -120,19 +130,44 @@ use libc::{c_char, c_uint};
 pub use relm_core::EventStream;
 pub use relm_state::{
     DisplayVariant,
+    ExitStatus,
+    Interval,
     IntoOption,
     IntoPair,
+    Record,
     Relm,
+    Timeout,
     Update,
     UpdateNew,
     create_executor,
     execute,
+    now,
+    quit,
+    run_main_loop,
 };
-use relm_state::init_component;
+#[cfg(debug_assertions)]
+pub use relm_state::{Change, Entry, Recorder};
+use relm_state::init_shared_component;
+#[cfg(debug_assertions)]
+use relm_state::init_shared_component_recorded;
 
-pub use component::Component;
+pub use application::Application;
+pub use binding::{FromWidgetValue, ToWidgetValue};
+#[doc(hidden)]
+pub use binding::{bind_property, bind_property_transform, connect_notify};
+pub use component::{Component, WeakComponent};
+use component::{Hook, Lifecycle};
+pub use components::{ChildContainer, Components, ComponentsIter};
+#[doc(hidden)]
+pub use components::{Branch, DynamicPosition, LoopChildren};
 pub use container::{Container, ContainerComponent, ContainerWidget};
+#[cfg(debug_assertions)]
+pub use debugger::Debugger;
+#[doc(hidden)]
+pub use reload::watch_view_file;
 pub use widget::Widget;
+pub use window::{OpenWindow, WindowHandle, open_windows, quit_on_last_window, windows};
+use window::register_window;
 
 extern "C" {
     pub fn g_object_new_with_properties(object_type: GType, n_properties: c_uint, names: *mut *const c_char,
@@ -149,9 +184,37 @@ macro_rules! impl_widget {
     };
 }
 
+/// Dummy macro holding the options of `relm_widget!` for `#[derive(Widget)]`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! widget_options {
+    ($($tt:tt)*) => {
+        ()
+    };
+}
+
 /// Macro to be used as a stable alternative to the #[widget] attribute.
+///
+/// The options of the attribute can be given with `#[widget(...)]` before the `impl`.
 #[macro_export]
 macro_rules! relm_widget {
+    (#[widget($($options:tt)*)] $($tts:tt)*) => {
+        mod __relm_gen_private {
+            use super::*;
+
+            #[derive(Widget)]
+            struct __RelmPrivateWidget {
+                options: widget_options! {
+                    $($options)*
+                },
+                widget: impl_widget! {
+                    $($tts)*
+                }
+            }
+        }
+
+        use_impl_self_type!($($tts)*);
+    };
     ($($tts:tt)*) => {
         mod __relm_gen_private {
             use super::*;
@@ -181,7 +244,7 @@ fn create_widget_test<WIDGET>(executor: &Executor, model_param: WIDGET::ModelPar
           WIDGET::Msg: DisplayVariant + 'static,
 {
     let (widget, component, relm) = create_widget(executor, model_param);
-    init_component::<WIDGET>(widget.stream(), component, executor, &relm);
+    init_widget::<WIDGET>(&widget, component, executor, &relm);
     widget
 }
 
@@ -194,7 +257,7 @@ pub fn create_component<CHILDWIDGET, WIDGET>(relm: &Relm<WIDGET>, model_param: C
           WIDGET: Widget,
 {
     let (widget, component, child_relm) = create_widget::<CHILDWIDGET>(relm.executor(), model_param);
-    init_component::<CHILDWIDGET>(widget.stream(), component, relm.executor(), &child_relm);
+    init_widget::<CHILDWIDGET>(&widget, component, relm.executor(), &child_relm);
     widget
 }
 
@@ -207,15 +270,15 @@ pub fn create_container<CHILDWIDGET, WIDGET>(relm: &Relm<WIDGET>, model_param: C
           WIDGET: Widget,
 {
     let (widget, component, child_relm) = create_widget::<CHILDWIDGET>(relm.executor(), model_param);
-    let container = component.container().clone();
-    let containers = component.other_containers();
-    init_component::<CHILDWIDGET>(widget.stream(), component, relm.executor(), &child_relm);
+    let container = component.borrow().container().clone();
+    let containers = component.borrow().other_containers();
+    init_widget::<CHILDWIDGET>(&widget, component, relm.executor(), &child_relm);
     ContainerComponent::new(widget, container, containers)
 }
 
 /// Create a new relm widget with `model_param` as initialization value.
 fn create_widget<WIDGET>(executor: &Executor, model_param: WIDGET::ModelParam)
-    -> (Component<WIDGET>, WIDGET, Relm<WIDGET>)
+    -> (Component<WIDGET>, Rc<RefCell<WIDGET>>, Relm<WIDGET>)
     where WIDGET: Widget + 'static,
           WIDGET::Msg: DisplayVariant + 'static,
 {
@@ -223,11 +286,39 @@ fn create_widget<WIDGET>(executor: &Executor, model_param: WIDGET::ModelParam)
 
     let relm = Relm::new(executor.clone(), stream.clone());
     let model = WIDGET::model(&relm, model_param);
+    // The children are created, and initialized, by the view: on_init() is called on them first.
     let mut widget = WIDGET::view(&relm, model);
     widget.init_view();
+    widget.on_init(&relm);
 
     let root = widget.root().clone();
-    (Component::new(stream, root), widget, relm)
+    let widget = Rc::new(RefCell::new(widget));
+    let lifecycle = Rc::new(Lifecycle::new(Rc::downgrade(&widget)));
+    (Component::new(stream, root, lifecycle), widget, relm)
+}
+
+/// Connect the lifecycle hooks of `widget` to its root widget.
+fn connect_lifecycle<WIDGET: Widget>(component: &Component<WIDGET>) {
+    let root = component.widget();
+    let lifecycle = component.lifecycle().clone();
+    let _ = root.connect_destroy(move |_| lifecycle.call(Hook::Destroy));
+    let lifecycle = component.lifecycle().clone();
+    let _ = root.connect_map(move |_| lifecycle.call(Hook::Show));
+    let lifecycle = component.lifecycle().clone();
+    let _ = root.connect_unmap(move |_| lifecycle.call(Hook::Hide));
+}
+
+/// Initialize a widget by creating its subscriptions, dispatching the messages from the stream of
+/// `component` and calling its lifecycle hooks.
+fn init_widget<WIDGET>(component: &Component<WIDGET>, widget: Rc<RefCell<WIDGET>>, executor: &Executor,
+    relm: &Relm<WIDGET>)
+    where WIDGET: Widget + 'static,
+          WIDGET::Msg: DisplayVariant + 'static,
+{
+    connect_lifecycle(component);
+    let lifecycle = component.lifecycle().clone();
+    init_shared_component(component.stream(), widget, executor, relm,
+        move |widget| lifecycle.call_pending(widget));
 }
 
 // TODO: remove this workaround.
@@ -311,7 +402,7 @@ pub fn init<WIDGET>(model_param: WIDGET::ModelParam) -> Result<Component<WIDGET>
 
     let executor = create_executor();
     let (widget, component, relm) = create_widget::<WIDGET>(&executor, model_param);
-    init_component::<WIDGET>(widget.stream(), component, &executor, &relm);
+    init_widget::<WIDGET>(&widget, component, &executor, &relm);
     Ok(widget)
 }
 
@@ -365,10 +456,58 @@ pub fn init<WIDGET>(model_param: WIDGET::ModelParam) -> Result<Component<WIDGET>
 /// Win::run(()).unwrap();
 /// # }
 /// ```
-pub fn run<WIDGET>(model_param: WIDGET::ModelParam) -> Result<(), ()>
+///
+/// The main loop runs until [`quit()`](fn.quit.html) is called, and its exit status is returned.
+/// Since it is not the loop of `gtk::main()`, `gtk::main_quit()` does not stop it.
+///
+/// When the root of the widget is a `gtk::Window`, it is managed like the windows opened with
+/// [`OpenWindow::open_window()`](trait.OpenWindow.html#tymethod.open_window).
+pub fn run<WIDGET>(model_param: WIDGET::ModelParam) -> Result<ExitStatus, ()>
     where WIDGET: Widget + 'static,
 {
-    let _component = init::<WIDGET>(model_param)?;
-    gtk::main();
-    Ok(())
+    let component = init::<WIDGET>(model_param)?;
+    let _component = register_window(component);
+    Ok(run_main_loop())
+}
+
+/// Same as [`run()`](fn.run.html), but also opens a [`Debugger`](struct.Debugger.html) window
+/// recording every message sent to the widget with the resulting model.
+///
+/// The recording only happens in debug builds: in release builds, this function is the same as
+/// `run()`.
+pub fn run_with_debugger<WIDGET>(model_param: WIDGET::ModelParam) -> Result<ExitStatus, ()>
+    where WIDGET: Widget + Record + 'static,
+          WIDGET::Model: Clone + Debug + 'static,
+          WIDGET::Msg: DisplayVariant + 'static,
+{
+    #[cfg(debug_assertions)]
+    let (component, _debugger) = init_with_debugger::<WIDGET>(model_param)?;
+    #[cfg(not(debug_assertions))]
+    let component = init::<WIDGET>(model_param)?;
+    let _component = register_window(component);
+    Ok(run_main_loop())
+}
+
+/// Initialize a widget like [`init()`](fn.init.html) while recording its messages, and the
+/// [`Debugger`](struct.Debugger.html) showing them.
+#[cfg(debug_assertions)]
+fn init_with_debugger<WIDGET>(model_param: WIDGET::ModelParam)
+    -> Result<(Component<WIDGET>, Component<Debugger<WIDGET>>), ()>
+    where WIDGET: Widget + Record + 'static,
+          WIDGET::Model: Clone + Debug + 'static,
+          WIDGET::Msg: DisplayVariant + 'static,
+{
+    futures_glib::init();
+    gtk::init().map_err(|_| ())?;
+
+    let executor = create_executor();
+    let recorder = Recorder::new();
+    let (widget, component, relm) = create_widget::<WIDGET>(&executor, model_param);
+    connect_lifecycle(&widget);
+    let lifecycle = widget.lifecycle().clone();
+    init_shared_component_recorded::<WIDGET, _>(widget.stream(), component, &executor, &relm, &recorder,
+        move |widget| lifecycle.call_pending(widget));
+    let (debugger, component, relm) = create_widget::<Debugger<WIDGET>>(&executor, recorder);
+    init_widget::<Debugger<WIDGET>>(&debugger, component, &executor, &relm);
+    Ok((widget, debugger))
 }
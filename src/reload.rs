@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Reload of the static properties of the view files, i.e. `view!("examples/buttons.relm")`, in
+//! debug builds with the `hot-reload` feature.
+//! The widget tree and the style sheets are not reloaded.
+
+use gtk;
+
+/// Watch a view file and set the literal properties of its widgets when it changes.
+/// This does nothing in release builds or when the `hot-reload` feature is disabled.
+#[cfg(not(all(debug_assertions, feature = "hot-reload")))]
+#[doc(hidden)]
+pub fn watch_view_file(_path: &'static str, _content: &'static str,
+    _widgets: Vec<(Option<&'static str>, gtk::Widget)>)
+{
+}
+
+/// Watch a view file and set the literal properties of its widgets when it changes.
+/// This does nothing in release builds or when the `hot-reload` feature is disabled.
+#[cfg(all(debug_assertions, feature = "hot-reload"))]
+#[doc(hidden)]
+pub fn watch_view_file(path: &'static str, content: &'static str,
+    widgets: Vec<(Option<&'static str>, gtk::Widget)>)
+{
+    imp::watch_view_file(path, content, widgets)
+}
+
+#[cfg(all(debug_assertions, feature = "hot-reload"))]
+mod imp {
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::ffi::CStr;
+    use std::fs::File;
+    use std::io::Read;
+    use std::mem;
+    use std::ptr;
+    use std::rc::Rc;
+
+    use glib::translate::ToGlibPtr;
+    use glib_sys::{GError, GFALSE, g_error_free};
+    use gobject_sys::{
+        GObject,
+        GObjectClass,
+        GParamSpec,
+        GTypeInstance,
+        GValue,
+        g_object_class_find_property,
+        g_object_set_property,
+        g_value_unset,
+    };
+    use gtk::{self, Continue, WidgetExt};
+    use gtk_sys::{
+        GtkBuilder,
+        GtkContainer,
+        GtkWidget,
+        gtk_builder_value_from_string,
+        gtk_container_child_set_property,
+        gtk_container_class_find_child_property,
+    };
+    use relm_gen_widget::{StaticProperty, static_properties};
+
+    /// Interval between the checks of the view file, in milliseconds.
+    const INTERVAL: u32 = 500;
+
+    pub fn watch_view_file(path: &'static str, content: &'static str,
+        widgets: Vec<(Option<&'static str>, gtk::Widget)>)
+    {
+        let widgets: HashMap<_, _> = widgets.into_iter()
+            .map(|(name, widget)| (name.map(|name| name.to_string()), widget))
+            .collect();
+        let destroyed = Rc::new(Cell::new(false));
+        if let Some(root) = widgets.get(&None) {
+            let destroyed = destroyed.clone();
+            let _ = root.connect_destroy(move |_| destroyed.set(true));
+        }
+        let mut last_content = content.to_string();
+        check_view_file(path, &mut last_content, &widgets);
+        let _ = gtk::timeout_add(INTERVAL, move || {
+            if destroyed.get() {
+                return Continue(false);
+            }
+            check_view_file(path, &mut last_content, &widgets);
+            Continue(true)
+        });
+    }
+
+    fn check_view_file(path: &str, last_content: &mut String, widgets: &HashMap<Option<String>, gtk::Widget>) {
+        let mut content = String::new();
+        if File::open(path).and_then(|mut file| file.read_to_string(&mut content)).is_err() ||
+            content == *last_content
+        {
+            return;
+        }
+        let properties = static_properties(path, &content);
+        *last_content = content;
+        let properties =
+            match properties {
+                Ok(properties) => properties,
+                Err(error) => {
+                    warn!("Cannot reload the view file {}: {}", path, error);
+                    return;
+                },
+            };
+        for property in properties {
+            match widgets.get(&property.widget) {
+                Some(widget) => {
+                    if let Err(error) = set_property(widget, &property) {
+                        warn!("Cannot set the property {} from the view file {}: {}", property.name, path, error);
+                    }
+                },
+                None => warn!("Cannot find the widget {} from the view file {}: the widget tree is only updated \
+                              when recompiling", property.widget.as_ref().map(String::as_str).unwrap_or(""), path),
+            }
+        }
+        info!("Reloaded the view file {}", path);
+    }
+
+    fn set_property(widget: &gtk::Widget, property: &StaticProperty) -> Result<(), String> {
+        let parent =
+            if property.child {
+                Some(widget.get_parent().ok_or_else(|| "the widget has no parent".to_string())?)
+            }
+            else {
+                None
+            };
+        let object: *mut GtkWidget =
+            match parent {
+                Some(ref parent) => parent.to_glib_none().0,
+                None => widget.to_glib_none().0,
+            };
+        let builder = gtk::Builder::new();
+        let name = property.name.to_glib_none();
+        let value = property.value.to_glib_none();
+        unsafe {
+            let class = (*(object as *mut GTypeInstance)).g_class as *mut GObjectClass;
+            let pspec: *mut GParamSpec =
+                if property.child {
+                    gtk_container_class_find_child_property(class, name.0)
+                }
+                else {
+                    g_object_class_find_property(class, name.0)
+                };
+            if pspec.is_null() {
+                return Err("unknown property".to_string());
+            }
+            let mut gvalue: GValue = mem::zeroed();
+            let mut error: *mut GError = ptr::null_mut();
+            let builder: *mut GtkBuilder = builder.to_glib_none().0;
+            if gtk_builder_value_from_string(builder, pspec, value.0, &mut gvalue, &mut error) == GFALSE {
+                let message = CStr::from_ptr((*error).message).to_string_lossy().into_owned();
+                g_error_free(error);
+                return Err(message);
+            }
+            if property.child {
+                gtk_container_child_set_property(object as *mut GtkContainer, widget.to_glib_none().0, name.0,
+                                                 &gvalue);
+            }
+            else {
+                g_object_set_property(object as *mut GObject, name.0, &gvalue);
+            }
+            g_value_unset(&mut gvalue);
+        }
+        Ok(())
+    }
+}
@@ -21,7 +21,7 @@
 
 use gtk::{self, IsA, Object};
 
-use super::{Relm, run};
+use super::{ExitStatus, Relm, run};
 use relm_state::Update;
 
 /// Trait to implement to manage widget's events.
@@ -42,6 +42,38 @@ pub trait Widget
     fn on_add<W: IsA<gtk::Widget> + IsA<Object>>(&self, _parent: W) {
     }
 
+    /// Method called when the component is destroyed, i.e. when the last clone of its `Component` is
+    /// dropped or when its root widget is destroyed, whichever comes first.
+    /// This is the place to release the resources that must not outlive the widget.
+    fn on_destroy(&mut self) {
+    }
+
+    /// Method called once the view is created, with all its widgets and child components.
+    /// Unlike [`model()`](trait.Update.html#tymethod.model), this is the place to use the widgets,
+    /// e.g. to give the focus to a widget, to connect streams depending on them or to send the initial
+    /// messages to the child components.
+    ///
+    /// The child components are created by the view of their parent, hence this method is called on
+    /// every child, and on the children of this child, before it is called on their parent.
+    /// It is called before [`subscriptions()`](trait.Update.html#method.subscriptions).
+    /// The messages emitted on the stream of the widget are handled by `update()` afterwards, but the
+    /// parent is not yet connected to the events of a child when this method is called on this child.
+    fn on_init(&mut self, _relm: &Relm<Self>) {
+    }
+
+    /// Method called when the root widget is hidden from the screen (unmapped).
+    fn on_hide(&mut self) {
+    }
+
+    /// Method called when the widget is removed from its parent with
+    /// [`ContainerWidget::remove_widget()`](trait.ContainerWidget.html#tymethod.remove_widget).
+    fn on_remove<W: IsA<gtk::Widget> + IsA<Object>>(&mut self, _parent: W) {
+    }
+
+    /// Method called when the root widget is shown on the screen (mapped).
+    fn on_show(&mut self) {
+    }
+
     /// Get the parent ID.
     /// This is useful for custom Container implementation: when you implement the
     /// [`Container::add_widget()`](trait.Container.html#tymethod.add_widget), you might want to
@@ -57,7 +89,7 @@ pub trait Widget
     fn root(&self) -> Self::Root;
 
     /// Create the window from this widget and start the main loop.
-    fn run(model_param: Self::ModelParam) -> Result<(), ()>
+    fn run(model_param: Self::ModelParam) -> Result<ExitStatus, ()>
         where Self: 'static,
     {
         run::<Self>(model_param)
@@ -0,0 +1,203 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Management of the top-level windows opened by relm.
+
+use std::any::Any;
+use std::cell::RefCell;
+
+use glib::Cast;
+use gtk::{self, IsA, WidgetExt};
+
+use relm_state::{EventStream, quit};
+use super::{Component, DisplayVariant, Relm, create_widget, init_widget};
+use widget::Widget;
+
+thread_local! {
+    static WINDOWS: RefCell<Windows> = RefCell::new(Windows {
+        entries: vec![],
+        next_id: 0,
+        quit_on_last_window: false,
+    });
+}
+
+struct Entry {
+    // Keep the component alive as long as the window is open.
+    _component: Box<Any>,
+    id: usize,
+    stream: Box<Any>,
+    window: gtk::Window,
+}
+
+struct Windows {
+    entries: Vec<Entry>,
+    next_id: usize,
+    quit_on_last_window: bool,
+}
+
+/// Handle to a window opened with [`OpenWindow::open_window()`](trait.OpenWindow.html#tymethod.open_window).
+///
+/// Unlike a `Component`, dropping this handle does not close the window: the window lives until it
+/// is closed by the user or by [`close()`](#method.close).
+pub struct WindowHandle<WIDGET: Widget> {
+    id: usize,
+    stream: EventStream<WIDGET::Msg>,
+    window: gtk::Window,
+}
+
+impl<WIDGET: Widget> Clone for WindowHandle<WIDGET> {
+    fn clone(&self) -> Self {
+        WindowHandle {
+            id: self.id,
+            stream: self.stream.clone(),
+            window: self.window.clone(),
+        }
+    }
+}
+
+impl<WIDGET: Widget> WindowHandle<WIDGET> {
+    /// Close the window, which destroys its component.
+    pub fn close(&self) {
+        self.window.destroy();
+    }
+
+    /// Emit a message to the widget of the window.
+    pub fn emit(&self, msg: WIDGET::Msg) {
+        self.stream.emit(msg);
+    }
+
+    /// Get the unique identifier of the window.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Check if the window is still open.
+    pub fn is_open(&self) -> bool {
+        WINDOWS.with(|windows| windows.borrow().entries.iter().any(|entry| entry.id == self.id))
+    }
+
+    /// Get the GTK+ window.
+    pub fn window(&self) -> &gtk::Window {
+        &self.window
+    }
+}
+
+/// Extension trait for `Relm` to open independent top-level windows.
+pub trait OpenWindow {
+    /// Create the relm `Widget` with `model_param` and show its root window.
+    ///
+    /// The window owns its component: there is no need to store anything to keep receiving events,
+    /// and the component is destroyed when the window is closed.
+    fn open_window<WIDGET>(&self, model_param: WIDGET::ModelParam) -> WindowHandle<WIDGET>
+        where WIDGET: Widget + 'static,
+              WIDGET::Msg: DisplayVariant + 'static,
+              WIDGET::Root: IsA<gtk::Window>;
+}
+
+impl<PARENTWIDGET: Widget> OpenWindow for Relm<PARENTWIDGET> {
+    fn open_window<WIDGET>(&self, model_param: WIDGET::ModelParam) -> WindowHandle<WIDGET>
+        where WIDGET: Widget + 'static,
+              WIDGET::Msg: DisplayVariant + 'static,
+              WIDGET::Root: IsA<gtk::Window>,
+    {
+        let (widget, component, child_relm) = create_widget::<WIDGET>(self.executor(), model_param);
+        init_widget::<WIDGET>(&widget, component, self.executor(), &child_relm);
+        let handle = register_window(widget).expect("root of a window component");
+        handle.window.show();
+        handle
+    }
+}
+
+/// Get the windows opened by relm, in the order they were opened.
+pub fn open_windows() -> Vec<gtk::Window> {
+    WINDOWS.with(|windows| windows.borrow().entries.iter().map(|entry| entry.window.clone()).collect())
+}
+
+/// Quit the main loop when the last window opened by relm is closed.
+/// This is disabled by default.
+pub fn quit_on_last_window(enable: bool) {
+    WINDOWS.with(|windows| windows.borrow_mut().quit_on_last_window = enable);
+}
+
+/// Get the handles of the open windows whose widget is `WIDGET`.
+pub fn windows<WIDGET>() -> Vec<WindowHandle<WIDGET>>
+    where WIDGET: Widget + 'static,
+          WIDGET::Msg: 'static,
+{
+    WINDOWS.with(|windows| {
+        windows.borrow().entries.iter()
+            .filter_map(|entry| {
+                entry.stream.downcast_ref::<EventStream<WIDGET::Msg>>().map(|stream| WindowHandle {
+                    id: entry.id,
+                    stream: stream.clone(),
+                    window: entry.window.clone(),
+                })
+            })
+            .collect()
+    })
+}
+
+/// Give the ownership of `component` to the window manager if its root is a window.
+/// Return the component back otherwise.
+pub fn register_window<WIDGET>(component: Component<WIDGET>) -> Result<WindowHandle<WIDGET>, Component<WIDGET>>
+    where WIDGET: Widget + 'static,
+          WIDGET::Msg: 'static,
+{
+    let window: gtk::Window =
+        match component.widget().clone().upcast::<gtk::Widget>().downcast() {
+            Ok(window) => window,
+            Err(_) => return Err(component),
+        };
+    let stream = component.stream().clone();
+    let id = WINDOWS.with(|windows| {
+        let mut windows = windows.borrow_mut();
+        let id = windows.next_id;
+        windows.next_id += 1;
+        windows.entries.push(Entry {
+            _component: Box::new(component),
+            id,
+            stream: Box::new(stream.clone()),
+            window: window.clone(),
+        });
+        id
+    });
+    let _ = window.connect_destroy(move |_| unregister_window(id));
+    Ok(WindowHandle {
+        id,
+        stream,
+        window,
+    })
+}
+
+fn unregister_window(id: usize) {
+    let (entry, quit_now) = WINDOWS.with(|windows| {
+        let mut windows = windows.borrow_mut();
+        let entry = windows.entries.iter().position(|entry| entry.id == id)
+            .map(|index| windows.entries.remove(index));
+        let quit_now = entry.is_some() && windows.quit_on_last_window && windows.entries.is_empty();
+        (entry, quit_now)
+    });
+    // Drop the component outside of the borrow, since it can close other windows.
+    drop(entry);
+    if quit_now {
+        quit(0);
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate gtk;
+#[macro_use]
+extern crate relm;
+#[macro_use]
+extern crate relm_derive;
+
+use std::cell::RefCell;
+
+use gtk::{ContainerExt, IsA, Label, Object, WidgetExt, Window, WindowType};
+use gtk::Orientation::Vertical;
+use relm::{Component, ContainerWidget, EventStream, Relm, Update, Widget};
+
+thread_local! {
+    // The hooks called on the Hooks widgets, in order.
+    static HOOKS: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+}
+
+fn hook(name: &'static str) {
+    HOOKS.with(|hooks| hooks.borrow_mut().push(name));
+}
+
+fn take_hooks() -> Vec<&'static str> {
+    HOOKS.with(|hooks| hooks.borrow_mut().drain(..).collect())
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Noop,
+}
+
+pub struct Hooks {
+    label: Label,
+}
+
+impl Update for Hooks {
+    type Model = ();
+    type ModelParam = ();
+    type Msg = Msg;
+
+    fn model(_: &Relm<Self>, _: ()) -> () {
+    }
+
+    fn update(&mut self, _event: Msg) {
+    }
+}
+
+impl Widget for Hooks {
+    type Root = Label;
+
+    fn on_destroy(&mut self) {
+        hook("destroy");
+    }
+
+    fn on_hide(&mut self) {
+        hook("hide");
+    }
+
+    fn on_init(&mut self, _relm: &Relm<Self>) {
+        hook("init");
+    }
+
+    fn on_remove<W: IsA<gtk::Widget> + IsA<Object>>(&mut self, _parent: W) {
+        hook("remove");
+    }
+
+    fn on_show(&mut self) {
+        hook("show");
+    }
+
+    fn root(&self) -> Self::Root {
+        self.label.clone()
+    }
+
+    fn view(_relm: &Relm<Self>, _model: ()) -> Self {
+        Hooks {
+            label: Label::new("hooks"),
+        }
+    }
+}
+
+// A widget containing a Hooks child.
+pub struct Parent {
+    _child: Component<Hooks>,
+    vbox: gtk::Box,
+}
+
+impl Update for Parent {
+    type Model = ();
+    type ModelParam = ();
+    type Msg = Msg;
+
+    fn model(_: &Relm<Self>, _: ()) -> () {
+    }
+
+    fn update(&mut self, _event: Msg) {
+    }
+}
+
+impl Widget for Parent {
+    type Root = gtk::Box;
+
+    fn on_init(&mut self, _relm: &Relm<Self>) {
+        hook("parent init");
+    }
+
+    fn root(&self) -> Self::Root {
+        self.vbox.clone()
+    }
+
+    fn view(relm: &Relm<Self>, _model: ()) -> Self {
+        let vbox = gtk::Box::new(Vertical, 0);
+        let child = vbox.add_widget::<Hooks, Parent>(relm, ());
+        Parent {
+            _child: child,
+            vbox,
+        }
+    }
+}
+
+// GTK+ can only be used from a single thread, so all the checks are done in the same test.
+#[test]
+fn lifecycle() {
+    let _parent = init_order();
+    hook_order();
+}
+
+fn init_order() -> Component<Parent> {
+    let parent = relm::init_test::<Parent>(()).unwrap();
+    // The children are created by the view of their parent, so they are initialized first.
+    assert_eq!(take_hooks(), vec!["init", "parent init"]);
+    parent
+}
+
+fn hook_order() {
+    let relm = Relm::<Hooks>::new(relm::create_executor(), EventStream::new());
+    let window = Window::new(WindowType::Toplevel);
+    let container = gtk::Box::new(Vertical, 0);
+    window.add(&container);
+    window.show_all();
+
+    let component = container.add_widget::<Hooks, Hooks>(&relm, ());
+    assert_eq!(take_hooks(), vec!["init"]);
+    component.widget().show();
+    component.widget().hide();
+    assert_eq!(take_hooks(), vec!["show", "hide"]);
+
+    // The widget is only destroyed when the last clone of its component is dropped.
+    container.remove_widget(component.clone());
+    assert_eq!(take_hooks(), vec!["remove"]);
+    drop(component);
+    assert_eq!(take_hooks(), vec!["destroy"]);
+    window.destroy();
+}
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2017 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate gtk;
+#[macro_use]
+extern crate relm;
+#[macro_use]
+extern crate relm_derive;
+extern crate relm_test;
+
+use std::cell::Cell;
+
+use gtk::{Continue, Window, WindowType};
+use relm::{Component, OpenWindow, Relm, Update, Widget, WindowHandle};
+
+thread_local! {
+    // Number of Child widgets dropped.
+    static DROPPED: Cell<usize> = Cell::new(0);
+}
+
+#[derive(Msg)]
+pub enum ChildMsg {
+    Noop,
+}
+
+pub struct Child {
+    window: Window,
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        DROPPED.with(|dropped| dropped.set(dropped.get() + 1));
+    }
+}
+
+impl Update for Child {
+    type Model = ();
+    type ModelParam = ();
+    type Msg = ChildMsg;
+
+    fn model(_: &Relm<Self>, _: ()) -> () {
+    }
+
+    fn update(&mut self, _event: ChildMsg) {
+    }
+}
+
+impl Widget for Child {
+    type Root = Window;
+
+    fn root(&self) -> Self::Root {
+        self.window.clone()
+    }
+
+    fn view(_relm: &Relm<Self>, _model: ()) -> Self {
+        Child {
+            window: Window::new(WindowType::Toplevel),
+        }
+    }
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Open,
+}
+
+pub struct Win {
+    relm: Relm<Win>,
+    window: Window,
+}
+
+impl Update for Win {
+    type Model = ();
+    type ModelParam = ();
+    type Msg = Msg;
+
+    fn model(_: &Relm<Self>, _: ()) -> () {
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Msg::Open => {
+                let _ = self.relm.open_window::<Child>(());
+            },
+        }
+    }
+}
+
+impl Widget for Win {
+    type Root = Window;
+
+    fn root(&self) -> Self::Root {
+        self.window.clone()
+    }
+
+    fn view(relm: &Relm<Self>, _model: ()) -> Self {
+        Win {
+            relm: relm.clone(),
+            window: Window::new(WindowType::Toplevel),
+        }
+    }
+}
+
+fn dropped() -> usize {
+    DROPPED.with(Cell::get)
+}
+
+fn open(component: &Component<Win>) -> WindowHandle<Child> {
+    component.emit(Msg::Open);
+    relm_test::run_loop();
+    relm::windows::<Child>().pop().expect("opened window")
+}
+
+// GTK+ can only be used from a single thread, so all the checks are done in the same test.
+#[test]
+fn windows() {
+    let component = relm::init_test::<Win>(()).unwrap();
+    open_and_close(&component);
+    quit_on_last_window(&component);
+}
+
+fn open_and_close(component: &Component<Win>) {
+    let handle = open(component);
+    assert!(handle.is_open());
+    assert_eq!(relm::open_windows().len(), 1);
+    assert_eq!(relm::windows::<Child>().len(), 1);
+    assert_eq!(relm::windows::<Win>().len(), 0);
+
+    // Closing the window unregisters it and drops its component.
+    handle.close();
+    relm_test::run_loop();
+    assert!(!handle.is_open());
+    assert!(relm::open_windows().is_empty());
+    assert!(relm::windows::<Child>().is_empty());
+    assert_eq!(dropped(), 1);
+}
+
+fn quit_on_last_window(component: &Component<Win>) {
+    // Disabled by default: closing the last window does not stop the main loop.
+    let handle = open(component);
+    let _ = gtk::idle_add(move || {
+        handle.close();
+        Continue(false)
+    });
+    let _ = gtk::timeout_add(100, || {
+        relm::quit(42);
+        Continue(false)
+    });
+    assert_eq!(relm::run_main_loop().code(), 42);
+    assert!(relm::open_windows().is_empty());
+
+    // When enabled, the main loop stops with 0 once the last window is closed, not before.
+    relm::quit_on_last_window(true);
+    let first = open(component);
+    let second = open(component);
+    let _ = gtk::idle_add(move || {
+        first.close();
+        Continue(false)
+    });
+    let _ = gtk::timeout_add(100, move || {
+        second.close();
+        Continue(false)
+    });
+    assert_eq!(relm::run_main_loop().code(), 0);
+    assert!(relm::open_windows().is_empty());
+    relm_test::run_loop();
+    assert_eq!(dropped(), 4);
+    relm::quit_on_last_window(false);
+}